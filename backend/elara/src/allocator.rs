@@ -0,0 +1,132 @@
+//! Modul Allocator ELARA
+//!
+//! Pembagian bandwidth estimasi satu sesi antara audio, video dan data
+//! channel. Audio dijamin lebih dulu, data (chat, gift) mendapat porsi
+//! kecil yang dicadangkan, dan video memakai sisanya.
+
+use napi_derive::napi;
+
+use crate::opus::OPUS_MIN_BITRATE;
+
+/// Porsi sisa bandwidth (setelah audio) yang dicadangkan untuk data
+const DATA_SHARE: f64 = 0.05;
+
+/// Cadangan data minimum (kbps), cukup untuk chat dan notifikasi gift
+const DATA_MIN_KBPS: u32 = 16;
+
+/// Cadangan data maksimum (kbps)
+const DATA_MAX_KBPS: u32 = 128;
+
+/// Hasil pembagian bitrate satu sesi
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BitrateAllocation {
+    /// Bandwidth estimasi yang dibagi (kbps)
+    pub total_kbps: u32,
+    /// Bitrate audio (kbps)
+    pub audio_kbps: u32,
+    /// Bitrate video termasuk overhead FEC (kbps), 0 berarti audio only
+    pub video_kbps: u32,
+    /// Cadangan data channel (kbps)
+    pub data_kbps: u32,
+}
+
+/// Pembagi bitrate per sesi
+#[derive(Debug, Clone)]
+pub struct BitrateAllocator {
+    audio_target_kbps: u32,
+    max_video_kbps: u32,
+    last: Option<BitrateAllocation>,
+}
+
+impl BitrateAllocator {
+    /// Buat allocator dengan target audio dan batas video (0 = tanpa batas)
+    pub fn new(audio_target_kbps: u32, max_video_kbps: u32) -> Self {
+        Self {
+            audio_target_kbps,
+            max_video_kbps,
+            last: None,
+        }
+    }
+
+    /// Ubah target bitrate audio (kbps)
+    pub fn set_audio_target(&mut self, kbps: u32) {
+        self.audio_target_kbps = kbps;
+    }
+
+    /// Ubah batas bitrate video (kbps), 0 berarti tanpa batas
+    pub fn set_max_video(&mut self, kbps: u32) {
+        self.max_video_kbps = kbps;
+    }
+
+    /// Pembagian terakhir
+    pub fn last(&self) -> Option<&BitrateAllocation> {
+        self.last.as_ref()
+    }
+
+    /// Bagi bandwidth estimasi (kbps); dipanggil tiap estimasi berubah
+    ///
+    /// Audio mendapat targetnya selama bandwidth cukup dan tidak pernah
+    /// di bawah bitrate minimum Opus. Data dicadangkan dari sisa, lalu
+    /// video memakai semua yang tersisa sampai batasnya.
+    pub fn allocate(&mut self, estimate_kbps: u32) -> BitrateAllocation {
+        let audio = self.audio_target_kbps.min(estimate_kbps).max(OPUS_MIN_BITRATE);
+        let remaining = estimate_kbps.saturating_sub(audio);
+
+        let data = ((remaining as f64 * DATA_SHARE) as u32)
+            .clamp(DATA_MIN_KBPS, DATA_MAX_KBPS)
+            .min(remaining);
+
+        let mut video = remaining - data;
+        if self.max_video_kbps > 0 {
+            video = video.min(self.max_video_kbps);
+        }
+
+        let allocation = BitrateAllocation {
+            total_kbps: estimate_kbps,
+            audio_kbps: audio,
+            video_kbps: video,
+            data_kbps: data,
+        };
+        self.last = Some(allocation.clone());
+        allocation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audio_first_then_data_then_video() {
+        let mut allocator = BitrateAllocator::new(64, 0);
+        let allocation = allocator.allocate(2064);
+        assert_eq!(allocation.audio_kbps, 64);
+        assert_eq!(allocation.data_kbps, 100);
+        assert_eq!(allocation.video_kbps, 1900);
+        assert_eq!(allocator.last(), Some(&allocation));
+    }
+
+    #[test]
+    fn low_bandwidth_keeps_audio() {
+        let mut allocator = BitrateAllocator::new(64, 0);
+        let allocation = allocator.allocate(70);
+        assert_eq!(allocation.audio_kbps, 64);
+        assert_eq!(allocation.data_kbps, 6);
+        assert_eq!(allocation.video_kbps, 0);
+
+        let allocation = allocator.allocate(40);
+        assert_eq!((allocation.audio_kbps, allocation.data_kbps, allocation.video_kbps), (40, 0, 0));
+
+        // Audio tidak pernah di bawah minimum Opus
+        assert_eq!(allocator.allocate(0).audio_kbps, OPUS_MIN_BITRATE);
+    }
+
+    #[test]
+    fn video_respects_node_limit() {
+        let mut allocator = BitrateAllocator::new(64, 1000);
+        let allocation = allocator.allocate(5000);
+        assert_eq!(allocation.video_kbps, 1000);
+        assert_eq!(allocation.data_kbps, DATA_MAX_KBPS);
+    }
+}
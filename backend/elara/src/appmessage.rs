@@ -0,0 +1,460 @@
+//! Modul Pesan Aplikasi ELARA
+//!
+//! Pesan in-call bertipe (chat, reaksi, gift, typing, kamera mati, skip)
+//! dengan skema yang dicek di kedua sisi. Format binary berversi:
+//! `[versi][tipe][field...]`, tiap field `[tag][panjang u16][nilai]`.
+//! Tipe dan field yang belum dikenal tidak dianggap error, agar client
+//! lama tetap jalan saat protokol bertambah.
+//!
+//! TODO(elara-wire): encoding harus memakai format wire ELARA. Source
+//! `elara-wire` belum bisa diambil di environment build ini dan belum ada
+//! spesifikasinya di repo, jadi format di atas masih sementara; jangan
+//! dipakai client di luar crate ini dulu. Semua encoding ada di
+//! `AppPayload::encode`/`decode` (dan `Fields`/`put_field`), sehingga
+//! penggantiannya tidak mengubah `AppPayload`, `AppMessage` maupun API JS.
+
+use napi_derive::napi;
+use thiserror::Error;
+
+/// Data channel untuk pesan aplikasi
+pub const APP_CHANNEL: &str = "app";
+
+/// Versi protokol pesan aplikasi
+pub const APP_PROTOCOL_VERSION: u8 = 1;
+
+/// Katalog gift (ID, harga koin), sama dengan `GiftPanel.tsx`
+pub const GIFT_CATALOG: [(&str, u32); 6] = [
+    ("wave", 5),
+    ("heart", 10),
+    ("star", 25),
+    ("gift", 50),
+    ("diamond", 100),
+    ("rocket", 500),
+];
+
+/// Panjang maksimum teks chat (bytes)
+pub const MAX_CHAT_LEN: usize = 1000;
+
+const MAX_EMOJI_LEN: usize = 32;
+const MAX_RECEIPT_ID_LEN: usize = 128;
+const MAX_RECEIPT_LEN: usize = 2048;
+
+const TYPE_CHAT: u8 = 1;
+const TYPE_REACTION: u8 = 2;
+const TYPE_GIFT_SENT: u8 = 3;
+const TYPE_TYPING: u8 = 4;
+const TYPE_CAMERA_OFF: u8 = 5;
+const TYPE_SKIP: u8 = 6;
+
+/// Error pesan aplikasi
+#[derive(Debug, Error, PartialEq)]
+pub enum AppMessageError {
+    /// Versi protokol tidak didukung
+    #[error("versi protokol pesan tidak didukung: {0}")]
+    UnsupportedVersion(u8),
+    /// Frame terpotong atau field rusak
+    #[error("format pesan tidak valid")]
+    Malformed,
+    /// Field wajib tidak ada
+    #[error("field pesan wajib tidak ada: {0}")]
+    MissingField(&'static str),
+    /// Isi pesan melanggar skema
+    #[error("pesan tidak valid: {0}")]
+    Invalid(String),
+}
+
+impl From<AppMessageError> for napi::Error {
+    fn from(err: AppMessageError) -> Self {
+        napi::Error::new(napi::Status::InvalidArg, err.to_string())
+    }
+}
+
+/// Jenis pesan aplikasi
+#[napi(string_enum = "kebab-case")]
+#[derive(Debug, PartialEq)]
+pub enum AppMessageKind {
+    /// Pesan chat
+    Chat,
+    /// Reaksi emoji
+    Reaction,
+    /// Gift terkirim
+    GiftSent,
+    /// Indikator mengetik
+    Typing,
+    /// Kamera dimatikan/dinyalakan
+    CameraOff,
+    /// Peer akan skip ke match berikutnya
+    Skip,
+    /// Tipe dari versi protokol yang lebih baru, abaikan saja
+    Unknown,
+}
+
+/// Pesan aplikasi untuk JS; field terisi sesuai `kind`
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppMessage {
+    /// Jenis pesan
+    pub kind: AppMessageKind,
+    /// Teks chat (`Chat`)
+    pub text: Option<String>,
+    /// Emoji reaksi (`Reaction`)
+    pub emoji: Option<String>,
+    /// ID gift dari katalog (`GiftSent`)
+    pub gift_id: Option<String>,
+    /// Jumlah koin (`GiftSent`)
+    pub amount: Option<u32>,
+    /// ID receipt transaksi gift dari backend (`GiftSent`)
+    pub receipt_id: Option<String>,
+    /// Receipt gift bertanda tangan dari backend (`GiftSent`)
+    pub receipt: Option<String>,
+    /// `true` saat mulai mengetik/kamera mati (`Typing`, `CameraOff`)
+    pub active: Option<bool>,
+    /// Kode tipe mentah (`Unknown`)
+    pub type_id: Option<u32>,
+}
+
+impl AppMessage {
+    fn empty(kind: AppMessageKind) -> Self {
+        Self {
+            kind,
+            text: None,
+            emoji: None,
+            gift_id: None,
+            amount: None,
+            receipt_id: None,
+            receipt: None,
+            active: None,
+            type_id: None,
+        }
+    }
+}
+
+/// Pesan aplikasi bertipe
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppPayload {
+    Chat { text: String },
+    Reaction { emoji: String },
+    GiftSent {
+        gift_id: String,
+        amount: u32,
+        receipt_id: String,
+        receipt: String,
+    },
+    Typing { active: bool },
+    CameraOff { active: bool },
+    Skip,
+    /// Tipe yang belum dikenal versi ini
+    Unknown { type_id: u8 },
+}
+
+/// Harga koin gift dari katalog
+pub fn gift_price(gift_id: &str) -> Option<u32> {
+    GIFT_CATALOG
+        .iter()
+        .find(|(id, _)| *id == gift_id)
+        .map(|(_, price)| *price)
+}
+
+/// Pembaca field `[tag][panjang u16][nilai]`
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn get(&self, tag: u8) -> Result<Option<&'a [u8]>, AppMessageError> {
+        let mut rest = self.0;
+        while !rest.is_empty() {
+            let header = rest.get(..3).ok_or(AppMessageError::Malformed)?;
+            let len = u16::from_be_bytes([header[1], header[2]]) as usize;
+            let value = rest.get(3..3 + len).ok_or(AppMessageError::Malformed)?;
+            if header[0] == tag {
+                return Ok(Some(value));
+            }
+            rest = &rest[3 + len..];
+        }
+        Ok(None)
+    }
+
+    fn string(&self, tag: u8, name: &'static str) -> Result<String, AppMessageError> {
+        let value = self.get(tag)?.ok_or(AppMessageError::MissingField(name))?;
+        String::from_utf8(value.to_vec()).map_err(|_| AppMessageError::Malformed)
+    }
+
+    fn u32(&self, tag: u8, name: &'static str) -> Result<u32, AppMessageError> {
+        let value = self.get(tag)?.ok_or(AppMessageError::MissingField(name))?;
+        Ok(u32::from_be_bytes(value.try_into().map_err(|_| AppMessageError::Malformed)?))
+    }
+
+    fn bool(&self, tag: u8, name: &'static str) -> Result<bool, AppMessageError> {
+        match self.get(tag)?.ok_or(AppMessageError::MissingField(name))? {
+            [value] => Ok(*value != 0),
+            _ => Err(AppMessageError::Malformed),
+        }
+    }
+}
+
+fn put_field(buf: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    buf.push(tag);
+    buf.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buf.extend_from_slice(value);
+}
+
+impl AppPayload {
+    /// Jenis pesan
+    pub fn kind(&self) -> AppMessageKind {
+        match self {
+            AppPayload::Chat { .. } => AppMessageKind::Chat,
+            AppPayload::Reaction { .. } => AppMessageKind::Reaction,
+            AppPayload::GiftSent { .. } => AppMessageKind::GiftSent,
+            AppPayload::Typing { .. } => AppMessageKind::Typing,
+            AppPayload::CameraOff { .. } => AppMessageKind::CameraOff,
+            AppPayload::Skip => AppMessageKind::Skip,
+            AppPayload::Unknown { .. } => AppMessageKind::Unknown,
+        }
+    }
+
+    /// Cek isi pesan terhadap skema
+    pub fn validate(&self) -> Result<(), AppMessageError> {
+        match self {
+            AppPayload::Chat { text } => {
+                if text.trim().is_empty() {
+                    return Err(AppMessageError::Invalid("teks chat kosong".to_string()));
+                }
+                if text.len() > MAX_CHAT_LEN {
+                    return Err(AppMessageError::Invalid(format!(
+                        "teks chat maksimum {} byte",
+                        MAX_CHAT_LEN
+                    )));
+                }
+            }
+            AppPayload::Reaction { emoji } => {
+                if emoji.is_empty() || emoji.len() > MAX_EMOJI_LEN {
+                    return Err(AppMessageError::Invalid("emoji reaksi tidak valid".to_string()));
+                }
+            }
+            AppPayload::GiftSent { gift_id, amount, receipt_id, receipt } => {
+                let price = gift_price(gift_id).ok_or_else(|| {
+                    AppMessageError::Invalid(format!("gift tidak dikenal: {}", gift_id))
+                })?;
+                // Boleh kombo beberapa gift sekaligus
+                if *amount == 0 || amount % price != 0 {
+                    return Err(AppMessageError::Invalid(format!(
+                        "jumlah koin {} tidak sesuai harga {} ({})",
+                        amount, gift_id, price
+                    )));
+                }
+                if receipt_id.is_empty()
+                    || receipt_id.len() > MAX_RECEIPT_ID_LEN
+                    || receipt.is_empty()
+                    || receipt.len() > MAX_RECEIPT_LEN
+                {
+                    return Err(AppMessageError::Invalid("receipt gift tidak valid".to_string()));
+                }
+            }
+            AppPayload::Typing { .. } | AppPayload::CameraOff { .. } | AppPayload::Skip => {}
+            AppPayload::Unknown { .. } => {
+                return Err(AppMessageError::Invalid("tipe pesan tidak dikenal".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode ke frame binary, gagal jika melanggar skema
+    pub fn encode(&self) -> Result<Vec<u8>, AppMessageError> {
+        self.validate()?;
+
+        let mut buf = vec![APP_PROTOCOL_VERSION];
+        match self {
+            AppPayload::Chat { text } => {
+                buf.push(TYPE_CHAT);
+                put_field(&mut buf, 1, text.as_bytes());
+            }
+            AppPayload::Reaction { emoji } => {
+                buf.push(TYPE_REACTION);
+                put_field(&mut buf, 1, emoji.as_bytes());
+            }
+            AppPayload::GiftSent { gift_id, amount, receipt_id, receipt } => {
+                buf.push(TYPE_GIFT_SENT);
+                put_field(&mut buf, 1, gift_id.as_bytes());
+                put_field(&mut buf, 2, &amount.to_be_bytes());
+                put_field(&mut buf, 3, receipt_id.as_bytes());
+                put_field(&mut buf, 4, receipt.as_bytes());
+            }
+            AppPayload::Typing { active } => {
+                buf.push(TYPE_TYPING);
+                put_field(&mut buf, 1, &[*active as u8]);
+            }
+            AppPayload::CameraOff { active } => {
+                buf.push(TYPE_CAMERA_OFF);
+                put_field(&mut buf, 1, &[*active as u8]);
+            }
+            AppPayload::Skip => buf.push(TYPE_SKIP),
+            AppPayload::Unknown { .. } => unreachable!("ditolak validate"),
+        }
+        Ok(buf)
+    }
+
+    /// Decode frame dari peer dan cek skemanya
+    ///
+    /// Tipe yang belum dikenal menjadi `Unknown`, bukan error.
+    pub fn decode(data: &[u8]) -> Result<Self, AppMessageError> {
+        let (&version, rest) = data.split_first().ok_or(AppMessageError::Malformed)?;
+        if version == 0 {
+            return Err(AppMessageError::UnsupportedVersion(version));
+        }
+        let (&type_id, fields) = rest.split_first().ok_or(AppMessageError::Malformed)?;
+        let fields = Fields(fields);
+
+        let payload = match type_id {
+            TYPE_CHAT => AppPayload::Chat { text: fields.string(1, "text")? },
+            TYPE_REACTION => AppPayload::Reaction { emoji: fields.string(1, "emoji")? },
+            TYPE_GIFT_SENT => AppPayload::GiftSent {
+                gift_id: fields.string(1, "giftId")?,
+                amount: fields.u32(2, "amount")?,
+                receipt_id: fields.string(3, "receiptId")?,
+                receipt: fields.string(4, "receipt")?,
+            },
+            TYPE_TYPING => AppPayload::Typing { active: fields.bool(1, "active")? },
+            TYPE_CAMERA_OFF => AppPayload::CameraOff { active: fields.bool(1, "active")? },
+            TYPE_SKIP => AppPayload::Skip,
+            _ => return Ok(AppPayload::Unknown { type_id }),
+        };
+        payload.validate()?;
+        Ok(payload)
+    }
+}
+
+impl TryFrom<AppMessage> for AppPayload {
+    type Error = AppMessageError;
+
+    fn try_from(message: AppMessage) -> Result<Self, Self::Error> {
+        let payload = match message.kind {
+            AppMessageKind::Chat => AppPayload::Chat {
+                text: message.text.ok_or(AppMessageError::MissingField("text"))?,
+            },
+            AppMessageKind::Reaction => AppPayload::Reaction {
+                emoji: message.emoji.ok_or(AppMessageError::MissingField("emoji"))?,
+            },
+            AppMessageKind::GiftSent => AppPayload::GiftSent {
+                gift_id: message.gift_id.ok_or(AppMessageError::MissingField("giftId"))?,
+                amount: message.amount.ok_or(AppMessageError::MissingField("amount"))?,
+                receipt_id: message.receipt_id.ok_or(AppMessageError::MissingField("receiptId"))?,
+                receipt: message.receipt.ok_or(AppMessageError::MissingField("receipt"))?,
+            },
+            AppMessageKind::Typing => AppPayload::Typing {
+                active: message.active.ok_or(AppMessageError::MissingField("active"))?,
+            },
+            AppMessageKind::CameraOff => AppPayload::CameraOff {
+                active: message.active.ok_or(AppMessageError::MissingField("active"))?,
+            },
+            AppMessageKind::Skip => AppPayload::Skip,
+            AppMessageKind::Unknown => {
+                return Err(AppMessageError::Invalid("tipe pesan tidak dikenal".to_string()))
+            }
+        };
+        payload.validate()?;
+        Ok(payload)
+    }
+}
+
+impl From<AppPayload> for AppMessage {
+    fn from(payload: AppPayload) -> Self {
+        let mut message = AppMessage::empty(payload.kind());
+        match payload {
+            AppPayload::Chat { text } => message.text = Some(text),
+            AppPayload::Reaction { emoji } => message.emoji = Some(emoji),
+            AppPayload::GiftSent { gift_id, amount, receipt_id, receipt } => {
+                message.gift_id = Some(gift_id);
+                message.amount = Some(amount);
+                message.receipt_id = Some(receipt_id);
+                message.receipt = Some(receipt);
+            }
+            AppPayload::Typing { active } | AppPayload::CameraOff { active } => {
+                message.active = Some(active)
+            }
+            AppPayload::Skip => {}
+            AppPayload::Unknown { type_id } => message.type_id = Some(type_id as u32),
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gift(amount: u32) -> AppPayload {
+        AppPayload::GiftSent {
+            gift_id: "rocket".to_string(),
+            amount,
+            receipt_id: "tx-42".to_string(),
+            receipt: "eyJ0eXBlIjoiZ2lmdC1yZWNlaXB0In0.00".to_string(),
+        }
+    }
+
+    #[test]
+    fn roundtrip_all_types() {
+        let payloads = [
+            AppPayload::Chat { text: "halo dari Bandung".to_string() },
+            AppPayload::Reaction { emoji: "❤️".to_string() },
+            gift(1000),
+            AppPayload::Typing { active: true },
+            AppPayload::CameraOff { active: false },
+            AppPayload::Skip,
+        ];
+        for payload in payloads {
+            let encoded = payload.encode().unwrap();
+            assert_eq!(encoded[0], APP_PROTOCOL_VERSION);
+            assert_eq!(AppPayload::decode(&encoded).unwrap(), payload);
+
+            let message = AppMessage::from(payload.clone());
+            assert_eq!(AppPayload::try_from(message).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn schema_is_enforced() {
+        assert!(matches!(
+            AppPayload::Chat { text: " ".to_string() }.encode(),
+            Err(AppMessageError::Invalid(_))
+        ));
+        assert!(matches!(gift(499).encode(), Err(AppMessageError::Invalid(_))));
+        assert!(matches!(
+            AppPayload::GiftSent {
+                gift_id: "yacht".to_string(),
+                amount: 5,
+                receipt_id: "tx".to_string(),
+                receipt: "r".to_string(),
+            }
+            .encode(),
+            Err(AppMessageError::Invalid(_))
+        ));
+        assert_eq!(
+            AppPayload::try_from(AppMessage::empty(AppMessageKind::GiftSent)),
+            Err(AppMessageError::MissingField("giftId"))
+        );
+
+        // Peer yang mengirim frame melanggar skema juga ditolak saat decode
+        let mut frame = vec![APP_PROTOCOL_VERSION, TYPE_GIFT_SENT];
+        put_field(&mut frame, 1, b"wave");
+        put_field(&mut frame, 2, &7u32.to_be_bytes());
+        put_field(&mut frame, 3, b"tx");
+        put_field(&mut frame, 4, b"r");
+        assert!(matches!(AppPayload::decode(&frame), Err(AppMessageError::Invalid(_))));
+        assert_eq!(AppPayload::decode(&frame[..5]), Err(AppMessageError::Malformed));
+        assert_eq!(AppPayload::decode(&[0, TYPE_SKIP]), Err(AppMessageError::UnsupportedVersion(0)));
+    }
+
+    #[test]
+    fn newer_messages_are_tolerated() {
+        // Tipe baru dari versi berikutnya
+        assert_eq!(AppPayload::decode(&[2, 99, 1, 0, 0]).unwrap(), AppPayload::Unknown { type_id: 99 });
+
+        // Field baru pada tipe lama dilewati
+        let mut frame = AppPayload::Chat { text: "hai".to_string() }.encode().unwrap();
+        put_field(&mut frame, 9, b"metadata baru");
+        frame[0] = 2;
+        assert_eq!(
+            AppPayload::decode(&frame).unwrap(),
+            AppPayload::Chat { text: "hai".to_string() }
+        );
+    }
+}
@@ -0,0 +1,570 @@
+//! Modul Codec ELARA
+//!
+//! Deskriptor codec bertipe dan negosiasi kemampuan codec antar peer.
+//! Setiap device mengumumkan codec yang didukungnya (urut preferensi),
+//! lalu kedua peer memilih codec terbaik yang didukung bersama.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+use crate::{AUDIO_RED_PAYLOAD_TYPE, PADDING_PAYLOAD_TYPE, VIDEO_FEC_PAYLOAD_TYPE};
+
+/// Payload type yang dipakai internal sehingga tidak boleh dipakai codec
+const RESERVED_PAYLOAD_TYPES: &[u8] = &[
+    PADDING_PAYLOAD_TYPE,
+    VIDEO_FEC_PAYLOAD_TYPE,
+    AUDIO_RED_PAYLOAD_TYPE,
+];
+
+/// Codec video
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoCodec {
+    /// H.264 / AVC
+    H264,
+    /// H.265 / HEVC
+    H265,
+    /// VP8
+    Vp8,
+    /// VP9
+    Vp9,
+    /// AV1
+    Av1,
+}
+
+impl VideoCodec {
+    /// Parse nama codec (case-insensitive, menerima alias avc/hevc)
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "h264" | "avc" => Ok(VideoCodec::H264),
+            "h265" | "hevc" => Ok(VideoCodec::H265),
+            "vp8" => Ok(VideoCodec::Vp8),
+            "vp9" => Ok(VideoCodec::Vp9),
+            "av1" => Ok(VideoCodec::Av1),
+            other => Err(Error::new(
+                Status::InvalidArg,
+                format!("Codec video tidak dikenal: {}", other),
+            )),
+        }
+    }
+
+    /// Nama codec
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "h264",
+            VideoCodec::H265 => "h265",
+            VideoCodec::Vp8 => "vp8",
+            VideoCodec::Vp9 => "vp9",
+            VideoCodec::Av1 => "av1",
+        }
+    }
+
+    /// Clock rate RTP (Hz)
+    pub fn clock_rate(&self) -> u32 {
+        90_000
+    }
+}
+
+/// Codec audio
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioCodec {
+    /// Opus
+    Opus,
+    /// AAC
+    Aac,
+}
+
+impl AudioCodec {
+    /// Parse nama codec (case-insensitive)
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "opus" => Ok(AudioCodec::Opus),
+            "aac" => Ok(AudioCodec::Aac),
+            other => Err(Error::new(
+                Status::InvalidArg,
+                format!("Codec audio tidak dikenal: {}", other),
+            )),
+        }
+    }
+
+    /// Nama codec
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioCodec::Opus => "opus",
+            AudioCodec::Aac => "aac",
+        }
+    }
+}
+
+/// Kemampuan codec video satu peer
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoCodecCapability {
+    /// Codec
+    pub codec: VideoCodec,
+    /// Payload type RTP (96-127, kecuali yang dicadangkan)
+    pub payload_type: u32,
+    /// Profile codec: profile-level-id untuk H.264 (hex, mis. "42e01f"),
+    /// profile-id untuk VP9/AV1 (mis. "0")
+    pub profile: Option<String>,
+    /// Encoder/decoder hardware tersedia
+    pub hardware_accelerated: bool,
+}
+
+/// Kemampuan codec audio satu peer
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioCodecCapability {
+    /// Codec
+    pub codec: AudioCodec,
+    /// Payload type RTP (96-127, kecuali yang dicadangkan)
+    pub payload_type: u32,
+    /// Clock rate (Hz)
+    pub clock_rate: u32,
+    /// Channels (1=mono, 2=stereo)
+    pub channels: u32,
+    /// In-band FEC didukung
+    pub fec: bool,
+    /// Discontinuous transmission didukung
+    pub dtx: bool,
+}
+
+/// Himpunan kemampuan codec, urut dari yang paling disukai
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodecCapabilities {
+    /// Codec video (kosong jika video dimatikan)
+    pub video: Vec<VideoCodecCapability>,
+    /// Codec audio (kosong jika audio dimatikan)
+    pub audio: Vec<AudioCodecCapability>,
+}
+
+impl Default for CodecCapabilities {
+    fn default() -> Self {
+        // H.264 constrained baseline dan VP8 didukung hampir semua device,
+        // jadi keduanya selalu tersedia sebagai fallback
+        Self {
+            video: vec![
+                VideoCodecCapability {
+                    codec: VideoCodec::H264,
+                    payload_type: 102,
+                    profile: Some("42e01f".to_string()),
+                    hardware_accelerated: true,
+                },
+                VideoCodecCapability {
+                    codec: VideoCodec::Vp8,
+                    payload_type: 96,
+                    profile: None,
+                    hardware_accelerated: false,
+                },
+            ],
+            audio: vec![AudioCodecCapability {
+                codec: AudioCodec::Opus,
+                payload_type: 111,
+                clock_rate: 48000,
+                channels: 2,
+                fec: true,
+                dtx: true,
+            }],
+        }
+    }
+}
+
+/// Hasil negosiasi codec
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NegotiatedCodecs {
+    /// Codec video terpilih (`None` jika salah satu peer tanpa video)
+    pub video: Option<VideoCodecCapability>,
+    /// Codec audio terpilih (`None` jika salah satu peer tanpa audio)
+    pub audio: Option<AudioCodecCapability>,
+}
+
+/// Cek payload type boleh dipakai codec: dinamis (96-127) dan tidak
+/// bentrok dengan payload type internal (padding pacer, FEC, RED)
+pub fn is_codec_payload_type(payload_type: u32) -> bool {
+    (96..=127).contains(&payload_type)
+        && !RESERVED_PAYLOAD_TYPES.iter().any(|&reserved| reserved as u32 == payload_type)
+}
+
+/// Parse profile-level-id H.264 menjadi (profile_idc, profile_iop, level_idc)
+fn parse_h264_profile(profile: Option<&str>) -> Option<(u8, u8, u8)> {
+    // Default RFC 6184 jika tidak disebut: baseline level 1.0
+    let profile = profile.unwrap_or("42000a");
+    if profile.len() != 6 {
+        return None;
+    }
+
+    let profile_idc = u8::from_str_radix(&profile[0..2], 16).ok()?;
+    let profile_iop = u8::from_str_radix(&profile[2..4], 16).ok()?;
+    let level_idc = u8::from_str_radix(&profile[4..6], 16).ok()?;
+    Some((profile_idc, profile_iop, level_idc))
+}
+
+/// Cocokkan profile dua kapabilitas video, kembalikan profile hasil negosiasi
+///
+/// `Some(None)` berarti cocok tanpa profile, `None` berarti tidak cocok.
+fn match_video_profile(
+    codec: &VideoCodec,
+    offered: Option<&str>,
+    local: Option<&str>,
+) -> Option<Option<String>> {
+    match codec {
+        VideoCodec::H264 => {
+            let (offer_idc, offer_iop, offer_level) = parse_h264_profile(offered)?;
+            let (local_idc, local_iop, local_level) = parse_h264_profile(local)?;
+
+            // Constrained baseline (constraint_set1) kompatibel dengan baseline
+            let offer_cb = offer_idc == 0x42 && offer_iop & 0x40 != 0;
+            let local_cb = local_idc == 0x42 && local_iop & 0x40 != 0;
+            if offer_idc != local_idc || offer_cb != local_cb {
+                return None;
+            }
+
+            // Level dipilih yang terendah agar kedua decoder sanggup
+            let level = offer_level.min(local_level);
+            Some(Some(format!("{:02x}{:02x}{:02x}", offer_idc, offer_iop, level)))
+        }
+        VideoCodec::Vp9 | VideoCodec::Av1 | VideoCodec::H265 => {
+            let offered = offered.unwrap_or("0");
+            let local = local.unwrap_or("0");
+            if offered == local {
+                Some(Some(offered.to_string()))
+            } else {
+                None
+            }
+        }
+        VideoCodec::Vp8 => Some(None),
+    }
+}
+
+fn describe_video(list: &[VideoCodecCapability]) -> String {
+    list.iter()
+        .map(|c| match &c.profile {
+            Some(profile) => format!("{}/{}", c.codec.as_str(), profile),
+            None => c.codec.as_str().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe_audio(list: &[AudioCodecCapability]) -> String {
+    list.iter()
+        .map(|c| c.codec.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl CodecCapabilities {
+    fn check_payload_types(&self) -> Result<()> {
+        let invalid = self
+            .video
+            .iter()
+            .map(|c| c.payload_type)
+            .chain(self.audio.iter().map(|c| c.payload_type))
+            .find(|&payload_type| !is_codec_payload_type(payload_type));
+
+        match invalid {
+            Some(payload_type) => Err(Error::new(
+                Status::InvalidArg,
+                format!("Payload type tidak valid: {}", payload_type),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Pilih codec terbaik yang didukung bersama
+    ///
+    /// `self` adalah offer dari remote peer; urutan preferensi offer yang
+    /// dipakai dan payload type mengikuti offer. Gagal dengan pesan yang
+    /// jelas jika kedua peer mengaktifkan suatu media tetapi tidak punya
+    /// codec yang sama, atau jika ada payload type yang dicadangkan.
+    pub fn negotiate(&self, local: &CodecCapabilities) -> Result<NegotiatedCodecs> {
+        self.check_payload_types()?;
+        local.check_payload_types()?;
+
+        let video = if self.video.is_empty() || local.video.is_empty() {
+            None
+        } else {
+            let selected = self.video.iter().find_map(|offered| {
+                local
+                    .video
+                    .iter()
+                    .filter(|own| own.codec == offered.codec)
+                    .find_map(|own| {
+                        match_video_profile(
+                            &offered.codec,
+                            offered.profile.as_deref(),
+                            own.profile.as_deref(),
+                        )
+                        .map(|profile| VideoCodecCapability {
+                            codec: offered.codec,
+                            payload_type: offered.payload_type,
+                            profile,
+                            hardware_accelerated: own.hardware_accelerated,
+                        })
+                    })
+            });
+
+            match selected {
+                Some(codec) => Some(codec),
+                None => {
+                    return Err(Error::new(
+                        Status::GenericFailure,
+                        format!(
+                            "Tidak ada codec video yang didukung kedua peer (remote: {}; lokal: {})",
+                            describe_video(&self.video),
+                            describe_video(&local.video)
+                        ),
+                    ))
+                }
+            }
+        };
+
+        let audio = if self.audio.is_empty() || local.audio.is_empty() {
+            None
+        } else {
+            let selected = self.audio.iter().find_map(|offered| {
+                local
+                    .audio
+                    .iter()
+                    .find(|own| own.codec == offered.codec && own.clock_rate == offered.clock_rate)
+                    .map(|own| AudioCodecCapability {
+                        codec: offered.codec,
+                        payload_type: offered.payload_type,
+                        clock_rate: offered.clock_rate,
+                        channels: offered.channels.min(own.channels).max(1),
+                        fec: offered.fec && own.fec,
+                        dtx: offered.dtx && own.dtx,
+                    })
+            });
+
+            match selected {
+                Some(codec) => Some(codec),
+                None => {
+                    return Err(Error::new(
+                        Status::GenericFailure,
+                        format!(
+                            "Tidak ada codec audio yang didukung kedua peer (remote: {}; lokal: {})",
+                            describe_audio(&self.audio),
+                            describe_audio(&local.audio)
+                        ),
+                    ))
+                }
+            }
+        };
+
+        if video.is_none() && audio.is_none() {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "Tidak ada media yang bisa dinegosiasikan antar peer".to_string(),
+            ));
+        }
+
+        Ok(NegotiatedCodecs { video, audio })
+    }
+
+    /// Cek apakah hasil negosiasi dari remote peer valid untuk kapabilitas ini
+    pub fn accepts(&self, answer: &NegotiatedCodecs) -> Result<()> {
+        if let Some(video) = &answer.video {
+            let supported = self.video.iter().any(|own| {
+                own.codec == video.codec
+                    && own.payload_type == video.payload_type
+                    && match_video_profile(&video.codec, video.profile.as_deref(), own.profile.as_deref())
+                        .is_some()
+            });
+            if !supported {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Answer memilih codec video yang tidak ditawarkan: {}", video.codec.as_str()),
+                ));
+            }
+        }
+
+        if let Some(audio) = &answer.audio {
+            let supported = self.audio.iter().any(|own| {
+                own.codec == audio.codec
+                    && own.payload_type == audio.payload_type
+                    && own.clock_rate == audio.clock_rate
+            });
+            if !supported {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Answer memilih codec audio yang tidak ditawarkan: {}", audio.codec.as_str()),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video_cap(codec: VideoCodec, payload_type: u32, profile: Option<&str>) -> VideoCodecCapability {
+        VideoCodecCapability {
+            codec,
+            payload_type,
+            profile: profile.map(str::to_string),
+            hardware_accelerated: false,
+        }
+    }
+
+    fn opus(payload_type: u32) -> AudioCodecCapability {
+        AudioCodecCapability {
+            codec: AudioCodec::Opus,
+            payload_type,
+            clock_rate: 48000,
+            channels: 2,
+            fec: true,
+            dtx: false,
+        }
+    }
+
+    fn caps(video: Vec<VideoCodecCapability>, audio: Vec<AudioCodecCapability>) -> CodecCapabilities {
+        CodecCapabilities { video, audio }
+    }
+
+    #[test]
+    fn follows_offer_preference_order() {
+        let offer = caps(
+            vec![video_cap(VideoCodec::Vp8, 96, None), video_cap(VideoCodec::H264, 102, Some("42e01f"))],
+            vec![opus(109)],
+        );
+        let result = offer.negotiate(&CodecCapabilities::default()).unwrap();
+
+        // Lokal lebih suka H.264, tetapi urutan offer yang menang
+        let video = result.video.unwrap();
+        assert_eq!((video.codec, video.payload_type), (VideoCodec::Vp8, 96));
+        // Payload type mengikuti offer
+        assert_eq!(result.audio.unwrap().payload_type, 109);
+    }
+
+    #[test]
+    fn falls_back_when_peer_lacks_hevc() {
+        let offer = caps(
+            vec![
+                video_cap(VideoCodec::H265, 104, Some("1")),
+                video_cap(VideoCodec::H264, 102, Some("42e01f")),
+                video_cap(VideoCodec::Vp8, 96, None),
+            ],
+            vec![opus(111)],
+        );
+        let video = offer.negotiate(&CodecCapabilities::default()).unwrap().video.unwrap();
+        assert_eq!(video.codec, VideoCodec::H264);
+
+        let local = caps(vec![video_cap(VideoCodec::Vp8, 96, None)], vec![opus(111)]);
+        let video = offer.negotiate(&local).unwrap().video.unwrap();
+        assert_eq!(video.codec, VideoCodec::Vp8);
+    }
+
+    #[test]
+    fn matches_h264_profile_and_picks_lower_level() {
+        let local = caps(vec![video_cap(VideoCodec::H264, 102, Some("42e01f"))], vec![]);
+
+        // Constrained baseline level 5.1 vs 3.1 -> level 3.1
+        let offer = caps(vec![video_cap(VideoCodec::H264, 100, Some("42e033"))], vec![]);
+        let video = offer.negotiate(&local).unwrap().video.unwrap();
+        assert_eq!(video.profile.as_deref(), Some("42e01f"));
+        assert_eq!(video.payload_type, 100);
+
+        // High profile tidak cocok dengan constrained baseline
+        let offer = caps(vec![video_cap(VideoCodec::H264, 100, Some("64001f"))], vec![]);
+        assert!(offer.negotiate(&local).is_err());
+
+        // Baseline tanpa constraint_set1 tidak cocok dengan constrained baseline
+        let offer = caps(vec![video_cap(VideoCodec::H264, 100, Some("42001f"))], vec![]);
+        assert!(offer.negotiate(&local).is_err());
+
+        // Profile rusak ditolak
+        let offer = caps(vec![video_cap(VideoCodec::H264, 100, Some("zz"))], vec![]);
+        assert!(offer.negotiate(&local).is_err());
+    }
+
+    #[test]
+    fn reports_missing_common_video_codec() {
+        let offer = caps(vec![video_cap(VideoCodec::Av1, 98, Some("0"))], vec![opus(111)]);
+        let err = offer.negotiate(&CodecCapabilities::default()).unwrap_err();
+        assert!(err.reason.contains("codec video"), "{}", err.reason);
+        assert!(err.reason.contains("av1"), "{}", err.reason);
+    }
+
+    #[test]
+    fn reports_missing_common_audio_codec() {
+        let aac = AudioCodecCapability {
+            codec: AudioCodec::Aac,
+            payload_type: 97,
+            clock_rate: 44100,
+            channels: 2,
+            fec: false,
+            dtx: false,
+        };
+        let offer = caps(vec![video_cap(VideoCodec::Vp8, 96, None)], vec![aac]);
+        let err = offer.negotiate(&CodecCapabilities::default()).unwrap_err();
+        assert!(err.reason.contains("codec audio"), "{}", err.reason);
+        assert!(err.reason.contains("aac"), "{}", err.reason);
+    }
+
+    #[test]
+    fn audio_only_peer_negotiates_without_video() {
+        let offer = caps(vec![], vec![opus(111)]);
+        let result = offer.negotiate(&CodecCapabilities::default()).unwrap();
+        assert!(result.video.is_none());
+        assert!(result.audio.is_some());
+    }
+
+    #[test]
+    fn rejects_reserved_payload_types() {
+        // PT padding dibuang penerima, jadi tidak boleh dipakai codec
+        let padding = PADDING_PAYLOAD_TYPE as u32;
+        let offer = caps(vec![video_cap(VideoCodec::Vp8, padding, None)], vec![opus(111)]);
+        let err = offer.negotiate(&CodecCapabilities::default()).unwrap_err();
+        assert!(err.reason.contains("Payload type"), "{}", err.reason);
+
+        let offer = caps(vec![video_cap(VideoCodec::Vp8, 96, None)], vec![opus(padding)]);
+        assert!(offer.negotiate(&CodecCapabilities::default()).is_err());
+        assert!(CodecCapabilities::default().negotiate(&offer).is_err());
+        assert!(!is_codec_payload_type(padding));
+
+        // PT FEC video dan RED audio diproses khusus oleh penerima
+        let fec = VIDEO_FEC_PAYLOAD_TYPE as u32;
+        let offer = caps(vec![video_cap(VideoCodec::H264, fec, Some("42e01f"))], vec![]);
+        assert!(offer.negotiate(&CodecCapabilities::default()).is_err());
+        let offer = caps(vec![], vec![opus(AUDIO_RED_PAYLOAD_TYPE as u32)]);
+        assert!(offer.negotiate(&CodecCapabilities::default()).is_err());
+        assert!(!is_codec_payload_type(fec));
+        assert!(!is_codec_payload_type(95));
+        assert!(is_codec_payload_type(96));
+    }
+
+    #[test]
+    fn accepts_only_offered_codecs() {
+        let local = CodecCapabilities::default();
+        let answer = local.negotiate(&local).unwrap();
+        assert!(local.accepts(&answer).is_ok());
+
+        // Payload type berbeda dari yang ditawarkan
+        let mut wrong_pt = answer.clone();
+        wrong_pt.video.as_mut().unwrap().payload_type = 120;
+        assert!(local.accepts(&wrong_pt).is_err());
+
+        // Codec yang tidak pernah ditawarkan
+        let mut wrong_codec = answer.clone();
+        wrong_codec.video = Some(video_cap(VideoCodec::Vp9, 102, Some("0")));
+        assert!(local.accepts(&wrong_codec).is_err());
+
+        let mut wrong_audio = answer.clone();
+        wrong_audio.audio.as_mut().unwrap().payload_type = 109;
+        assert!(local.accepts(&wrong_audio).is_err());
+    }
+}
@@ -0,0 +1,1152 @@
+//! Modul Data Channel ELARA
+//!
+//! Data channel di atas transport sesi untuk chat, reaksi dan indikator
+//! mengetik tanpa lewat server socket.io. Mirip SCTP versi ringkas: pesan
+//! dipecah menjadi chunk ber-TSN, penerima membalas ack kumulatif beserta
+//! gap block, chunk yang hilang dikirim ulang (fast retransmit atau
+//! timeout), dan congestion window membatasi data yang sedang jalan.
+//!
+//! Beberapa channel bernama dimultipleks di satu transport, masing-masing
+//! dengan TSN dan mode reliability sendiri, sehingga reaksi yang
+//! unreliable tidak tertahan di belakang chat.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use napi_derive::napi;
+use thiserror::Error;
+
+use crate::rtp::{Unwrapper, DEFAULT_MTU};
+
+/// Byte pertama paket data channel; di luar rentang RFC 7983 dan
+/// berbeda dari probe MTU (0xEE)
+pub const DATA_CHANNEL_MAGIC: u8 = 0xED;
+
+/// Channel bawaan yang dipakai `send_message`
+pub const DEFAULT_CHANNEL: &str = "default";
+
+/// Ukuran pesan maksimum (bytes)
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Batas data yang boleh menumpuk di sisi kirim per channel; `send`
+/// ditolak di atasnya
+pub const MAX_BUFFERED_BYTES: usize = 1024 * 1024;
+
+/// Panjang nama channel maksimum (bytes)
+const MAX_CHANNEL_NAME_LEN: usize = 64;
+
+/// Buffer penerima untuk chunk yang datang tidak berurutan
+const RECEIVE_WINDOW: usize = 256 * 1024;
+
+/// Jarak TSN maksimum di depan `next_expected` yang masih diterima;
+/// di bawah batas offset gap block u16
+const RECEIVE_WINDOW_CHUNKS: i64 = 4096;
+
+const KIND_DATA: u8 = 0;
+const KIND_ACK: u8 = 1;
+const KIND_FORWARD: u8 = 2;
+
+const FLAG_BEGIN: u8 = 0x01;
+const FLAG_END: u8 = 0x02;
+const FLAG_BINARY: u8 = 0x04;
+
+/// Header chunk data: magic, jenis, flag, channel, TSN
+const DATA_HEADER_LEN: usize = 9;
+
+/// Header ack: magic, jenis, jumlah gap, channel, TSN berikutnya, window
+const ACK_HEADER_LEN: usize = 13;
+
+/// Paket forward: magic, jenis, channel, TSN berikutnya
+const FORWARD_LEN: usize = 8;
+
+/// Gap block maksimum per ack
+const MAX_GAP_BLOCKS: usize = 16;
+
+/// Laporan hilang sebelum fast retransmit (RFC 4960 bagian 7.2.4)
+const FAST_RETRANSMIT_REPORTS: u32 = 3;
+
+const INITIAL_RTO_MS: u64 = 1000;
+const MIN_RTO_MS: u64 = 200;
+const MAX_RTO_MS: u64 = 10_000;
+
+/// Mode reliability channel
+#[napi(string_enum = "kebab-case")]
+#[derive(Debug, PartialEq)]
+pub enum ChannelReliability {
+    /// Semua pesan sampai, sesuai urutan kirim (chat)
+    ReliableOrdered,
+    /// Semua pesan sampai, langsung diteruskan begitu lengkap
+    ReliableUnordered,
+    /// Pesan boleh hilang, tanpa urutan (reaksi, indikator mengetik)
+    Unreliable,
+}
+
+/// Konfigurasi channel; kedua peer membuka channel dengan nama dan
+/// konfigurasi yang sama
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct DataChannelConfig {
+    /// Nama channel
+    pub name: String,
+    /// Mode reliability
+    pub reliability: ChannelReliability,
+    /// Batas kirim ulang per chunk (unreliable, default 0)
+    pub max_retransmits: Option<u32>,
+    /// Umur maksimum pesan sebelum dibuang (unreliable, ms)
+    pub max_lifetime_ms: Option<u32>,
+}
+
+/// Error data channel
+#[derive(Debug, Error, PartialEq)]
+pub enum DataChannelError {
+    /// Pesan melebihi `MAX_MESSAGE_SIZE`
+    #[error("pesan terlalu besar: {size} byte, maksimum {max}")]
+    MessageTooLarge { size: usize, max: usize },
+    /// Buffer kirim penuh, tunggu `buffered_amount` turun
+    #[error("buffer kirim penuh: {buffered} byte tertunda")]
+    BufferFull { buffered: usize },
+    /// Channel belum dibuka
+    #[error("channel tidak dikenal: {0}")]
+    UnknownChannel(String),
+    /// Channel dengan nama ini sudah dibuka
+    #[error("channel sudah dibuka: {0}")]
+    ChannelExists(String),
+    /// Konfigurasi channel tidak valid
+    #[error("konfigurasi channel tidak valid: {0}")]
+    InvalidConfig(String),
+}
+
+impl From<DataChannelError> for napi::Error {
+    fn from(err: DataChannelError) -> Self {
+        let status = match err {
+            DataChannelError::BufferFull { .. } => napi::Status::QueueFull,
+            _ => napi::Status::InvalidArg,
+        };
+        napi::Error::new(status, err.to_string())
+    }
+}
+
+/// Paket data channel
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataPacket {
+    /// Potongan pesan
+    Data {
+        channel: u16,
+        tsn: u32,
+        begin: bool,
+        end: bool,
+        binary: bool,
+        payload: Vec<u8>,
+    },
+    /// Ack: semua TSN sebelum `next_tsn` sudah diterima, ditambah gap
+    /// block (offset dari `next_tsn`, inklusif) yang diterima di luar urutan
+    Ack {
+        channel: u16,
+        next_tsn: u32,
+        window: u32,
+        gaps: Vec<(u16, u16)>,
+    },
+    /// Pengirim membuang pesan di bawah `next_tsn` (channel unreliable),
+    /// penerima tidak perlu menunggunya lagi
+    Forward { channel: u16, next_tsn: u32 },
+}
+
+impl DataPacket {
+    /// Parse paket, `None` jika bukan paket data channel
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 2 || data[0] != DATA_CHANNEL_MAGIC {
+            return None;
+        }
+        let read_u32 = |at: usize| u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+
+        match data[1] {
+            KIND_DATA if data.len() >= DATA_HEADER_LEN => Some(DataPacket::Data {
+                channel: u16::from_be_bytes([data[3], data[4]]),
+                tsn: read_u32(5),
+                begin: data[2] & FLAG_BEGIN != 0,
+                end: data[2] & FLAG_END != 0,
+                binary: data[2] & FLAG_BINARY != 0,
+                payload: data[DATA_HEADER_LEN..].to_vec(),
+            }),
+            KIND_ACK if data.len() >= ACK_HEADER_LEN => {
+                let count = data[2] as usize;
+                if data.len() < ACK_HEADER_LEN + count * 4 {
+                    return None;
+                }
+                let gaps: Vec<(u16, u16)> = data[ACK_HEADER_LEN..]
+                    .chunks_exact(4)
+                    .take(count)
+                    .map(|gap| {
+                        (
+                            u16::from_be_bytes([gap[0], gap[1]]),
+                            u16::from_be_bytes([gap[2], gap[3]]),
+                        )
+                    })
+                    .collect();
+                // Gap terbalik tidak valid (dan membuat range panic)
+                if gaps.iter().any(|(start, end)| start > end) {
+                    return None;
+                }
+                Some(DataPacket::Ack {
+                    channel: u16::from_be_bytes([data[3], data[4]]),
+                    next_tsn: read_u32(5),
+                    window: read_u32(9),
+                    gaps,
+                })
+            }
+            KIND_FORWARD if data.len() >= FORWARD_LEN => Some(DataPacket::Forward {
+                channel: u16::from_be_bytes([data[2], data[3]]),
+                next_tsn: read_u32(4),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Serialisasi ke bytes
+    pub fn serialize(&self) -> Vec<u8> {
+        match self {
+            DataPacket::Data { channel, tsn, begin, end, binary, payload } => {
+                let mut flags = 0;
+                if *begin {
+                    flags |= FLAG_BEGIN;
+                }
+                if *end {
+                    flags |= FLAG_END;
+                }
+                if *binary {
+                    flags |= FLAG_BINARY;
+                }
+                let mut buf = Vec::with_capacity(DATA_HEADER_LEN + payload.len());
+                buf.extend_from_slice(&[DATA_CHANNEL_MAGIC, KIND_DATA, flags]);
+                buf.extend_from_slice(&channel.to_be_bytes());
+                buf.extend_from_slice(&tsn.to_be_bytes());
+                buf.extend_from_slice(payload);
+                buf
+            }
+            DataPacket::Ack { channel, next_tsn, window, gaps } => {
+                let gaps = &gaps[..gaps.len().min(MAX_GAP_BLOCKS)];
+                let mut buf = Vec::with_capacity(ACK_HEADER_LEN + gaps.len() * 4);
+                buf.extend_from_slice(&[DATA_CHANNEL_MAGIC, KIND_ACK, gaps.len() as u8]);
+                buf.extend_from_slice(&channel.to_be_bytes());
+                buf.extend_from_slice(&next_tsn.to_be_bytes());
+                buf.extend_from_slice(&window.to_be_bytes());
+                for (start, end) in gaps {
+                    buf.extend_from_slice(&start.to_be_bytes());
+                    buf.extend_from_slice(&end.to_be_bytes());
+                }
+                buf
+            }
+            DataPacket::Forward { channel, next_tsn } => {
+                let mut buf = Vec::with_capacity(FORWARD_LEN);
+                buf.extend_from_slice(&[DATA_CHANNEL_MAGIC, KIND_FORWARD]);
+                buf.extend_from_slice(&channel.to_be_bytes());
+                buf.extend_from_slice(&next_tsn.to_be_bytes());
+                buf
+            }
+        }
+    }
+}
+
+/// Pesan lengkap dari peer
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelMessage {
+    /// Nama channel
+    pub channel: String,
+    /// Isi pesan
+    pub payload: Vec<u8>,
+    /// Dikirim sebagai binary (Buffer), bukan string
+    pub binary: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    tsn: i64,
+    /// TSN chunk pertama pesan ini
+    message: i64,
+    begin: bool,
+    end: bool,
+    binary: bool,
+    created_ms: u64,
+    payload: Vec<u8>,
+}
+
+impl Chunk {
+    /// Porsi buffer penerima yang dipakai, termasuk header agar chunk
+    /// kosong tetap terhitung
+    fn receive_cost(&self) -> usize {
+        DATA_HEADER_LEN + self.payload.len()
+    }
+}
+
+#[derive(Debug)]
+struct InFlight {
+    chunk: Chunk,
+    sent_ms: u64,
+    transmissions: u32,
+    missing_reports: u32,
+    needs_resend: bool,
+}
+
+/// Satu channel dengan TSN, congestion window dan reassembly sendiri
+#[derive(Debug)]
+struct DataChannel {
+    id: u16,
+    name: String,
+    ordered: bool,
+    max_retransmits: Option<u32>,
+    max_lifetime_ms: Option<u64>,
+    // Sisi kirim
+    next_tsn: i64,
+    outgoing: VecDeque<Chunk>,
+    in_flight: BTreeMap<i64, InFlight>,
+    buffered_bytes: usize,
+    flight_bytes: usize,
+    cwnd: usize,
+    ssthresh: usize,
+    peer_window: usize,
+    peer_next: i64,
+    forward_sent_ms: Option<u64>,
+    srtt_ms: Option<f64>,
+    rttvar_ms: f64,
+    rto_ms: u64,
+    ack_sequence: Unwrapper,
+    retransmissions: u64,
+    // Sisi terima
+    tsn_sequence: Unwrapper,
+    next_expected: i64,
+    above: BTreeSet<i64>,
+    received: BTreeMap<i64, Chunk>,
+    received_bytes: usize,
+    deliver_next: i64,
+    partial: Vec<u8>,
+    ack_pending: bool,
+}
+
+impl DataChannel {
+    fn new(id: u16, config: &DataChannelConfig, mtu: usize) -> Self {
+        let unreliable = config.reliability == ChannelReliability::Unreliable;
+        Self {
+            id,
+            name: config.name.clone(),
+            ordered: config.reliability == ChannelReliability::ReliableOrdered,
+            max_retransmits: unreliable
+                .then(|| config.max_retransmits.unwrap_or(if config.max_lifetime_ms.is_some() { u32::MAX } else { 0 })),
+            max_lifetime_ms: config.max_lifetime_ms.map(|ms| ms as u64),
+            next_tsn: 0,
+            outgoing: VecDeque::new(),
+            in_flight: BTreeMap::new(),
+            buffered_bytes: 0,
+            flight_bytes: 0,
+            cwnd: 4 * mtu,
+            ssthresh: RECEIVE_WINDOW,
+            peer_window: RECEIVE_WINDOW,
+            peer_next: 0,
+            forward_sent_ms: None,
+            srtt_ms: None,
+            rttvar_ms: 0.0,
+            rto_ms: INITIAL_RTO_MS,
+            ack_sequence: Unwrapper::default(),
+            retransmissions: 0,
+            tsn_sequence: Unwrapper::default(),
+            next_expected: 0,
+            above: BTreeSet::new(),
+            received: BTreeMap::new(),
+            received_bytes: 0,
+            deliver_next: 0,
+            partial: Vec::new(),
+            ack_pending: false,
+        }
+    }
+
+    fn send(&mut self, message: &[u8], binary: bool, mtu: usize, now_ms: u64) -> Result<(), DataChannelError> {
+        if message.len() > MAX_MESSAGE_SIZE {
+            return Err(DataChannelError::MessageTooLarge {
+                size: message.len(),
+                max: MAX_MESSAGE_SIZE,
+            });
+        }
+        if self.buffered_bytes + message.len() > MAX_BUFFERED_BYTES {
+            return Err(DataChannelError::BufferFull {
+                buffered: self.buffered_bytes,
+            });
+        }
+
+        let chunk_size = mtu.saturating_sub(DATA_HEADER_LEN).max(1);
+        let count = message.len().div_ceil(chunk_size).max(1);
+        let first = self.next_tsn;
+        for index in 0..count {
+            let start = index * chunk_size;
+            let end = (start + chunk_size).min(message.len());
+            self.outgoing.push_back(Chunk {
+                tsn: self.next_tsn,
+                message: first,
+                begin: index == 0,
+                end: index == count - 1,
+                binary,
+                created_ms: now_ms,
+                payload: message[start..end].to_vec(),
+            });
+            self.next_tsn += 1;
+        }
+        self.buffered_bytes += message.len();
+        Ok(())
+    }
+
+    fn packet(&self, chunk: &Chunk) -> Vec<u8> {
+        DataPacket::Data {
+            channel: self.id,
+            tsn: chunk.tsn as u32,
+            begin: chunk.begin,
+            end: chunk.end,
+            binary: chunk.binary,
+            payload: chunk.payload.clone(),
+        }
+        .serialize()
+    }
+
+    fn can_send(&self, size: usize) -> bool {
+        self.flight_bytes == 0 || self.flight_bytes + size <= self.cwnd.min(self.peer_window)
+    }
+
+    /// Buang seluruh chunk satu pesan (channel unreliable)
+    fn abandon(&mut self, message: i64) {
+        let tsns: Vec<i64> = self
+            .in_flight
+            .iter()
+            .filter(|(_, flight)| flight.chunk.message == message)
+            .map(|(&tsn, _)| tsn)
+            .collect();
+        for tsn in tsns {
+            let flight = self.in_flight.remove(&tsn).unwrap();
+            if !flight.needs_resend {
+                self.flight_bytes -= flight.chunk.payload.len();
+            }
+            self.buffered_bytes -= flight.chunk.payload.len();
+        }
+
+        let buffered = &mut self.buffered_bytes;
+        self.outgoing.retain(|chunk| {
+            if chunk.message == message {
+                *buffered -= chunk.payload.len();
+                return false;
+            }
+            true
+        });
+    }
+
+    fn poll(&mut self, now_ms: u64, mtu: usize, out: &mut Vec<Vec<u8>>) {
+        if let Some(lifetime) = self.max_lifetime_ms {
+            let expired: BTreeSet<i64> = self
+                .in_flight
+                .values()
+                .map(|flight| &flight.chunk)
+                .chain(self.outgoing.iter())
+                .filter(|chunk| now_ms.saturating_sub(chunk.created_ms) >= lifetime)
+                .map(|chunk| chunk.message)
+                .collect();
+            for message in expired {
+                self.abandon(message);
+            }
+        }
+
+        // Timeout: semua chunk yang jalan dianggap hilang (RFC 4960 6.3.3)
+        let timed_out = self
+            .in_flight
+            .values()
+            .filter(|flight| !flight.needs_resend)
+            .any(|flight| now_ms.saturating_sub(flight.sent_ms) >= self.rto_ms);
+        if timed_out {
+            self.ssthresh = (self.cwnd / 2).max(4 * mtu);
+            self.cwnd = mtu;
+            self.rto_ms = (self.rto_ms * 2).min(MAX_RTO_MS);
+            for flight in self.in_flight.values_mut() {
+                flight.needs_resend = true;
+            }
+            self.flight_bytes = 0;
+        }
+
+        let resend: Vec<i64> = self
+            .in_flight
+            .iter()
+            .filter(|(_, flight)| flight.needs_resend)
+            .map(|(&tsn, _)| tsn)
+            .collect();
+        for tsn in resend {
+            let Some(flight) = self.in_flight.get(&tsn) else {
+                continue;
+            };
+            if self.max_retransmits.is_some_and(|max| flight.transmissions > max) {
+                self.abandon(flight.chunk.message);
+                continue;
+            }
+            let size = flight.chunk.payload.len();
+            if !self.can_send(size) {
+                break;
+            }
+            let packet = self.packet(&flight.chunk);
+            let flight = self.in_flight.get_mut(&tsn).unwrap();
+            flight.needs_resend = false;
+            flight.transmissions += 1;
+            flight.missing_reports = 0;
+            flight.sent_ms = now_ms;
+            self.flight_bytes += size;
+            self.retransmissions += 1;
+            out.push(packet);
+        }
+
+        while let Some(chunk) = self.outgoing.front() {
+            if !self.can_send(chunk.payload.len()) {
+                break;
+            }
+            let chunk = self.outgoing.pop_front().unwrap();
+            self.flight_bytes += chunk.payload.len();
+            out.push(self.packet(&chunk));
+            self.in_flight.insert(
+                chunk.tsn,
+                InFlight {
+                    chunk,
+                    sent_ms: now_ms,
+                    transmissions: 1,
+                    missing_reports: 0,
+                    needs_resend: false,
+                },
+            );
+        }
+
+        // Pesan yang dibuang membuat celah; beri tahu penerima agar tidak
+        // menunggunya, ulangi tiap RTO sampai di-ack
+        let floor = self
+            .in_flight
+            .keys()
+            .next()
+            .copied()
+            .or(self.outgoing.front().map(|chunk| chunk.tsn))
+            .unwrap_or(self.next_tsn);
+        if floor > self.peer_next
+            && self
+                .forward_sent_ms
+                .is_none_or(|sent| now_ms.saturating_sub(sent) >= self.rto_ms)
+        {
+            self.forward_sent_ms = Some(now_ms);
+            out.push(
+                DataPacket::Forward {
+                    channel: self.id,
+                    next_tsn: floor as u32,
+                }
+                .serialize(),
+            );
+        }
+
+        if self.ack_pending {
+            self.ack_pending = false;
+            out.push(self.build_ack().serialize());
+        }
+    }
+
+    fn build_ack(&self) -> DataPacket {
+        let mut gaps: Vec<(u16, u16)> = Vec::new();
+        for &tsn in &self.above {
+            let offset = (tsn - self.next_expected).min(u16::MAX as i64) as u16;
+            if let Some((_, end)) = gaps
+                .last_mut()
+                .filter(|(_, end)| end.checked_add(1) == Some(offset))
+            {
+                *end = offset;
+            } else if gaps.len() < MAX_GAP_BLOCKS {
+                gaps.push((offset, offset));
+            } else {
+                break;
+            }
+        }
+
+        DataPacket::Ack {
+            channel: self.id,
+            next_tsn: self.next_expected as u32,
+            window: RECEIVE_WINDOW.saturating_sub(self.received_bytes) as u32,
+            gaps,
+        }
+    }
+
+    fn advance(&mut self) {
+        while self.above.remove(&self.next_expected) {
+            self.next_expected += 1;
+        }
+    }
+
+    fn on_data(&mut self, chunk: Chunk) -> Vec<ChannelMessage> {
+        // Duplikat pun dibalas ack, mungkin ack sebelumnya yang hilang
+        self.ack_pending = true;
+        if chunk.tsn < self.next_expected || self.above.contains(&chunk.tsn) {
+            return Vec::new();
+        }
+        // Peer yang melompat jauh ke depan diabaikan, TSN-nya pasti salah
+        if chunk.tsn >= self.next_expected + RECEIVE_WINDOW_CHUNKS {
+            return Vec::new();
+        }
+        if chunk.tsn != self.next_expected
+            && self.received_bytes + chunk.receive_cost() > RECEIVE_WINDOW
+        {
+            return Vec::new();
+        }
+        let tsn = chunk.tsn;
+        self.above.insert(tsn);
+        self.advance();
+        self.received_bytes += chunk.receive_cost();
+        self.received.insert(tsn, chunk);
+
+        if self.ordered {
+            self.deliver_ordered()
+        } else {
+            self.deliver_unordered(tsn)
+        }
+    }
+
+    fn deliver_ordered(&mut self) -> Vec<ChannelMessage> {
+        let mut messages = Vec::new();
+        while let Some(chunk) = self.received.remove(&self.deliver_next) {
+            self.received_bytes -= chunk.receive_cost();
+            self.deliver_next += 1;
+            if chunk.begin {
+                self.partial.clear();
+            }
+            self.partial.extend_from_slice(&chunk.payload);
+            if chunk.end {
+                messages.push(ChannelMessage {
+                    channel: self.name.clone(),
+                    payload: std::mem::take(&mut self.partial),
+                    binary: chunk.binary,
+                });
+            }
+        }
+        messages
+    }
+
+    fn deliver_unordered(&mut self, tsn: i64) -> Vec<ChannelMessage> {
+        let Some((first, last)) = self.message_bounds(tsn) else {
+            return Vec::new();
+        };
+
+        let mut payload = Vec::new();
+        let mut binary = false;
+        for tsn in first..=last {
+            let chunk = self.received.remove(&tsn).unwrap();
+            self.received_bytes -= chunk.receive_cost();
+            payload.extend_from_slice(&chunk.payload);
+            binary = chunk.binary;
+        }
+        vec![ChannelMessage {
+            channel: self.name.clone(),
+            payload,
+            binary,
+        }]
+    }
+
+    /// TSN awal dan akhir pesan yang memuat `tsn`, jika semua chunk-nya ada
+    fn message_bounds(&self, tsn: i64) -> Option<(i64, i64)> {
+        let mut first = tsn;
+        while !self.received.get(&first)?.begin {
+            first -= 1;
+        }
+        let mut last = tsn;
+        while !self.received.get(&last)?.end {
+            last += 1;
+        }
+        Some((first, last))
+    }
+
+    /// Pesan yang memuat `tsn` tidak akan lengkap: ada chunk yang hilang
+    /// di bawah `next_expected`, artinya sudah dibuang pengirim
+    fn is_abandoned(&self, tsn: i64) -> bool {
+        let mut first = tsn;
+        loop {
+            match self.received.get(&first) {
+                Some(chunk) if chunk.begin => break,
+                Some(_) => first -= 1,
+                None => return first < self.next_expected,
+            }
+        }
+        let mut last = tsn;
+        loop {
+            match self.received.get(&last) {
+                Some(chunk) if chunk.end => return false,
+                Some(_) => last += 1,
+                None => return last < self.next_expected,
+            }
+        }
+    }
+
+    fn on_forward(&mut self, next_tsn: i64) {
+        self.ack_pending = true;
+        if next_tsn <= self.next_expected {
+            return;
+        }
+        self.next_expected = next_tsn;
+        self.above.retain(|&tsn| tsn >= next_tsn);
+        self.advance();
+
+        let abandoned: Vec<i64> = self
+            .received
+            .keys()
+            .copied()
+            .filter(|&tsn| tsn < next_tsn && self.is_abandoned(tsn))
+            .collect();
+        for tsn in abandoned {
+            let chunk = self.received.remove(&tsn).unwrap();
+            self.received_bytes -= chunk.receive_cost();
+        }
+    }
+
+    fn on_rtt_sample(&mut self, rtt_ms: f64) {
+        // RFC 6298
+        match self.srtt_ms {
+            None => {
+                self.srtt_ms = Some(rtt_ms);
+                self.rttvar_ms = rtt_ms / 2.0;
+            }
+            Some(srtt) => {
+                self.rttvar_ms = 0.75 * self.rttvar_ms + 0.25 * (srtt - rtt_ms).abs();
+                self.srtt_ms = Some(0.875 * srtt + 0.125 * rtt_ms);
+            }
+        }
+        let rto = self.srtt_ms.unwrap_or(rtt_ms) + 4.0 * self.rttvar_ms;
+        self.rto_ms = (rto as u64).clamp(MIN_RTO_MS, MAX_RTO_MS);
+    }
+
+    fn on_ack(&mut self, next_tsn: i64, window: usize, gaps: &[(u16, u16)], mtu: usize, now_ms: u64) {
+        if next_tsn > self.next_tsn {
+            return;
+        }
+        self.peer_window = window;
+        self.peer_next = self.peer_next.max(next_tsn);
+
+        let mut acked: Vec<i64> = self.in_flight.range(..next_tsn).map(|(&tsn, _)| tsn).collect();
+        let mut highest_gap = None;
+        for &(start, end) in gaps {
+            let range = next_tsn + start as i64..=next_tsn + end as i64;
+            acked.extend(self.in_flight.range(range).map(|(&tsn, _)| tsn));
+            highest_gap = Some(next_tsn + end as i64);
+        }
+
+        let mut acked_bytes = 0;
+        let mut rtt_sample = None;
+        for tsn in acked {
+            let Some(flight) = self.in_flight.remove(&tsn) else {
+                continue;
+            };
+            let size = flight.chunk.payload.len();
+            if !flight.needs_resend {
+                self.flight_bytes -= size;
+            }
+            self.buffered_bytes -= size;
+            acked_bytes += size;
+            // Karn: RTT hanya dari chunk yang tidak dikirim ulang
+            if flight.transmissions == 1 {
+                rtt_sample = Some(now_ms.saturating_sub(flight.sent_ms));
+            }
+        }
+        if let Some(rtt) = rtt_sample {
+            self.on_rtt_sample(rtt as f64);
+        }
+
+        // Chunk di bawah gap tertinggi yang belum di-ack kemungkinan hilang
+        let mut lost = false;
+        if let Some(highest) = highest_gap {
+            for flight in self.in_flight.range_mut(..highest).map(|(_, flight)| flight) {
+                if flight.needs_resend {
+                    continue;
+                }
+                flight.missing_reports += 1;
+                if flight.missing_reports >= FAST_RETRANSMIT_REPORTS {
+                    flight.needs_resend = true;
+                    self.flight_bytes -= flight.chunk.payload.len();
+                    lost = true;
+                }
+            }
+        }
+
+        if lost {
+            self.ssthresh = (self.cwnd / 2).max(4 * mtu);
+            self.cwnd = self.ssthresh;
+        } else if acked_bytes > 0 {
+            if self.cwnd <= self.ssthresh {
+                self.cwnd += acked_bytes.min(mtu);
+            } else {
+                self.cwnd += (mtu * acked_bytes / self.cwnd).max(1);
+            }
+        }
+    }
+}
+
+/// ID channel dari namanya (FNV-1a dilipat ke 16 bit), sehingga kedua
+/// peer mendapat ID yang sama tanpa negosiasi; 0 khusus channel bawaan
+fn channel_id(name: &str) -> u16 {
+    if name == DEFAULT_CHANNEL {
+        return 0;
+    }
+    let hash = name
+        .bytes()
+        .fold(0x811c_9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193));
+    match (hash ^ (hash >> 16)) as u16 {
+        0 => 1,
+        id => id,
+    }
+}
+
+/// Kumpulan data channel satu sesi, dimultipleks di satu transport
+#[derive(Debug)]
+pub struct DataChannels {
+    mtu: usize,
+    channels: BTreeMap<u16, DataChannel>,
+}
+
+impl Default for DataChannels {
+    fn default() -> Self {
+        Self::new(DEFAULT_MTU)
+    }
+}
+
+impl DataChannels {
+    /// Buat multiplexer dengan channel bawaan (reliable, berurutan)
+    pub fn new(mtu: usize) -> Self {
+        let config = DataChannelConfig {
+            name: DEFAULT_CHANNEL.to_string(),
+            reliability: ChannelReliability::ReliableOrdered,
+            max_retransmits: None,
+            max_lifetime_ms: None,
+        };
+        let mut channels = BTreeMap::new();
+        channels.insert(0, DataChannel::new(0, &config, mtu));
+        Self { mtu, channels }
+    }
+
+    /// Ubah ukuran paket maksimum untuk chunk berikutnya
+    pub fn set_mtu(&mut self, mtu: usize) {
+        self.mtu = mtu;
+    }
+
+    /// Buka channel bernama, kembalikan ID-nya di wire
+    pub fn open(&mut self, config: &DataChannelConfig) -> Result<u16, DataChannelError> {
+        if config.name.is_empty() || config.name.len() > MAX_CHANNEL_NAME_LEN {
+            return Err(DataChannelError::InvalidConfig(format!(
+                "nama channel harus 1-{} byte",
+                MAX_CHANNEL_NAME_LEN
+            )));
+        }
+        if config.reliability != ChannelReliability::Unreliable
+            && (config.max_retransmits.is_some() || config.max_lifetime_ms.is_some())
+        {
+            return Err(DataChannelError::InvalidConfig(
+                "maxRetransmits/maxLifetimeMs hanya untuk channel unreliable".to_string(),
+            ));
+        }
+
+        let id = channel_id(&config.name);
+        if let Some(existing) = self.channels.get(&id) {
+            if existing.name == config.name {
+                return Err(DataChannelError::ChannelExists(config.name.clone()));
+            }
+            return Err(DataChannelError::InvalidConfig(format!(
+                "ID channel {} bentrok dengan {}, pakai nama lain",
+                config.name, existing.name
+            )));
+        }
+        self.channels.insert(id, DataChannel::new(id, config, self.mtu));
+        Ok(id)
+    }
+
+    /// Tutup channel; channel bawaan tidak bisa ditutup
+    pub fn close(&mut self, name: &str) -> bool {
+        let id = channel_id(name);
+        if id == 0 || self.channels.get(&id).is_none_or(|channel| channel.name != name) {
+            return false;
+        }
+        self.channels.remove(&id).is_some()
+    }
+
+    fn channel_mut(&mut self, name: &str) -> Result<&mut DataChannel, DataChannelError> {
+        self.channels
+            .get_mut(&channel_id(name))
+            .filter(|channel| channel.name == name)
+            .ok_or_else(|| DataChannelError::UnknownChannel(name.to_string()))
+    }
+
+    /// Antrekan pesan di channel; kembalikan bytes yang belum di-ack
+    pub fn send(
+        &mut self,
+        name: &str,
+        message: &[u8],
+        binary: bool,
+        now_ms: u64,
+    ) -> Result<usize, DataChannelError> {
+        let mtu = self.mtu;
+        let channel = self.channel_mut(name)?;
+        channel.send(message, binary, mtu, now_ms)?;
+        Ok(channel.buffered_bytes)
+    }
+
+    /// Bytes pesan di channel yang belum di-ack peer (antre + sedang jalan)
+    pub fn buffered_amount(&self, name: &str) -> Option<usize> {
+        self.channels
+            .get(&channel_id(name))
+            .filter(|channel| channel.name == name)
+            .map(|channel| channel.buffered_bytes)
+    }
+
+    /// Jumlah chunk yang dikirim ulang di semua channel
+    pub fn retransmissions(&self) -> u64 {
+        self.channels.values().map(|channel| channel.retransmissions).sum()
+    }
+
+    /// Paket yang perlu dikirim sekarang: retransmisi, chunk baru dan ack
+    ///
+    /// Panggil berkala (mis. tiap 10-20 ms) dan setiap selesai
+    /// `handle_packet`.
+    pub fn poll(&mut self, now_ms: u64) -> Vec<Vec<u8>> {
+        let mut out = Vec::new();
+        for channel in self.channels.values_mut() {
+            channel.poll(now_ms, self.mtu, &mut out);
+        }
+        out
+    }
+
+    /// Proses paket dari peer, kembalikan pesan yang sudah lengkap
+    ///
+    /// Paket untuk channel yang belum dibuka di sisi ini diabaikan.
+    pub fn handle_packet(&mut self, data: &[u8], now_ms: u64) -> Vec<ChannelMessage> {
+        let mtu = self.mtu;
+        match DataPacket::parse(data) {
+            Some(DataPacket::Data { channel, tsn, begin, end, binary, payload }) => {
+                let Some(channel) = self.channels.get_mut(&channel) else {
+                    return Vec::new();
+                };
+                let tsn = channel.tsn_sequence.unwrap(tsn, 32);
+                channel.on_data(Chunk {
+                    tsn,
+                    message: tsn,
+                    begin,
+                    end,
+                    binary,
+                    created_ms: now_ms,
+                    payload,
+                })
+            }
+            Some(DataPacket::Ack { channel, next_tsn, window, gaps }) => {
+                if let Some(channel) = self.channels.get_mut(&channel) {
+                    let next_tsn = channel.ack_sequence.unwrap(next_tsn, 32);
+                    channel.on_ack(next_tsn, window as usize, &gaps, mtu, now_ms);
+                }
+                Vec::new()
+            }
+            Some(DataPacket::Forward { channel, next_tsn }) => {
+                if let Some(channel) = self.channels.get_mut(&channel) {
+                    let next_tsn = channel.tsn_sequence.unwrap(next_tsn, 32);
+                    channel.on_forward(next_tsn);
+                }
+                Vec::new()
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kirim paket dari `from` ke `to`, buang yang dipilih `drop`
+    fn exchange(
+        from: &mut DataChannels,
+        to: &mut DataChannels,
+        now: u64,
+        drop: impl Fn(usize, &[u8]) -> bool,
+    ) -> Vec<ChannelMessage> {
+        let mut messages = Vec::new();
+        for (index, packet) in from.poll(now).into_iter().enumerate() {
+            if !drop(index, &packet) {
+                messages.extend(to.handle_packet(&packet, now));
+            }
+        }
+        messages
+    }
+
+    fn unreliable(name: &str) -> DataChannelConfig {
+        DataChannelConfig {
+            name: name.to_string(),
+            reliability: ChannelReliability::Unreliable,
+            max_retransmits: None,
+            max_lifetime_ms: None,
+        }
+    }
+
+    #[test]
+    fn large_message_is_fragmented_and_reassembled() {
+        let mut alice = DataChannels::new(200);
+        let mut bob = DataChannels::new(200);
+        let photo: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+        alice.send(DEFAULT_CHANNEL, b"halo", false, 0).unwrap();
+        alice.send(DEFAULT_CHANNEL, &photo, true, 0).unwrap();
+        alice.send(DEFAULT_CHANNEL, b"", false, 0).unwrap();
+
+        let mut received = Vec::new();
+        for now in (0..500).step_by(10) {
+            received.extend(exchange(&mut alice, &mut bob, now, |_, _| false));
+            exchange(&mut bob, &mut alice, now, |_, _| false);
+        }
+        let payloads: Vec<(Vec<u8>, bool)> =
+            received.into_iter().map(|message| (message.payload, message.binary)).collect();
+        assert_eq!(payloads, vec![(b"halo".to_vec(), false), (photo, true), (Vec::new(), false)]);
+        assert_eq!(alice.buffered_amount(DEFAULT_CHANNEL), Some(0));
+        assert_eq!(alice.retransmissions(), 0);
+    }
+
+    #[test]
+    fn lost_chunks_are_retransmitted_in_order() {
+        let mut alice = DataChannels::new(100);
+        let mut bob = DataChannels::new(100);
+        for i in 0..20u8 {
+            alice.send(DEFAULT_CHANNEL, &[i; 150], false, 0).unwrap();
+        }
+
+        let mut received = Vec::new();
+        for (round, now) in (0..10_000).step_by(20).enumerate() {
+            // Setiap paket ketiga di ronde genap hilang
+            let lossy = round % 2 == 0;
+            received.extend(exchange(&mut alice, &mut bob, now, |i, _| lossy && i % 3 == 1));
+            exchange(&mut bob, &mut alice, now, |_, _| false);
+        }
+        let payloads: Vec<Vec<u8>> = received.into_iter().map(|message| message.payload).collect();
+        let expected: Vec<Vec<u8>> = (0..20u8).map(|i| vec![i; 150]).collect();
+        assert_eq!(payloads, expected);
+        assert!(alice.retransmissions() > 0);
+        assert_eq!(alice.buffered_amount(DEFAULT_CHANNEL), Some(0));
+    }
+
+    #[test]
+    fn unreliable_channel_skips_lost_messages() {
+        let mut alice = DataChannels::new(100);
+        let mut bob = DataChannels::new(100);
+        alice.open(&unreliable("reactions")).unwrap();
+        bob.open(&unreliable("reactions")).unwrap();
+
+        // Pesan multi-chunk pertama hilang seluruhnya, lalu yang lain lewat
+        alice.send("reactions", &[1; 250], false, 0).unwrap();
+        exchange(&mut alice, &mut bob, 0, |_, _| true);
+        alice.send("reactions", b"heart", false, 10).unwrap();
+        alice.send(DEFAULT_CHANNEL, b"chat", false, 10).unwrap();
+
+        let mut received = Vec::new();
+        for now in (10..5_000).step_by(20) {
+            received.extend(exchange(&mut alice, &mut bob, now, |_, _| false));
+            exchange(&mut bob, &mut alice, now, |_, _| false);
+        }
+        let mut channels: Vec<(&str, &[u8])> = received
+            .iter()
+            .map(|message| (message.channel.as_str(), message.payload.as_slice()))
+            .collect();
+        channels.sort();
+        assert_eq!(channels, vec![("default", &b"chat"[..]), ("reactions", &b"heart"[..])]);
+        assert_eq!(alice.buffered_amount("reactions"), Some(0));
+        assert_eq!(alice.retransmissions(), 0);
+
+        // Chunk tengah pesan hilang; sisa chunk di penerima ikut dibuang
+        alice.send("reactions", &[2; 250], false, 5_000).unwrap();
+        let middle = |_, packet: &[u8]| {
+            matches!(DataPacket::parse(packet), Some(DataPacket::Data { begin: false, end: false, .. }))
+        };
+        for now in (5_000..10_000).step_by(20) {
+            assert!(exchange(&mut alice, &mut bob, now, middle).is_empty());
+            exchange(&mut bob, &mut alice, now, |_, _| false);
+        }
+        assert_eq!(bob.channels[&channel_id("reactions")].received_bytes, 0);
+    }
+
+    #[test]
+    fn limits_and_backpressure() {
+        let mut channels = DataChannels::new(1200);
+        assert_eq!(
+            channels.send(DEFAULT_CHANNEL, &vec![0; MAX_MESSAGE_SIZE + 1], false, 0),
+            Err(DataChannelError::MessageTooLarge {
+                size: MAX_MESSAGE_SIZE + 1,
+                max: MAX_MESSAGE_SIZE,
+            })
+        );
+        for _ in 0..MAX_BUFFERED_BYTES / MAX_MESSAGE_SIZE {
+            channels.send(DEFAULT_CHANNEL, &vec![0; MAX_MESSAGE_SIZE], true, 0).unwrap();
+        }
+        assert!(matches!(
+            channels.send(DEFAULT_CHANNEL, b"x", false, 0),
+            Err(DataChannelError::BufferFull { .. })
+        ));
+
+        // Tanpa ack, hanya congestion window awal yang dikirim
+        let sent = channels.poll(0);
+        assert_eq!(sent.len(), 4);
+        assert!(sent.iter().all(|packet| packet.len() <= 1200));
+        assert!(channels.poll(10).is_empty());
+
+        // Channel lain tidak tertahan buffer yang penuh
+        channels.open(&unreliable("typing")).unwrap();
+        assert_eq!(channels.send("typing", b"1", false, 10), Ok(1));
+        assert_eq!(channels.open(&unreliable("typing")), Err(DataChannelError::ChannelExists("typing".into())));
+        assert!(matches!(
+            channels.open(&DataChannelConfig {
+                max_retransmits: Some(3),
+                reliability: ChannelReliability::ReliableOrdered,
+                ..unreliable("chat")
+            }),
+            Err(DataChannelError::InvalidConfig(_))
+        ));
+        assert!(!channels.close(DEFAULT_CHANNEL));
+        assert!(channels.close("typing"));
+        assert!(DataPacket::parse(&[0x80, 0]).is_none());
+    }
+
+    #[test]
+    fn hostile_peer_cannot_exhaust_receiver() {
+        let mut bob = DataChannels::new(1200);
+        let data = |tsn: u32| {
+            DataPacket::Data {
+                channel: 0,
+                tsn,
+                begin: false,
+                end: false,
+                binary: true,
+                payload: Vec::new(),
+            }
+            .serialize()
+        };
+
+        // TSN jauh di depan tidak disimpan dan ack tidak overflow
+        bob.handle_packet(&data(70_000), 0);
+        bob.handle_packet(&data(70_001), 0);
+        assert!(bob.channels[&0].above.is_empty());
+        assert_eq!(bob.poll(0).len(), 1);
+
+        // Banjir chunk kosong tetap dibatasi window penerima
+        for tsn in 1..RECEIVE_WINDOW_CHUNKS as u32 {
+            bob.handle_packet(&data(tsn), 0);
+        }
+        let channel = &bob.channels[&0];
+        assert!(channel.received_bytes <= RECEIVE_WINDOW);
+        assert!(channel.above.len() * DATA_HEADER_LEN <= RECEIVE_WINDOW);
+        assert!(matches!(
+            DataPacket::parse(&bob.poll(10)[0]),
+            Some(DataPacket::Ack { next_tsn: 0, ref gaps, .. }) if gaps.len() == 1
+        ));
+    }
+
+    #[test]
+    fn inverted_gap_block_is_rejected() {
+        let mut alice = DataChannels::new(1200);
+        alice.send(DEFAULT_CHANNEL, b"halo", false, 0).unwrap();
+        assert_eq!(alice.poll(0).len(), 1);
+
+        let mut ack = DataPacket::Ack { channel: 0, next_tsn: 0, window: 1 << 20, gaps: vec![(0, 0)] }.serialize();
+        let gap = ack.len() - 4;
+        ack[gap..].copy_from_slice(&[0, 5, 0, 2]);
+        assert!(DataPacket::parse(&ack).is_none());
+
+        // Ack rusak diabaikan tanpa panic, chunk tetap menunggu ack
+        assert!(alice.handle_packet(&ack, 10).is_empty());
+        assert_eq!(alice.buffered_amount(DEFAULT_CHANNEL), Some(4));
+    }
+}
@@ -0,0 +1,659 @@
+//! Modul FEC ELARA
+//!
+//! Forward error correction untuk link dengan RTT tinggi, di mana
+//! retransmisi NACK terlalu lambat:
+//! - Video: paket paritas XOR gaya FlexFEC yang melindungi satu grup
+//!   paket media; satu paket hilang per grup bisa dipulihkan.
+//! - Audio: redundansi RED (RFC 2198), paket sebelumnya ikut dikirim
+//!   di dalam paket berikutnya.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use uuid::Uuid;
+
+use crate::rtp::{PacketError, RtpPacket};
+
+/// Payload type paket FEC video
+pub const VIDEO_FEC_PAYLOAD_TYPE: u8 = 118;
+
+/// Payload type RED audio
+pub const AUDIO_RED_PAYLOAD_TYPE: u8 = 63;
+
+/// Header FEC: SSRC media, SN base, mask, PT/marker, timestamp, panjang
+pub const FEC_HEADER_LEN: usize = 15;
+
+/// Ukuran grup maksimum (lebar mask)
+const MAX_GROUP_SIZE: usize = 16;
+
+/// Jumlah paket media yang diingat decoder untuk pemulihan
+const MAX_MEDIA_HISTORY: usize = 512;
+
+/// Jumlah paket FEC yang ditunggu decoder
+const MAX_PENDING_FEC: usize = 64;
+
+/// Loss (persen) di atas ini FEC diaktifkan
+const FEC_ENABLE_LOSS: f32 = 2.0;
+
+/// Loss (persen) di bawah ini FEC dimatikan lagi (histeresis)
+const FEC_DISABLE_LOSS: f32 = 1.0;
+
+/// RTT (ms) di atas ini retransmisi dianggap terlalu lambat
+const HIGH_RTT_MS: u32 = 250;
+
+/// Offset timestamp maksimum blok RED (14 bit)
+const RED_MAX_TIMESTAMP_OFFSET: u32 = 0x3FFF;
+
+/// Panjang blok RED maksimum (10 bit)
+const RED_MAX_BLOCK_LEN: usize = 0x3FF;
+
+/// Pengaturan FEC hasil kebijakan adaptasi
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FecSettings {
+    /// Jumlah paket media per paket FEC video, 0 berarti mati
+    pub video_group_size: usize,
+    /// Jumlah paket audio sebelumnya yang ikut dikirim (RED), 0 berarti mati
+    pub audio_redundancy: usize,
+}
+
+impl FecSettings {
+    /// Pilih pengaturan FEC dari packet loss (persen) dan RTT
+    ///
+    /// FEC aktif saat loss melewati ambang dan mati lagi hanya setelah
+    /// loss turun jauh di bawahnya, agar tidak bolak-balik. Di RTT tinggi
+    /// ambang diturunkan karena retransmisi tidak sempat tiba.
+    pub fn for_network(packet_loss: f32, rtt_ms: u32, current: FecSettings) -> Self {
+        let threshold = if current.is_enabled() || rtt_ms >= HIGH_RTT_MS {
+            FEC_DISABLE_LOSS
+        } else {
+            FEC_ENABLE_LOSS
+        };
+        if packet_loss < threshold {
+            return Self::default();
+        }
+
+        let (video_group_size, audio_redundancy) = match packet_loss {
+            l if l < 5.0 => (10, 1),
+            l if l < 10.0 => (5, 1),
+            l if l < 20.0 => (4, 2),
+            _ => (3, 2),
+        };
+        Self {
+            video_group_size,
+            audio_redundancy,
+        }
+    }
+
+    /// FEC aktif
+    pub fn is_enabled(&self) -> bool {
+        self.video_group_size > 0 || self.audio_redundancy > 0
+    }
+
+    /// Porsi bandwidth yang tersisa untuk video setelah paritas FEC
+    pub fn video_share(&self) -> f64 {
+        match self.video_group_size {
+            0 => 1.0,
+            n => n as f64 / (n + 1) as f64,
+        }
+    }
+
+    /// Overhead RED audio (kbps) untuk bitrate audio tertentu
+    pub fn audio_overhead_kbps(&self, audio_bitrate_kbps: u32) -> u32 {
+        audio_bitrate_kbps * self.audio_redundancy as u32
+    }
+}
+
+/// Akumulator XOR satu grup FEC
+#[derive(Default)]
+struct XorGroup {
+    media_ssrc: u32,
+    base_sequence: u16,
+    mask: u16,
+    pt_marker: u8,
+    timestamp: u32,
+    length: u16,
+    payload: Vec<u8>,
+}
+
+impl XorGroup {
+    fn xor(&mut self, pt_marker: u8, timestamp: u32, payload: &[u8]) {
+        self.pt_marker ^= pt_marker;
+        self.timestamp ^= timestamp;
+        self.length ^= payload.len() as u16;
+        if self.payload.len() < payload.len() {
+            self.payload.resize(payload.len(), 0);
+        }
+        for (acc, byte) in self.payload.iter_mut().zip(payload) {
+            *acc ^= byte;
+        }
+    }
+
+    fn protected(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..MAX_GROUP_SIZE as u16)
+            .filter(|bit| self.mask & (1 << bit) != 0)
+            .map(|bit| self.base_sequence.wrapping_add(bit))
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(FEC_HEADER_LEN + self.payload.len());
+        buf.extend_from_slice(&self.media_ssrc.to_be_bytes());
+        buf.extend_from_slice(&self.base_sequence.to_be_bytes());
+        buf.extend_from_slice(&self.mask.to_be_bytes());
+        buf.push(self.pt_marker);
+        buf.extend_from_slice(&self.timestamp.to_be_bytes());
+        buf.extend_from_slice(&self.length.to_be_bytes());
+        buf.extend_from_slice(&self.payload);
+        buf
+    }
+
+    fn parse(data: &[u8]) -> Result<Self, PacketError> {
+        if data.len() < FEC_HEADER_LEN {
+            return Err(PacketError::Truncated {
+                needed: FEC_HEADER_LEN,
+                actual: data.len(),
+            });
+        }
+
+        Ok(Self {
+            media_ssrc: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            base_sequence: u16::from_be_bytes([data[4], data[5]]),
+            mask: u16::from_be_bytes([data[6], data[7]]),
+            pt_marker: data[8],
+            timestamp: u32::from_be_bytes([data[9], data[10], data[11], data[12]]),
+            length: u16::from_be_bytes([data[13], data[14]]),
+            payload: data[FEC_HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+fn pt_marker(packet: &RtpPacket) -> u8 {
+    ((packet.marker as u8) << 7) | (packet.payload_type & 0x7F)
+}
+
+/// Encoder FEC video (sisi sender)
+pub struct FecEncoder {
+    payload_type: u8,
+    ssrc: u32,
+    sequence_number: u16,
+    group_size: usize,
+    group: XorGroup,
+    count: usize,
+}
+
+impl FecEncoder {
+    /// Buat encoder dengan SSRC dan nomor urut awal acak; FEC mati
+    pub fn new(payload_type: u8) -> Self {
+        let random = Uuid::new_v4().as_u128();
+
+        Self {
+            payload_type,
+            ssrc: random as u32,
+            sequence_number: (random >> 32) as u16,
+            group_size: 0,
+            group: XorGroup::default(),
+            count: 0,
+        }
+    }
+
+    /// Set jumlah paket media per paket FEC, 0 mematikan FEC
+    pub fn set_group_size(&mut self, group_size: usize) {
+        self.group_size = group_size.min(MAX_GROUP_SIZE);
+        if self.group_size == 0 {
+            self.group = XorGroup::default();
+            self.count = 0;
+        }
+    }
+
+    /// Lindungi paket media; kembalikan paket FEC saat grup penuh
+    ///
+    /// Grup juga ditutup di akhir frame (marker) agar paritas tidak
+    /// menunggu frame berikutnya.
+    pub fn protect(&mut self, packet: &RtpPacket) -> Option<RtpPacket> {
+        if self.group_size == 0 {
+            return None;
+        }
+
+        if self.count == 0 {
+            self.group = XorGroup {
+                media_ssrc: packet.ssrc,
+                base_sequence: packet.sequence_number,
+                ..XorGroup::default()
+            };
+        }
+
+        let bit = packet.sequence_number.wrapping_sub(self.group.base_sequence);
+        if bit as usize >= MAX_GROUP_SIZE {
+            // Nomor urut melompat; mulai grup baru dari paket ini
+            self.count = 0;
+            return self.protect(packet);
+        }
+
+        self.group.mask |= 1 << bit;
+        self.group.xor(pt_marker(packet), packet.timestamp, &packet.payload);
+        self.count += 1;
+
+        if self.count < self.group_size && !packet.marker {
+            return None;
+        }
+
+        self.count = 0;
+        let fec = RtpPacket {
+            payload_type: self.payload_type,
+            sequence_number: self.sequence_number,
+            timestamp: packet.timestamp,
+            ssrc: self.ssrc,
+            marker: false,
+            payload: std::mem::take(&mut self.group).serialize(),
+        };
+        self.sequence_number = self.sequence_number.wrapping_add(1);
+        Some(fec)
+    }
+}
+
+/// Decoder FEC video (sisi receiver)
+#[derive(Default)]
+pub struct FecDecoder {
+    /// Paket media per (SSRC, nomor urut); simulcast memakai beberapa SSRC
+    media: HashMap<(u32, u16), RtpPacket>,
+    order: VecDeque<(u32, u16)>,
+    pending: VecDeque<XorGroup>,
+    recovered: u64,
+}
+
+impl FecDecoder {
+    /// Buat decoder baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Jumlah paket yang berhasil dipulihkan
+    pub fn recovered(&self) -> u64 {
+        self.recovered
+    }
+
+    fn remember(&mut self, packet: RtpPacket) {
+        let key = (packet.ssrc, packet.sequence_number);
+        if self.media.insert(key, packet).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > MAX_MEDIA_HISTORY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.media.remove(&oldest);
+            }
+        }
+    }
+
+    /// Catat paket media; kembalikan paket yang kini bisa dipulihkan
+    pub fn push_media(&mut self, packet: &RtpPacket) -> Vec<RtpPacket> {
+        self.remember(packet.clone());
+        self.recover()
+    }
+
+    /// Proses paket FEC; kembalikan paket media yang dipulihkan
+    pub fn push_fec(&mut self, packet: &RtpPacket) -> Result<Vec<RtpPacket>, PacketError> {
+        self.pending.push_back(XorGroup::parse(&packet.payload)?);
+        while self.pending.len() > MAX_PENDING_FEC {
+            self.pending.pop_front();
+        }
+        Ok(self.recover())
+    }
+
+    fn recover(&mut self) -> Vec<RtpPacket> {
+        let mut recovered = Vec::new();
+
+        loop {
+            let mut progress = false;
+            let mut index = 0;
+
+            while index < self.pending.len() {
+                let group = &self.pending[index];
+                let missing: Vec<u16> = group
+                    .protected()
+                    .filter(|&sequence| !self.media.contains_key(&(group.media_ssrc, sequence)))
+                    .collect();
+
+                match missing.as_slice() {
+                    // Semua paket sudah ada, paritas tidak dibutuhkan lagi
+                    [] => {
+                        self.pending.remove(index);
+                    }
+                    [sequence] => {
+                        if let Some(group) = self.pending.remove(index) {
+                            if let Some(packet) = self.rebuild(group, *sequence) {
+                                self.remember(packet.clone());
+                                recovered.push(packet);
+                                self.recovered += 1;
+                                progress = true;
+                            }
+                        }
+                    }
+                    _ => index += 1,
+                }
+            }
+
+            if !progress {
+                return recovered;
+            }
+        }
+    }
+
+    fn rebuild(&self, mut group: XorGroup, sequence: u16) -> Option<RtpPacket> {
+        for protected in group.protected().collect::<Vec<_>>() {
+            if let Some(packet) = self.media.get(&(group.media_ssrc, protected)) {
+                group.xor(pt_marker(packet), packet.timestamp, &packet.payload);
+            }
+        }
+
+        let length = group.length as usize;
+        if length > group.payload.len() {
+            return None;
+        }
+        group.payload.truncate(length);
+
+        Some(RtpPacket {
+            payload_type: group.pt_marker & 0x7F,
+            sequence_number: sequence,
+            timestamp: group.timestamp,
+            ssrc: group.media_ssrc,
+            marker: group.pt_marker & 0x80 != 0,
+            payload: group.payload,
+        })
+    }
+}
+
+/// Encoder RED audio (sisi sender)
+pub struct RedEncoder {
+    payload_type: u8,
+    redundancy: usize,
+    history: VecDeque<(u32, u8, Vec<u8>)>,
+}
+
+impl RedEncoder {
+    /// Buat encoder RED; redundansi mati
+    pub fn new(payload_type: u8) -> Self {
+        Self {
+            payload_type,
+            redundancy: 0,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Set jumlah paket sebelumnya yang ikut dikirim, 0 mematikan RED
+    pub fn set_redundancy(&mut self, redundancy: usize) {
+        self.redundancy = redundancy;
+        while self.history.len() > redundancy {
+            self.history.pop_front();
+        }
+    }
+
+    /// Bungkus paket menjadi RED berisi paket ini dan paket sebelumnya
+    ///
+    /// Hanya paket berurutan tepat sebelum paket ini yang disertakan,
+    /// sehingga receiver bisa menurunkan nomor urut tiap blok.
+    pub fn wrap(&mut self, packet: &mut RtpPacket) {
+        if self.redundancy == 0 {
+            return;
+        }
+
+        let blocks: Vec<&(u32, u8, Vec<u8>)> = self
+            .history
+            .iter()
+            .rev()
+            .take_while(|(timestamp, _, data)| {
+                packet.timestamp.wrapping_sub(*timestamp) <= RED_MAX_TIMESTAMP_OFFSET
+                    && data.len() <= RED_MAX_BLOCK_LEN
+            })
+            .collect();
+
+        let mut payload = Vec::new();
+        for (timestamp, payload_type, data) in blocks.iter().rev() {
+            let offset = packet.timestamp.wrapping_sub(*timestamp);
+            payload.push(0x80 | payload_type);
+            payload.push((offset >> 6) as u8);
+            payload.push(((offset << 2) as u8) | (data.len() >> 8) as u8);
+            payload.push(data.len() as u8);
+        }
+        payload.push(packet.payload_type & 0x7F);
+        for (_, _, data) in blocks.iter().rev() {
+            payload.extend_from_slice(data);
+        }
+        payload.extend_from_slice(&packet.payload);
+
+        self.history
+            .push_back((packet.timestamp, packet.payload_type, packet.payload.clone()));
+        while self.history.len() > self.redundancy {
+            self.history.pop_front();
+        }
+
+        packet.payload_type = self.payload_type;
+        packet.payload = payload;
+    }
+}
+
+/// Decoder RED audio (sisi receiver)
+pub struct RedDecoder {
+    payload_type: u8,
+    received: HashSet<u16>,
+    order: VecDeque<u16>,
+    recovered: u64,
+}
+
+impl RedDecoder {
+    /// Buat decoder RED
+    pub fn new(payload_type: u8) -> Self {
+        Self {
+            payload_type,
+            received: HashSet::new(),
+            order: VecDeque::new(),
+            recovered: 0,
+        }
+    }
+
+    /// Jumlah paket yang dipulihkan dari blok redundan
+    pub fn recovered(&self) -> u64 {
+        self.recovered
+    }
+
+    fn mark_received(&mut self, sequence: u16) -> bool {
+        if !self.received.insert(sequence) {
+            return false;
+        }
+        self.order.push_back(sequence);
+        while self.order.len() > MAX_MEDIA_HISTORY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.received.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Buka paket; paket non-RED dikembalikan apa adanya
+    ///
+    /// Blok redundan hanya dikembalikan untuk paket yang belum diterima.
+    pub fn depacketize(&mut self, packet: RtpPacket) -> Result<Vec<RtpPacket>, PacketError> {
+        if packet.payload_type != self.payload_type {
+            self.mark_received(packet.sequence_number);
+            return Ok(vec![packet]);
+        }
+
+        let data = &packet.payload;
+        let mut headers = Vec::new();
+        let mut offset = 0;
+        loop {
+            let first = *data.get(offset).ok_or(PacketError::Truncated {
+                needed: offset + 1,
+                actual: data.len(),
+            })?;
+            if first & 0x80 == 0 {
+                offset += 1;
+                break;
+            }
+            let header = data.get(offset..offset + 4).ok_or(PacketError::Truncated {
+                needed: offset + 4,
+                actual: data.len(),
+            })?;
+            let timestamp_offset = ((header[1] as u32) << 6) | (header[2] as u32 >> 2);
+            let length = (((header[2] & 0x03) as usize) << 8) | header[3] as usize;
+            headers.push((first & 0x7F, timestamp_offset, length));
+            offset += 4;
+        }
+        let primary_type = data[offset - 1] & 0x7F;
+
+        let count = headers.len();
+        let mut packets = Vec::new();
+        for (index, (payload_type, timestamp_offset, length)) in headers.into_iter().enumerate() {
+            let block = data.get(offset..offset + length).ok_or(PacketError::Truncated {
+                needed: offset + length,
+                actual: data.len(),
+            })?;
+            offset += length;
+
+            let sequence_number = packet.sequence_number.wrapping_sub((count - index) as u16);
+            if self.mark_received(sequence_number) {
+                self.recovered += 1;
+                packets.push(RtpPacket {
+                    payload_type,
+                    sequence_number,
+                    timestamp: packet.timestamp.wrapping_sub(timestamp_offset),
+                    ssrc: packet.ssrc,
+                    marker: false,
+                    payload: block.to_vec(),
+                });
+            }
+        }
+
+        if self.mark_received(packet.sequence_number) {
+            packets.push(RtpPacket {
+                payload_type: primary_type,
+                payload: data[offset..].to_vec(),
+                ..packet
+            });
+        }
+
+        Ok(packets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(sequence_number: u16, timestamp: u32, marker: bool, len: usize) -> RtpPacket {
+        RtpPacket {
+            payload_type: 96,
+            sequence_number,
+            timestamp,
+            ssrc: 0xABCD,
+            marker,
+            payload: (0..len).map(|i| sequence_number.wrapping_add(i as u16) as u8).collect(),
+        }
+    }
+
+    #[test]
+    fn xor_fec_recovers_single_loss_per_group() {
+        let mut encoder = FecEncoder::new(VIDEO_FEC_PAYLOAD_TYPE);
+        encoder.set_group_size(4);
+        let media: Vec<RtpPacket> = (0..4)
+            .map(|i| packet(65534u16.wrapping_add(i), 9000, i == 3, 100 + i as usize * 7))
+            .collect();
+
+        let fec: Vec<RtpPacket> = media.iter().filter_map(|p| encoder.protect(p)).collect();
+        assert_eq!(fec.len(), 1);
+
+        let mut decoder = FecDecoder::new();
+        for (i, p) in media.iter().enumerate() {
+            if i != 2 {
+                assert!(decoder.push_media(p).is_empty());
+            }
+        }
+        let recovered = decoder.push_fec(&fec[0]).unwrap();
+        assert_eq!(recovered, vec![media[2].clone()]);
+        assert_eq!(decoder.recovered(), 1);
+
+        // Paket terakhir (marker) juga bisa dipulihkan lewat paket media
+        let mut decoder = FecDecoder::new();
+        decoder.push_fec(&fec[0]).unwrap();
+        for p in &media[..3] {
+            decoder.push_media(p);
+        }
+        assert_eq!(decoder.recovered(), 1);
+    }
+
+    #[test]
+    fn colliding_sequence_numbers_on_other_ssrc_are_ignored() {
+        let stream = |ssrc: u32, encoder: &mut FecEncoder| {
+            let media: Vec<RtpPacket> = (0..4)
+                .map(|i| RtpPacket { ssrc, ..packet(10 + i, ssrc * 3000, i == 3, 40 + ssrc as usize * 9) })
+                .collect();
+            let fec = media.iter().filter_map(|p| encoder.protect(p)).collect::<Vec<_>>();
+            (media, fec)
+        };
+        let mut encoder = FecEncoder::new(VIDEO_FEC_PAYLOAD_TYPE);
+        encoder.set_group_size(4);
+        let (low, low_fec) = stream(1, &mut encoder);
+        let (high, high_fec) = stream(2, &mut encoder);
+
+        // Layer rendah kehilangan SN 12, layer tinggi kehilangan SN 13
+        let mut decoder = FecDecoder::new();
+        for p in low.iter().filter(|p| p.sequence_number != 12) {
+            decoder.push_media(p);
+        }
+        for p in high.iter().filter(|p| p.sequence_number != 13) {
+            decoder.push_media(p);
+        }
+
+        assert_eq!(decoder.push_fec(&low_fec[0]).unwrap(), vec![low[2].clone()]);
+        assert_eq!(decoder.push_fec(&high_fec[0]).unwrap(), vec![high[3].clone()]);
+        assert_eq!(decoder.recovered(), 2);
+    }
+
+    #[test]
+    fn fec_group_closes_on_frame_end() {
+        let mut encoder = FecEncoder::new(VIDEO_FEC_PAYLOAD_TYPE);
+        encoder.set_group_size(10);
+        assert!(encoder.protect(&packet(1, 0, false, 50)).is_none());
+        assert!(encoder.protect(&packet(2, 0, true, 50)).is_some());
+
+        encoder.set_group_size(0);
+        assert!(encoder.protect(&packet(3, 0, true, 50)).is_none());
+    }
+
+    #[test]
+    fn red_recovers_previous_packets() {
+        let mut encoder = RedEncoder::new(AUDIO_RED_PAYLOAD_TYPE);
+        encoder.set_redundancy(2);
+        let original: Vec<RtpPacket> = (0..4).map(|i| packet(100 + i, i as u32 * 960, false, 60)).collect();
+        let wrapped: Vec<RtpPacket> = original
+            .iter()
+            .cloned()
+            .map(|mut p| {
+                encoder.wrap(&mut p);
+                p
+            })
+            .collect();
+        assert!(wrapped.iter().all(|p| p.payload_type == AUDIO_RED_PAYLOAD_TYPE));
+
+        // Paket 101 dan 102 hilang, keduanya ada di paket 103
+        let mut decoder = RedDecoder::new(AUDIO_RED_PAYLOAD_TYPE);
+        assert_eq!(decoder.depacketize(wrapped[0].clone()).unwrap(), vec![original[0].clone()]);
+        let mut out = decoder.depacketize(wrapped[3].clone()).unwrap();
+        out.sort_by_key(|p| p.sequence_number);
+        assert_eq!(out, original[1..].to_vec());
+        assert_eq!(decoder.recovered(), 2);
+
+        // Paket telat yang sudah dipulihkan tidak dikeluarkan lagi
+        assert!(decoder.depacketize(wrapped[2].clone()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn policy_has_hysteresis_and_lower_threshold_on_high_rtt() {
+        let off = FecSettings::default();
+        assert!(!FecSettings::for_network(1.5, 100, off).is_enabled());
+        assert!(FecSettings::for_network(1.5, 400, off).is_enabled());
+
+        let on = FecSettings::for_network(3.0, 100, off);
+        assert_eq!(on.video_group_size, 10);
+        assert!(FecSettings::for_network(1.5, 100, on).is_enabled());
+        assert!(!FecSettings::for_network(0.5, 100, on).is_enabled());
+        assert!(FecSettings::for_network(25.0, 100, on).video_share() < on.video_share());
+    }
+}
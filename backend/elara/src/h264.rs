@@ -0,0 +1,630 @@
+//! Modul H.264 ELARA
+//!
+//! Payload format RTP untuk H.264 (RFC 6184, packetization-mode 1):
+//! single NAL unit, agregasi STAP-A dan fragmentasi FU-A.
+
+use crate::rtp::{Depayloader, FrameInfo, PacketError, Payloader, RtpPacket};
+
+const NAL_TYPE_MASK: u8 = 0x1F;
+const NAL_REF_IDC_MASK: u8 = 0x60;
+const NAL_FORBIDDEN_MASK: u8 = 0x80;
+
+const NAL_SLICE: u8 = 1;
+const NAL_IDR: u8 = 5;
+const NAL_SPS: u8 = 7;
+const NAL_AUD: u8 = 9;
+const NAL_FILLER: u8 = 12;
+const STAP_A: u8 = 24;
+const FU_A: u8 = 28;
+
+const FU_START: u8 = 0x80;
+const FU_END: u8 = 0x40;
+
+const ANNEX_B_START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+/// Pecah stream Annex-B menjadi NAL unit (tanpa start code)
+pub fn split_annex_b(data: &[u8]) -> Vec<&[u8]> {
+    let mut nals = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut i = 0;
+
+    while i + 2 < data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            if let Some(s) = start {
+                // Start code 4 byte: nol di depan milik start code, bukan NAL
+                let mut end = i;
+                while end > s && data[end - 1] == 0 {
+                    end -= 1;
+                }
+                if end > s {
+                    nals.push(&data[s..end]);
+                }
+            }
+            i += 3;
+            start = Some(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    match start {
+        Some(s) if s < data.len() => nals.push(&data[s..]),
+        // Tanpa start code: anggap satu NAL utuh
+        None if !data.is_empty() => nals.push(data),
+        _ => {}
+    }
+
+    nals
+}
+
+/// Cek apakah access unit Annex-B berisi IDR (keyframe)
+pub fn is_h264_keyframe(access_unit: &[u8]) -> bool {
+    split_annex_b(access_unit)
+        .iter()
+        .any(|nal| matches!(nal[0] & NAL_TYPE_MASK, NAL_IDR | NAL_SPS))
+}
+
+/// Cek apakah payload RTP H.264 membuka keyframe (tanpa menyusun frame)
+///
+/// Dipakai relay untuk mencari titik switch layer: NAL IDR/SPS tunggal,
+/// STAP-A yang berisi IDR/SPS, atau fragment FU-A pertama dari IDR.
+pub fn is_h264_keyframe_packet(payload: &[u8]) -> bool {
+    let Some(&first) = payload.first() else {
+        return false;
+    };
+
+    match first & NAL_TYPE_MASK {
+        STAP_A => {
+            let mut offset = 1;
+            while offset + 2 < payload.len() {
+                let size = u16::from_be_bytes([payload[offset], payload[offset + 1]]) as usize;
+                offset += 2;
+                if size == 0 || offset + size > payload.len() {
+                    return false;
+                }
+                if matches!(payload[offset] & NAL_TYPE_MASK, NAL_IDR | NAL_SPS) {
+                    return true;
+                }
+                offset += size;
+            }
+            false
+        }
+        FU_A => payload
+            .get(1)
+            .is_some_and(|&header| header & FU_START != 0 && header & NAL_TYPE_MASK == NAL_IDR),
+        nal_type => matches!(nal_type, NAL_IDR | NAL_SPS),
+    }
+}
+
+/// Cek apakah payload RTP membawa NAL pertama sebuah access unit
+///
+/// Yaitu AUD, SPS, atau slice non-IDR dengan first_mb_in_slice 0 (bit
+/// pertama ue(v) bernilai 1). IDR selalu didahului parameter set dari
+/// encoder, jadi IDR tanpa SPS di depannya berarti awal AU hilang.
+fn starts_access_unit(payload: &[u8]) -> bool {
+    let (nal_type, body) = match payload.first().map(|&b| b & NAL_TYPE_MASK) {
+        Some(STAP_A) => (payload.get(3).map(|&b| b & NAL_TYPE_MASK), payload.get(4)),
+        Some(FU_A) => match payload.get(1) {
+            Some(&header) if header & FU_START != 0 => {
+                (Some(header & NAL_TYPE_MASK), payload.get(2))
+            }
+            _ => (None, None),
+        },
+        nal_type => (nal_type, payload.get(1)),
+    };
+
+    match nal_type {
+        Some(NAL_AUD | NAL_SPS) => true,
+        Some(NAL_SLICE) => body.is_some_and(|&b| b & 0x80 != 0),
+        _ => false,
+    }
+}
+
+/// Payloader H.264
+#[derive(Default)]
+pub struct H264Payloader;
+
+impl H264Payloader {
+    /// Buat payloader baru
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn flush_aggregate(aggregate: &mut Vec<&[u8]>, out: &mut Vec<Vec<u8>>) {
+        match aggregate.len() {
+            0 => {}
+            1 => out.push(aggregate[0].to_vec()),
+            _ => {
+                let nri = aggregate
+                    .iter()
+                    .map(|nal| nal[0] & NAL_REF_IDC_MASK)
+                    .max()
+                    .unwrap_or(0);
+                let forbidden = aggregate
+                    .iter()
+                    .fold(0, |acc, nal| acc | (nal[0] & NAL_FORBIDDEN_MASK));
+
+                let mut payload = vec![forbidden | nri | STAP_A];
+                for nal in aggregate.iter() {
+                    payload.extend_from_slice(&(nal.len() as u16).to_be_bytes());
+                    payload.extend_from_slice(nal);
+                }
+                out.push(payload);
+            }
+        }
+        aggregate.clear();
+    }
+
+    fn fragment(nal: &[u8], max_payload: usize, out: &mut Vec<Vec<u8>>) {
+        let indicator = (nal[0] & (NAL_FORBIDDEN_MASK | NAL_REF_IDC_MASK)) | FU_A;
+        let nal_type = nal[0] & NAL_TYPE_MASK;
+        let body = &nal[1..];
+        let chunk_size = max_payload - 2;
+        let chunks = body.chunks(chunk_size).count();
+
+        for (i, chunk) in body.chunks(chunk_size).enumerate() {
+            let mut header = nal_type;
+            if i == 0 {
+                header |= FU_START;
+            }
+            if i + 1 == chunks {
+                header |= FU_END;
+            }
+
+            let mut payload = Vec::with_capacity(chunk.len() + 2);
+            payload.push(indicator);
+            payload.push(header);
+            payload.extend_from_slice(chunk);
+            out.push(payload);
+        }
+    }
+}
+
+impl Payloader for H264Payloader {
+    fn payload(&mut self, frame: &[u8], max_payload: usize) -> Result<Vec<Vec<u8>>, PacketError> {
+        // FU-A butuh minimal 2 byte header + 1 byte data
+        if max_payload < 3 {
+            return Err(PacketError::MtuTooSmall(max_payload));
+        }
+
+        let mut out = Vec::new();
+        let mut aggregate: Vec<&[u8]> = Vec::new();
+        // Header STAP-A (1 byte) + panjang NAL (2 byte)
+        let mut aggregate_len = 1;
+
+        for nal in split_annex_b(frame) {
+            let nal_type = nal[0] & NAL_TYPE_MASK;
+            if nal_type == NAL_AUD || nal_type == NAL_FILLER {
+                continue;
+            }
+
+            if nal.len() > max_payload {
+                Self::flush_aggregate(&mut aggregate, &mut out);
+                aggregate_len = 1;
+                Self::fragment(nal, max_payload, &mut out);
+                continue;
+            }
+
+            if aggregate_len + 2 + nal.len() > max_payload {
+                Self::flush_aggregate(&mut aggregate, &mut out);
+                aggregate_len = 1;
+            }
+
+            aggregate.push(nal);
+            aggregate_len += 2 + nal.len();
+        }
+
+        Self::flush_aggregate(&mut aggregate, &mut out);
+        Ok(out)
+    }
+}
+
+/// Depayloader H.264 - menyusun access unit Annex-B dari paket RTP
+#[derive(Default)]
+pub struct H264Depayloader {
+    access_unit: Vec<u8>,
+    fragment: Option<Vec<u8>>,
+    timestamp: Option<u32>,
+    last_sequence: Option<u16>,
+    corrupted: bool,
+    lost_frames: u64,
+    last_keyframe: bool,
+}
+
+impl H264Depayloader {
+    /// Buat depayloader baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push_nal(&mut self, nal: &[u8]) {
+        self.access_unit.extend_from_slice(&ANNEX_B_START_CODE);
+        self.access_unit.extend_from_slice(nal);
+    }
+
+    fn handle_payload(&mut self, payload: &[u8]) -> Result<(), PacketError> {
+        if payload.is_empty() {
+            return Err(PacketError::InvalidPayload("payload H.264 kosong".to_string()));
+        }
+
+        match payload[0] & NAL_TYPE_MASK {
+            1..=23 => {
+                if self.fragment.take().is_some() {
+                    self.corrupted = true;
+                }
+                self.push_nal(payload);
+            }
+            STAP_A => {
+                let mut offset = 1;
+                while offset < payload.len() {
+                    if offset + 2 > payload.len() {
+                        return Err(PacketError::Truncated { needed: offset + 2, actual: payload.len() });
+                    }
+                    let size = u16::from_be_bytes([payload[offset], payload[offset + 1]]) as usize;
+                    offset += 2;
+                    if size == 0 || offset + size > payload.len() {
+                        return Err(PacketError::InvalidPayload("ukuran NAL STAP-A tidak valid".to_string()));
+                    }
+                    self.push_nal(&payload[offset..offset + size]);
+                    offset += size;
+                }
+            }
+            FU_A => {
+                if payload.len() < 2 {
+                    return Err(PacketError::Truncated { needed: 2, actual: payload.len() });
+                }
+                let indicator = payload[0];
+                let header = payload[1];
+
+                if header & FU_START != 0 {
+                    if self.fragment.is_some() {
+                        // Fragment sebelumnya tidak pernah selesai
+                        self.corrupted = true;
+                    }
+                    let nal_header = (indicator & (NAL_FORBIDDEN_MASK | NAL_REF_IDC_MASK))
+                        | (header & NAL_TYPE_MASK);
+                    let mut nal = vec![nal_header];
+                    nal.extend_from_slice(&payload[2..]);
+                    self.fragment = Some(nal);
+                } else {
+                    match self.fragment.as_mut() {
+                        Some(nal) => nal.extend_from_slice(&payload[2..]),
+                        None => {
+                            // Awal fragment hilang
+                            self.corrupted = true;
+                            return Ok(());
+                        }
+                    }
+                }
+
+                if header & FU_END != 0 {
+                    if let Some(nal) = self.fragment.take() {
+                        self.push_nal(&nal);
+                    }
+                }
+            }
+            other => {
+                return Err(PacketError::InvalidPayload(format!("tipe NAL {} tidak didukung", other)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Depayloader for H264Depayloader {
+    fn push(&mut self, packet: &RtpPacket) -> Result<Option<Vec<u8>>, PacketError> {
+        let missing = self
+            .last_sequence
+            .map_or(0, |last| packet.sequence_number.wrapping_sub(last).wrapping_sub(1));
+        self.last_sequence = Some(packet.sequence_number);
+
+        if self.timestamp != Some(packet.timestamp) {
+            // Access unit baru; sisa AU lama tanpa marker dibuang
+            let open = self.timestamp.is_some();
+            if open || self.corrupted {
+                self.lost_frames += 1;
+                self.access_unit.clear();
+            }
+            self.fragment = None;
+            // Satu paket hilang pada AU yang belum selesai adalah marker-nya.
+            // Selebihnya bisa berupa frame utuh yang hilang, jadi AU baru
+            // hanya rusak jika NAL pertamanya sendiri tidak ada
+            let missing_from_new = if open { missing.saturating_sub(1) } else { missing };
+            self.corrupted = missing_from_new > 0 && !starts_access_unit(&packet.payload);
+            self.timestamp = Some(packet.timestamp);
+        } else if missing > 0 {
+            self.corrupted = true;
+            self.fragment = None;
+        }
+
+        let result = self.handle_payload(&packet.payload);
+        if let Err(err) = result {
+            self.corrupted = true;
+            return Err(err);
+        }
+
+        if !packet.marker {
+            return Ok(None);
+        }
+
+        let timestamp = packet.timestamp;
+        let incomplete = self.corrupted || self.fragment.is_some();
+        let access_unit = std::mem::take(&mut self.access_unit);
+        self.fragment = None;
+        self.corrupted = false;
+        self.timestamp = None;
+
+        if incomplete {
+            self.lost_frames += 1;
+            Err(PacketError::IncompleteFrame { timestamp })
+        } else {
+            self.last_keyframe = is_h264_keyframe(&access_unit);
+            Ok(Some(access_unit))
+        }
+    }
+
+    fn reset(&mut self) {
+        self.access_unit.clear();
+        self.fragment = None;
+        self.timestamp = None;
+        self.corrupted = false;
+    }
+
+    fn lost_frames(&self) -> u64 {
+        self.lost_frames
+    }
+
+    fn last_frame_info(&self) -> FrameInfo {
+        FrameInfo {
+            keyframe: self.last_keyframe,
+            ..FrameInfo::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtp::{Packetizer, RTP_HEADER_LEN};
+
+    const NAL_PPS: u8 = 8;
+
+    // SPS/PPS dari stream x264 baseline 320x240
+    const SPS: [u8; 24] = [
+        0x67, 0x42, 0xc0, 0x0d, 0xd9, 0x01, 0x41, 0xfb, 0x01, 0x10, 0x00, 0x00, 0x03, 0x00, 0x10,
+        0x00, 0x00, 0x03, 0x03, 0xc0, 0xf1, 0x42, 0x99, 0x60,
+    ];
+    const PPS: [u8; 5] = [0x68, 0xcb, 0x83, 0xcb, 0x20];
+
+    /// Slice dengan header NAL asli dan isi deterministik
+    fn slice(header: &[u8], len: usize) -> Vec<u8> {
+        let mut nal = header.to_vec();
+        let mut state: u32 = 0x1234_5678;
+        while nal.len() < len {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            // Hindari emulasi start code di dalam slice
+            nal.push(((state >> 16) as u8) | 0x01);
+        }
+        nal
+    }
+
+    fn annex_b(nals: &[&[u8]], long_start_code: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        for nal in nals {
+            if long_start_code {
+                out.extend_from_slice(&ANNEX_B_START_CODE);
+            } else {
+                out.extend_from_slice(&[0, 0, 1]);
+            }
+            out.extend_from_slice(nal);
+        }
+        out
+    }
+
+    /// Stream Annex-B Baseline 32x32 (AUD+SPS+PPS+IDR, P skip, P dengan
+    /// I_PCM) dari `tests/data/gen_h264_fixture.py`
+    const FIXTURE: &[u8] = include_bytes!("../tests/data/h264_baseline_32x32.h264");
+
+    /// Pecah fixture menjadi access unit di setiap AUD
+    fn fixture_access_units() -> Vec<Vec<u8>> {
+        let mut frames: Vec<Vec<u8>> = Vec::new();
+        for nal in split_annex_b(FIXTURE) {
+            if nal[0] & NAL_TYPE_MASK == NAL_AUD {
+                frames.push(Vec::new());
+            }
+            frames.last_mut().unwrap().extend(annex_b(&[nal], true));
+        }
+        frames
+    }
+
+    fn idr_access_unit() -> Vec<u8> {
+        let idr = slice(&[0x65, 0x88, 0x84, 0x00], 5000);
+        annex_b(&[&[0x09, 0xf0], &SPS, &PPS, &idr], true)
+    }
+
+    fn round_trip(frames: &[Vec<u8>], mtu: usize) -> Vec<Result<Option<Vec<u8>>, PacketError>> {
+        let mut packetizer = Packetizer::new(102, Box::new(H264Payloader::new()));
+        packetizer.set_mtu(mtu);
+        let mut depayloader = H264Depayloader::new();
+
+        let mut results = Vec::new();
+        for (i, frame) in frames.iter().enumerate() {
+            for packet in packetizer.packetize(frame, i as u32 * 3000).unwrap() {
+                assert!(packet.serialize().len() <= mtu);
+                let parsed = RtpPacket::parse(&packet.serialize()).unwrap();
+                results.push(depayloader.push(&parsed));
+            }
+        }
+        results
+    }
+
+    fn without_aud(frame: &[u8]) -> Vec<u8> {
+        let nals: Vec<&[u8]> = split_annex_b(frame)
+            .into_iter()
+            .filter(|nal| nal[0] & NAL_TYPE_MASK != NAL_AUD)
+            .collect();
+        annex_b(&nals, true)
+    }
+
+    #[test]
+    fn split_handles_three_and_four_byte_start_codes() {
+        let mut stream = annex_b(&[&SPS], true);
+        stream.extend(annex_b(&[&PPS], false));
+        let nals = split_annex_b(&stream);
+        assert_eq!(nals, vec![&SPS[..], &PPS[..]]);
+    }
+
+    #[test]
+    fn parameter_sets_are_aggregated_into_stap_a() {
+        let mut payloader = H264Payloader::new();
+        let payloads = payloader.payload(&annex_b(&[&SPS, &PPS], true), 1188).unwrap();
+
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0][0] & NAL_TYPE_MASK, STAP_A);
+        assert_eq!(payloads[0][0] & NAL_REF_IDC_MASK, 0x60);
+    }
+
+    #[test]
+    fn small_nal_uses_single_nal_mode() {
+        let p_slice = slice(&[0x41, 0x9a, 0x02], 600);
+        let mut payloader = H264Payloader::new();
+        let payloads = payloader.payload(&annex_b(&[&p_slice], false), 1188).unwrap();
+
+        assert_eq!(payloads, vec![p_slice]);
+    }
+
+    #[test]
+    fn large_idr_is_fragmented_with_fu_a() {
+        let mut payloader = H264Payloader::new();
+        let payloads = payloader.payload(&idr_access_unit(), 1200 - RTP_HEADER_LEN).unwrap();
+
+        let fragments: Vec<_> = payloads
+            .iter()
+            .filter(|p| p[0] & NAL_TYPE_MASK == FU_A)
+            .collect();
+        assert!(fragments.len() >= 5);
+        assert_ne!(fragments[0][1] & FU_START, 0);
+        assert_ne!(fragments.last().unwrap()[1] & FU_END, 0);
+        assert!(payloads.iter().all(|p| p.len() <= 1200 - RTP_HEADER_LEN));
+    }
+
+    #[test]
+    fn stream_round_trips_through_rtp() {
+        let frames = vec![
+            idr_access_unit(),
+            annex_b(&[&slice(&[0x41, 0x9a, 0x02], 900)], true),
+            annex_b(&[&slice(&[0x41, 0x9a, 0x04], 2600)], false),
+        ];
+
+        for mtu in [1200, 576, 300] {
+            let output: Vec<Vec<u8>> = round_trip(&frames, mtu)
+                .into_iter()
+                .filter_map(|r| r.unwrap())
+                .collect();
+
+            assert_eq!(output.len(), frames.len());
+            for (frame, decoded) in frames.iter().zip(output.iter()) {
+                assert_eq!(decoded, &without_aud(frame));
+            }
+            assert!(is_h264_keyframe(&output[0]));
+            assert!(!is_h264_keyframe(&output[1]));
+        }
+    }
+
+    #[test]
+    fn encoder_stream_round_trips_through_rtp() {
+        let frames = fixture_access_units();
+        let types: Vec<Vec<u8>> = frames
+            .iter()
+            .map(|frame| split_annex_b(frame).iter().map(|nal| nal[0] & NAL_TYPE_MASK).collect())
+            .collect();
+        let p_frame = vec![NAL_AUD, NAL_SLICE];
+        assert_eq!(types, vec![vec![NAL_AUD, NAL_SPS, NAL_PPS, NAL_IDR], p_frame.clone(), p_frame]);
+
+        for mtu in [1200, 576, 300] {
+            let output: Vec<Vec<u8>> = round_trip(&frames, mtu)
+                .into_iter()
+                .filter_map(|r| r.unwrap())
+                .collect();
+
+            let expected: Vec<Vec<u8>> = frames.iter().map(|frame| without_aud(frame)).collect();
+            assert_eq!(output, expected);
+            let keyframes: Vec<bool> = output.iter().map(|frame| is_h264_keyframe(frame)).collect();
+            assert_eq!(keyframes, vec![true, false, false]);
+        }
+    }
+
+    #[test]
+    fn missing_fragment_is_detected() {
+        let mut packetizer = Packetizer::new(102, Box::new(H264Payloader::new()));
+        let mut packets = packetizer.packetize(&idr_access_unit(), 0).unwrap();
+        packets.remove(3);
+
+        let mut depayloader = H264Depayloader::new();
+        let mut results: Vec<_> = packets.iter().map(|p| depayloader.push(p)).collect();
+
+        assert_eq!(results.pop().unwrap(), Err(PacketError::IncompleteFrame { timestamp: 0 }));
+        assert!(results.iter().all(|r| r == &Ok(None)));
+        assert_eq!(depayloader.lost_frames(), 1);
+
+        // Frame berikutnya tetap bisa disusun
+        let next = annex_b(&[&slice(&[0x41, 0x9a, 0x02], 400)], true);
+        let packets = packetizer.packetize(&next, 3000).unwrap();
+        assert_eq!(depayloader.push(&packets[0]), Ok(Some(next)));
+    }
+
+    #[test]
+    fn lost_marker_packet_reports_previous_frame() {
+        let mut packetizer = Packetizer::new(102, Box::new(H264Payloader::new()));
+        let mut first = packetizer.packetize(&idr_access_unit(), 0).unwrap();
+        first.pop();
+        let second_frame = annex_b(&[&slice(&[0x41, 0x9a, 0x02], 400)], true);
+        let second = packetizer.packetize(&second_frame, 3000).unwrap();
+
+        let mut depayloader = H264Depayloader::new();
+        for packet in &first {
+            assert_eq!(depayloader.push(packet), Ok(None));
+        }
+        assert_eq!(depayloader.push(&second[0]), Ok(Some(second_frame)));
+        assert_eq!(depayloader.lost_frames(), 1);
+    }
+
+    #[test]
+    fn lost_first_packet_marks_new_frame_incomplete() {
+        let mut packetizer = Packetizer::new(102, Box::new(H264Payloader::new()));
+        let first_frame = annex_b(&[&slice(&[0x41, 0x9a, 0x02], 400)], true);
+        let first = packetizer.packetize(&first_frame, 0).unwrap();
+        // STAP-A berisi SPS/PPS milik IDR hilang
+        let mut idr = packetizer.packetize(&idr_access_unit(), 3000).unwrap();
+        assert_eq!(idr[0].payload[0] & NAL_TYPE_MASK, STAP_A);
+        idr.remove(0);
+
+        let mut depayloader = H264Depayloader::new();
+        assert_eq!(depayloader.push(&first[0]), Ok(Some(first_frame)));
+        let mut results: Vec<_> = idr.iter().map(|p| depayloader.push(p)).collect();
+
+        assert_eq!(results.pop().unwrap(), Err(PacketError::IncompleteFrame { timestamp: 3000 }));
+        assert!(results.iter().all(|r| r == &Ok(None)));
+        assert_eq!(depayloader.lost_frames(), 1);
+    }
+
+    #[test]
+    fn complete_frame_after_lost_frame_is_kept() {
+        let mut packetizer = Packetizer::new(102, Box::new(H264Payloader::new()));
+        let p_frame = |n: u8| annex_b(&[&slice(&[0x41, 0x9a, n], 2600)], true);
+        let first = packetizer.packetize(&p_frame(2), 0).unwrap();
+        // Frame kedua hilang seluruhnya
+        packetizer.packetize(&p_frame(4), 3000).unwrap();
+        let idr = packetizer.packetize(&idr_access_unit(), 6000).unwrap();
+        packetizer.packetize(&p_frame(6), 9000).unwrap();
+        let p_after_loss = packetizer.packetize(&p_frame(8), 12000).unwrap();
+
+        let mut depayloader = H264Depayloader::new();
+        let mut last = |packets: &[RtpPacket]| packets.iter().map(|p| depayloader.push(p)).last();
+        assert_eq!(last(&first), Some(Ok(Some(p_frame(2)))));
+        assert_eq!(last(&idr), Some(Ok(Some(without_aud(&idr_access_unit())))));
+        assert_eq!(last(&p_after_loss), Some(Ok(Some(p_frame(8)))));
+        assert_eq!(depayloader.lost_frames(), 0);
+    }
+}
@@ -0,0 +1,453 @@
+//! Modul Jitter Buffer ELARA
+//!
+//! Menampung paket media yang diterima, mengurutkan ulang berdasarkan
+//! nomor urut, menahan paket selama target delay yang menyesuaikan
+//! jitter jaringan, lalu menyerahkannya ke depayloader untuk disusun
+//! menjadi frame lengkap.
+
+use std::collections::BTreeMap;
+
+use crate::rtp::{Depayloader, PacketError, RtpPacket, Unwrapper};
+
+/// Delay minimum (ms)
+const MIN_TARGET_DELAY_MS: f64 = 20.0;
+
+/// Delay maksimum (ms); lebih dari ini percakapan terasa tertunda
+const MAX_TARGET_DELAY_MS: f64 = 400.0;
+
+/// Kapasitas paket maksimum sebelum paket tertua dibuang
+const MAX_BUFFERED_PACKETS: usize = 1024;
+
+/// Statistik jitter buffer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JitterStats {
+    /// Interarrival jitter (ms, RFC 3550)
+    pub jitter_ms: f64,
+    /// Target delay saat ini (ms)
+    pub target_delay_ms: f64,
+    /// Paket yang tidak datang sebelum deadline putar
+    pub packets_lost: u64,
+    /// Paket yang datang setelah slotnya dilewati (sudah dihitung hilang)
+    pub packets_late: u64,
+    /// Paket duplikat
+    pub packets_duplicate: u64,
+    /// Paket dibuang karena buffer penuh atau rusak
+    pub packets_discarded: u64,
+    /// Paket diterima
+    pub packets_received: u64,
+    /// Bytes diterima
+    pub bytes_received: u64,
+    /// Frame lengkap yang dikeluarkan
+    pub frames_output: u64,
+    /// Frame yang dibuang depayloader karena tidak lengkap
+    pub frames_dropped: u64,
+}
+
+struct BufferedPacket {
+    packet: RtpPacket,
+    timestamp: i64,
+}
+
+/// Jitter buffer adaptif
+pub struct JitterBuffer<D: Depayloader> {
+    depayloader: D,
+    clock_rate: u32,
+    packets: BTreeMap<i64, BufferedPacket>,
+    sequence: Unwrapper,
+    timestamp: Unwrapper,
+    next_sequence: Option<i64>,
+    /// Offset minimum (arrival - media time) dalam ms
+    base_offset_ms: Option<f64>,
+    last_transit: Option<(i64, f64)>,
+    jitter: f64,
+    target_delay_ms: f64,
+    /// Batas bawah delay, misalnya agar retransmisi NACK sempat tiba
+    min_delay_ms: f64,
+    /// Delay tambahan untuk sinkronisasi dengan stream lain
+    sync_delay_ms: f64,
+    /// Timestamp terakhir (asli dan unwrapped)
+    last_timestamp: Option<(u32, i64)>,
+    stats: JitterStats,
+}
+
+impl<D: Depayloader> JitterBuffer<D> {
+    /// Buat jitter buffer untuk stream dengan clock rate tertentu
+    pub fn new(clock_rate: u32, depayloader: D) -> Self {
+        Self {
+            depayloader,
+            clock_rate,
+            packets: BTreeMap::new(),
+            sequence: Unwrapper::default(),
+            timestamp: Unwrapper::default(),
+            next_sequence: None,
+            base_offset_ms: None,
+            last_transit: None,
+            jitter: 0.0,
+            target_delay_ms: MIN_TARGET_DELAY_MS,
+            min_delay_ms: 0.0,
+            sync_delay_ms: 0.0,
+            last_timestamp: None,
+            stats: JitterStats::default(),
+        }
+    }
+
+    /// Depayloader yang dipakai
+    pub fn depayloader(&self) -> &D {
+        &self.depayloader
+    }
+
+    /// Statistik saat ini
+    pub fn stats(&self) -> JitterStats {
+        JitterStats {
+            jitter_ms: self.jitter_ms(),
+            target_delay_ms: self.delay_ms(),
+            frames_dropped: self.depayloader.lost_frames(),
+            ..self.stats.clone()
+        }
+    }
+
+    /// Set batas bawah delay (ms), tetap dibatasi delay maksimum
+    pub fn set_min_delay_ms(&mut self, delay_ms: f64) {
+        self.min_delay_ms = delay_ms.clamp(0.0, MAX_TARGET_DELAY_MS);
+    }
+
+    /// Set delay tambahan untuk lip sync (ms)
+    pub fn set_sync_delay_ms(&mut self, delay_ms: f64) {
+        self.sync_delay_ms = delay_ms.clamp(0.0, MAX_TARGET_DELAY_MS);
+    }
+
+    /// Delay sinkronisasi saat ini (ms)
+    pub fn sync_delay_ms(&self) -> f64 {
+        self.sync_delay_ms
+    }
+
+    /// Timestamp RTP terakhir dan waktu putarnya (ms, clock lokal)
+    pub fn last_playout(&self) -> Option<(u32, f64)> {
+        self.last_timestamp
+            .map(|(timestamp, unwrapped)| (timestamp, self.playout_time(unwrapped)))
+    }
+
+    /// Jumlah paket yang sedang ditahan
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    /// Buffer kosong
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    fn to_ms(&self, timestamp: i64) -> f64 {
+        timestamp as f64 * 1000.0 / self.clock_rate as f64
+    }
+
+    fn delay_ms(&self) -> f64 {
+        self.target_delay_ms.max(self.min_delay_ms) + self.sync_delay_ms
+    }
+
+    fn jitter_ms(&self) -> f64 {
+        self.to_ms(self.jitter.round() as i64)
+    }
+
+    /// Waktu putar (ms, clock lokal) untuk timestamp media
+    fn playout_time(&self, timestamp: i64) -> f64 {
+        self.base_offset_ms.unwrap_or(0.0) + self.to_ms(timestamp) + self.delay_ms()
+    }
+
+    fn update_timing(&mut self, timestamp: i64, arrival_ms: u64) {
+        let arrival_ms = arrival_ms as f64;
+        let offset = arrival_ms - self.to_ms(timestamp);
+
+        // Offset minimum = paket dengan delay jaringan terkecil; naik pelan
+        // agar drift clock pengirim tetap terikuti
+        self.base_offset_ms = Some(match self.base_offset_ms {
+            Some(base) if offset >= base => base + (offset - base) / 512.0,
+            _ => offset,
+        });
+
+        // Jitter RFC 3550 dihitung per frame (timestamp berbeda), bukan
+        // per paket, agar burst paket satu frame video tidak terhitung
+        match self.last_transit {
+            Some((last_timestamp, _)) if last_timestamp >= timestamp => return,
+            Some((_, last_transit)) => {
+                let transit = offset * self.clock_rate as f64 / 1000.0;
+                let d = (transit - last_transit).abs();
+                self.jitter += (d - self.jitter) / 16.0;
+                self.last_transit = Some((timestamp, transit));
+            }
+            None => {
+                self.last_transit = Some((timestamp, offset * self.clock_rate as f64 / 1000.0));
+            }
+        }
+
+        // Target naik cepat saat jitter naik, turun pelan saat membaik
+        let desired = (self.jitter_ms() * 3.0 + 10.0).clamp(MIN_TARGET_DELAY_MS, MAX_TARGET_DELAY_MS);
+        self.target_delay_ms = if desired > self.target_delay_ms {
+            desired
+        } else {
+            self.target_delay_ms * 0.99 + desired * 0.01
+        };
+    }
+
+    /// Masukkan paket yang diterima pada waktu `arrival_ms` (clock lokal)
+    pub fn push(&mut self, packet: RtpPacket, arrival_ms: u64) {
+        let sequence = self.sequence.unwrap(packet.sequence_number as u32, 16);
+        let timestamp = self.timestamp.unwrap(packet.timestamp, 32);
+        if self.last_timestamp.map(|(_, last)| last) < Some(timestamp) {
+            self.last_timestamp = Some((packet.timestamp, timestamp));
+        }
+
+        self.stats.packets_received += 1;
+        self.stats.bytes_received += packet.payload.len() as u64;
+
+        if let Some(next) = self.next_sequence {
+            if sequence < next {
+                self.stats.packets_late += 1;
+                return;
+            }
+        }
+        if self.packets.contains_key(&sequence) {
+            self.stats.packets_duplicate += 1;
+            return;
+        }
+
+        self.update_timing(timestamp, arrival_ms);
+        self.packets.insert(sequence, BufferedPacket { packet, timestamp });
+
+        while self.packets.len() > MAX_BUFFERED_PACKETS {
+            if let Some((&oldest, _)) = self.packets.iter().next() {
+                self.packets.remove(&oldest);
+                self.stats.packets_discarded += 1;
+                self.next_sequence = self.packets.keys().next().copied();
+                self.depayloader.reset();
+            }
+        }
+    }
+
+    /// Keluarkan frame yang sudah waktunya diputar
+    ///
+    /// Paket yang hilang dilewati begitu paket sesudahnya melewati
+    /// deadline putar, sehingga satu paket hilang tidak menahan stream.
+    pub fn poll(&mut self, now_ms: u64) -> Vec<Vec<u8>> {
+        let now_ms = now_ms as f64;
+        let mut frames = Vec::new();
+
+        while let Some((&first, buffered)) = self.packets.iter().next() {
+            if self.playout_time(buffered.timestamp) > now_ms {
+                break;
+            }
+
+            let next = self.next_sequence.unwrap_or(first);
+            if first > next {
+                // Paket next..first tidak datang sebelum deadline
+                self.stats.packets_lost += (first - next) as u64;
+            }
+
+            if let Some(buffered) = self.packets.remove(&first) {
+                self.next_sequence = Some(first + 1);
+                match self.depayloader.push(&buffered.packet) {
+                    Ok(Some(frame)) => {
+                        self.stats.frames_output += 1;
+                        frames.push(frame);
+                    }
+                    Ok(None) => {}
+                    // Frame rusak dihitung depayloader lewat lost_frames
+                    Err(PacketError::IncompleteFrame { .. }) => {}
+                    Err(_) => self.stats.packets_discarded += 1,
+                }
+            }
+        }
+
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtp::FrameInfo;
+
+    /// Depayloader sederhana: satu paket = satu frame, celah = frame hilang
+    #[derive(Default)]
+    struct PacketPerFrame {
+        last_sequence: Option<u16>,
+        lost: u64,
+    }
+
+    impl Depayloader for PacketPerFrame {
+        fn push(&mut self, packet: &RtpPacket) -> Result<Option<Vec<u8>>, PacketError> {
+            if let Some(last) = self.last_sequence {
+                self.lost += packet.sequence_number.wrapping_sub(last).wrapping_sub(1) as u64;
+            }
+            self.last_sequence = Some(packet.sequence_number);
+            Ok(Some(packet.payload.clone()))
+        }
+
+        fn reset(&mut self) {}
+
+        fn lost_frames(&self) -> u64 {
+            self.lost
+        }
+
+        fn last_frame_info(&self) -> FrameInfo {
+            FrameInfo::default()
+        }
+    }
+
+    /// PRNG deterministik untuk trace simulasi
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            self.0 >> 33
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    /// Satu paket audio 20 ms (960 sampel @ 48 kHz)
+    fn audio_packet(index: u32, first_sequence: u16, first_timestamp: u32) -> RtpPacket {
+        RtpPacket {
+            payload_type: 111,
+            sequence_number: first_sequence.wrapping_add(index as u16),
+            timestamp: first_timestamp.wrapping_add(index * 960),
+            ssrc: 1,
+            marker: false,
+            payload: index.to_be_bytes().to_vec(),
+        }
+    }
+
+    struct Trace {
+        /// (waktu tiba ms, paket)
+        arrivals: Vec<(u64, RtpPacket)>,
+    }
+
+    /// Buat trace: tiap paket dikirim tiap 20 ms, delay = base + jitter
+    /// acak, sebagian hilang sesuai `loss_percent`
+    fn trace(
+        count: u32,
+        jitter_ms: u64,
+        loss_percent: u64,
+        seed: u64,
+        first_sequence: u16,
+        first_timestamp: u32,
+    ) -> Trace {
+        let mut rng = Lcg(seed);
+        let mut arrivals = Vec::new();
+        for i in 0..count {
+            if rng.below(100) < loss_percent {
+                continue;
+            }
+            let delay = 50 + if jitter_ms > 0 { rng.below(jitter_ms) } else { 0 };
+            arrivals.push((i as u64 * 20 + delay, audio_packet(i, first_sequence, first_timestamp)));
+        }
+        arrivals.sort_by_key(|(arrival, _)| *arrival);
+        Trace { arrivals }
+    }
+
+    /// Jalankan trace dengan clock 1 ms, kembalikan frame yang keluar
+    fn run(trace: &Trace, buffer: &mut JitterBuffer<PacketPerFrame>) -> Vec<u32> {
+        let end = trace.arrivals.last().map(|(t, _)| *t).unwrap_or(0) + 1000;
+        let mut pending = trace.arrivals.iter().peekable();
+        let mut output = Vec::new();
+
+        for now in 0..end {
+            while let Some((arrival, packet)) = pending.peek() {
+                if *arrival > now {
+                    break;
+                }
+                buffer.push(packet.clone(), *arrival);
+                pending.next();
+            }
+            for frame in buffer.poll(now) {
+                output.push(u32::from_be_bytes([frame[0], frame[1], frame[2], frame[3]]));
+            }
+        }
+        output
+    }
+
+    fn is_strictly_increasing(frames: &[u32]) -> bool {
+        frames.windows(2).all(|w| w[0] < w[1])
+    }
+
+    #[test]
+    fn reordered_packets_are_output_in_order() {
+        let trace = trace(500, 40, 0, 7, 1000, 5000);
+        // Pastikan trace memang mengandung reorder
+        assert!(trace.arrivals.windows(2).any(|w| w[0].1.sequence_number > w[1].1.sequence_number));
+
+        let mut buffer = JitterBuffer::new(48_000, PacketPerFrame::default());
+        let output = run(&trace, &mut buffer);
+
+        assert!(is_strictly_increasing(&output));
+        let stats = buffer.stats();
+        assert!(output.len() as u64 + stats.packets_lost + stats.packets_late >= 500);
+        // Setelah adaptasi hampir tidak ada paket yang telat
+        assert!(stats.packets_late < 25, "late: {}", stats.packets_late);
+    }
+
+    #[test]
+    fn lost_packets_are_skipped_and_counted() {
+        let trace = trace(500, 0, 10, 11, 0, 0);
+        let received = trace.arrivals.len() as u64;
+
+        let mut buffer = JitterBuffer::new(48_000, PacketPerFrame::default());
+        let output = run(&trace, &mut buffer);
+        let stats = buffer.stats();
+
+        let span = (output.last().unwrap() - output.first().unwrap() + 1) as u64;
+        assert_eq!(output.len() as u64, received);
+        assert_eq!(stats.packets_lost, span - received);
+        assert!(is_strictly_increasing(&output));
+        assert_eq!(stats.packets_late, 0);
+    }
+
+    #[test]
+    fn target_delay_follows_jitter() {
+        let mut calm = JitterBuffer::new(48_000, PacketPerFrame::default());
+        run(&trace(300, 2, 0, 3, 0, 0), &mut calm);
+
+        let mut bursty = JitterBuffer::new(48_000, PacketPerFrame::default());
+        run(&trace(300, 120, 0, 3, 0, 0), &mut bursty);
+
+        let calm = calm.stats();
+        let bursty = bursty.stats();
+        assert!(bursty.jitter_ms > calm.jitter_ms * 5.0);
+        assert!(bursty.target_delay_ms > calm.target_delay_ms + 40.0);
+        assert!(bursty.target_delay_ms <= MAX_TARGET_DELAY_MS);
+    }
+
+    #[test]
+    fn sequence_and_timestamp_wraparound() {
+        let trace = trace(300, 30, 0, 5, u16::MAX - 100, u32::MAX - 960 * 50);
+        let mut buffer = JitterBuffer::new(48_000, PacketPerFrame::default());
+        let output = run(&trace, &mut buffer);
+
+        assert!(is_strictly_increasing(&output));
+        assert!(output.len() > 280);
+        assert_eq!(buffer.stats().packets_discarded, 0);
+    }
+
+    #[test]
+    fn late_and_duplicate_packets_are_reported() {
+        let mut buffer = JitterBuffer::new(48_000, PacketPerFrame::default());
+        buffer.push(audio_packet(0, 0, 0), 0);
+        buffer.push(audio_packet(0, 0, 0), 1);
+        buffer.push(audio_packet(2, 0, 0), 40);
+
+        // Paket 1 tidak datang; paket 2 diputar dan paket 1 dianggap hilang
+        let frames = buffer.poll(1000);
+        assert_eq!(frames.len(), 2);
+
+        buffer.push(audio_packet(1, 0, 0), 1001);
+        let stats = buffer.stats();
+        assert_eq!(stats.packets_duplicate, 1);
+        assert_eq!(stats.packets_discarded, 0);
+        assert_eq!(stats.packets_lost, 1);
+        assert_eq!(stats.packets_late, 1);
+        assert!(buffer.is_empty());
+    }
+}
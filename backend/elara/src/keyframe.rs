@@ -0,0 +1,182 @@
+//! Modul Keyframe ELARA
+//!
+//! Permintaan keyframe antar peer (PLI/FIR, RFC 4585/5104). Receiver
+//! meminta keyframe saat decoder kehilangan sinkronisasi dan mengulang
+//! permintaan sampai keyframe datang; sender membatasi berapa sering
+//! encoder dipaksa membuat IDR.
+
+/// Interval minimum antar keyframe paksa di sisi sender (ms)
+const MIN_KEYFRAME_INTERVAL_MS: u64 = 250;
+
+/// Interval minimum pengulangan PLI/FIR di sisi receiver (ms)
+const MIN_REQUEST_INTERVAL_MS: u64 = 300;
+
+/// Jenis permintaan keyframe yang perlu dikirim
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyframeRequest {
+    /// Picture Loss Indication
+    Pli,
+    /// Full Intra Request dengan nomor urut perintah
+    Fir(u8),
+}
+
+/// Pelacak permintaan keyframe di sisi receiver
+#[derive(Debug, Default)]
+pub struct KeyframeRequester {
+    pending: Option<bool>,
+    fir_sequence: u8,
+    last_sent_ms: Option<u64>,
+    lost_frames: u64,
+}
+
+impl KeyframeRequester {
+    /// Buat requester baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Minta keyframe; `full` mengirim FIR alih-alih PLI
+    pub fn request(&mut self, full: bool) {
+        if full && self.pending != Some(true) {
+            // Perintah FIR baru memakai nomor urut baru
+            self.fir_sequence = self.fir_sequence.wrapping_add(1);
+            self.last_sent_ms = None;
+        }
+        self.pending = Some(self.pending.unwrap_or(false) || full);
+    }
+
+    /// Ada permintaan yang belum terjawab keyframe
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Laporkan hasil depayload: total frame rusak dan apakah keyframe diterima
+    pub fn on_frames(&mut self, lost_frames: u64, keyframe_received: bool) {
+        if keyframe_received {
+            self.pending = None;
+            self.last_sent_ms = None;
+        }
+        if lost_frames > self.lost_frames {
+            self.request(false);
+        }
+        self.lost_frames = lost_frames;
+    }
+
+    /// Permintaan yang perlu dikirim sekarang
+    ///
+    /// Permintaan diulang tiap RTT (minimal 300 ms) sampai keyframe
+    /// diterima, karena PLI/FIR sendiri bisa hilang.
+    pub fn poll(&mut self, now_ms: u64, rtt_ms: u32) -> Option<KeyframeRequest> {
+        let full = self.pending?;
+        let interval = (rtt_ms as u64).max(MIN_REQUEST_INTERVAL_MS);
+        if let Some(last) = self.last_sent_ms {
+            if now_ms.saturating_sub(last) < interval {
+                return None;
+            }
+        }
+
+        self.last_sent_ms = Some(now_ms);
+        Some(if full {
+            KeyframeRequest::Fir(self.fir_sequence)
+        } else {
+            KeyframeRequest::Pli
+        })
+    }
+}
+
+/// Pembatas keyframe paksa di sisi sender
+#[derive(Debug, Default)]
+pub struct KeyframeThrottle {
+    last_forced_ms: Option<u64>,
+    last_fir_sequence: Option<(u32, u8)>,
+    ignored: u64,
+}
+
+impl KeyframeThrottle {
+    /// Buat throttle baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Jumlah permintaan yang diabaikan karena rate limit atau duplikat
+    pub fn ignored(&self) -> u64 {
+        self.ignored
+    }
+
+    /// Proses permintaan dari peer; `true` berarti encoder harus membuat IDR
+    ///
+    /// FIR dengan nomor urut yang sama adalah pengulangan perintah lama dan
+    /// tidak memaksa keyframe lagi. Dalam satu RTT (minimal 250 ms) setelah
+    /// keyframe paksa, permintaan lain diabaikan karena keyframe tersebut
+    /// masih di jalan.
+    pub fn on_request(
+        &mut self,
+        sender_ssrc: u32,
+        request: KeyframeRequest,
+        now_ms: u64,
+        rtt_ms: u32,
+    ) -> bool {
+        if let KeyframeRequest::Fir(sequence) = request {
+            if self.last_fir_sequence == Some((sender_ssrc, sequence)) {
+                self.ignored += 1;
+                return false;
+            }
+            self.last_fir_sequence = Some((sender_ssrc, sequence));
+        }
+
+        let interval = (rtt_ms as u64).max(MIN_KEYFRAME_INTERVAL_MS);
+        if let Some(last) = self.last_forced_ms {
+            if now_ms.saturating_sub(last) < interval {
+                self.ignored += 1;
+                return false;
+            }
+        }
+
+        self.last_forced_ms = Some(now_ms);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requester_repeats_until_keyframe() {
+        let mut requester = KeyframeRequester::new();
+        assert_eq!(requester.poll(0, 100), None);
+
+        requester.on_frames(1, false);
+        assert_eq!(requester.poll(0, 100), Some(KeyframeRequest::Pli));
+        assert_eq!(requester.poll(100, 100), None);
+        assert_eq!(requester.poll(300, 100), Some(KeyframeRequest::Pli));
+
+        requester.on_frames(1, true);
+        assert!(!requester.is_pending());
+        assert_eq!(requester.poll(1000, 100), None);
+    }
+
+    #[test]
+    fn fir_uses_new_sequence_per_command() {
+        let mut requester = KeyframeRequester::new();
+        requester.request(true);
+        let first = requester.poll(0, 100);
+        requester.request(true);
+        assert_eq!(requester.poll(400, 100), first);
+
+        requester.on_frames(0, true);
+        requester.request(true);
+        assert_ne!(requester.poll(500, 100), first);
+    }
+
+    #[test]
+    fn throttle_limits_forced_keyframes() {
+        let mut throttle = KeyframeThrottle::new();
+        assert!(throttle.on_request(1, KeyframeRequest::Pli, 0, 100));
+        assert!(!throttle.on_request(2, KeyframeRequest::Pli, 100, 100));
+        assert!(throttle.on_request(1, KeyframeRequest::Fir(7), 300, 100));
+        assert!(!throttle.on_request(1, KeyframeRequest::Fir(7), 900, 100));
+        assert!(throttle.on_request(1, KeyframeRequest::Pli, 900, 100));
+        assert_eq!(throttle.ignored(), 2);
+    }
+}
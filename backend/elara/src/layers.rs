@@ -0,0 +1,570 @@
+//! Modul Layers ELARA
+//!
+//! Simulcast dan SVC untuk panggilan lewat relay atau grup. Pengirim
+//! boleh mengirim beberapa spatial/temporal layer sekaligus; penerima
+//! (atau relay) memilih layer yang diteruskan sesuai bandwidth-nya
+//! sendiri, jadi penerima yang lemah tidak memaksa pengirim turun ke
+//! kualitas terendah.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::codec::VideoCodec;
+use crate::h264::is_h264_keyframe_packet;
+use crate::keyframe::KeyframeRequester;
+use crate::rtp::{PacketError, RtpPacket};
+use crate::vpx::{is_vp8_keyframe, Vp8Descriptor, Vp9Descriptor};
+
+/// Jumlah spatial layer maksimum
+pub const MAX_SPATIAL_LAYERS: usize = 3;
+
+/// Jumlah temporal layer maksimum per spatial layer
+pub const MAX_TEMPORAL_LAYERS: u32 = 3;
+
+/// Porsi bandwidth penerima yang boleh dipakai video, sisanya cadangan
+const LAYER_HEADROOM: f64 = 0.9;
+
+/// Jarak timestamp saat menyambung stream simulcast (satu frame 30 fps di 90 kHz)
+const SWITCH_TIMESTAMP_STEP: u32 = 3000;
+
+/// Mode pengiriman layer video
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq)]
+pub enum LayerMode {
+    /// Satu stream, hanya temporal layer
+    Single,
+    /// Satu stream (SSRC) per spatial layer
+    Simulcast,
+    /// Semua spatial layer dalam satu stream (VP9 SVC)
+    Svc,
+}
+
+/// Satu spatial layer video
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoLayer {
+    /// Lebar frame
+    pub width: u32,
+    /// Tinggi frame
+    pub height: u32,
+    /// Frame rate pada temporal layer tertinggi
+    pub fps: u32,
+    /// Bitrate semua temporal layer (kbps); untuk SVC termasuk layer di bawahnya
+    pub bitrate: u32,
+    /// Jumlah temporal layer (1-3)
+    pub temporal_layers: u32,
+    /// SSRC stream layer (simulcast), diisi engine pengirim
+    pub ssrc: Option<u32>,
+}
+
+/// Layer yang dipilih penerima
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerSelection {
+    /// Indeks spatial layer
+    pub spatial_layer: u32,
+    /// Indeks temporal layer tertinggi yang diteruskan
+    pub temporal_layer: u32,
+    /// Perkiraan bitrate yang diterima (kbps)
+    pub bitrate_kbps: u32,
+}
+
+/// Porsi kumulatif bitrate sampai temporal layer `temporal` (pola L1T2/L1T3)
+fn temporal_share(count: u32, temporal: u32) -> f64 {
+    match (count, temporal) {
+        (2, 0) => 0.6,
+        (3, 0) => 0.4,
+        (3, 1) => 0.6,
+        _ => 1.0,
+    }
+}
+
+/// Validasi daftar layer, urut dari resolusi terendah
+pub fn validate_layers(mode: LayerMode, layers: &[VideoLayer]) -> Result<()> {
+    if layers.is_empty() || layers.len() > MAX_SPATIAL_LAYERS {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Jumlah layer harus 1-{}", MAX_SPATIAL_LAYERS),
+        ));
+    }
+    if mode == LayerMode::Single && layers.len() > 1 {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "Mode single hanya punya satu spatial layer".to_string(),
+        ));
+    }
+
+    for (index, layer) in layers.iter().enumerate() {
+        if layer.width == 0 || layer.height == 0 || layer.fps == 0 || layer.bitrate == 0 {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Layer {} memiliki resolusi, fps atau bitrate nol", index),
+            ));
+        }
+        if !(1..=MAX_TEMPORAL_LAYERS).contains(&layer.temporal_layers) {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Layer {} harus punya 1-{} temporal layer", index, MAX_TEMPORAL_LAYERS),
+            ));
+        }
+    }
+
+    for pair in layers.windows(2) {
+        if pair[0].bitrate >= pair[1].bitrate || pair[0].width > pair[1].width {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "Layer harus urut dari resolusi dan bitrate terendah".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Pilih layer tertinggi yang muat di bandwidth penerima (kbps)
+///
+/// Jika spatial layer terendah pun tidak muat, temporal layer-nya
+/// dikurangi (frame rate turun) alih-alih video dimatikan.
+pub fn select_layer(layers: &[VideoLayer], bandwidth_kbps: u32) -> Option<LayerSelection> {
+    let budget = bandwidth_kbps as f64 * LAYER_HEADROOM;
+    let (spatial, layer) = layers
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, layer)| layer.bitrate as f64 <= budget)
+        .or_else(|| layers.first().map(|layer| (0, layer)))?;
+
+    let count = layer.temporal_layers.clamp(1, MAX_TEMPORAL_LAYERS);
+    let temporal = (0..count)
+        .rev()
+        .find(|&t| layer.bitrate as f64 * temporal_share(count, t) <= budget)
+        .unwrap_or(0);
+
+    Some(LayerSelection {
+        spatial_layer: spatial as u32,
+        temporal_layer: temporal,
+        bitrate_kbps: (layer.bitrate as f64 * temporal_share(count, temporal)) as u32,
+    })
+}
+
+/// Jumlah spatial layer yang sanggup dikirim dengan bandwidth pengirim (kbps)
+///
+/// Stream simulcast dikirim bersamaan sehingga bitrate-nya dijumlah;
+/// bitrate SVC sudah kumulatif. Layer terendah selalu dikirim.
+pub fn active_layer_count(mode: LayerMode, layers: &[VideoLayer], bandwidth_kbps: u32) -> u32 {
+    let count = match mode {
+        LayerMode::Single => 1,
+        LayerMode::Simulcast => layers
+            .iter()
+            .scan(0u32, |total, layer| {
+                *total += layer.bitrate;
+                Some(*total)
+            })
+            .take_while(|&total| total <= bandwidth_kbps)
+            .count(),
+        LayerMode::Svc => layers
+            .iter()
+            .take_while(|layer| layer.bitrate <= bandwidth_kbps)
+            .count(),
+    };
+    (count as u32).max(1)
+}
+
+/// Informasi layer satu paket video dari payload descriptor
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PacketLayer {
+    /// Indeks spatial layer (SVC)
+    pub spatial_layer: u8,
+    /// Indeks temporal layer
+    pub temporal_layer: u8,
+    /// Awal frame (untuk SVC: awal frame pada spatial layer ini)
+    pub start_of_frame: bool,
+    /// Akhir frame pada spatial layer ini
+    pub end_of_frame: bool,
+    /// Paket membuka keyframe
+    pub keyframe: bool,
+    /// Titik switch-up ke temporal layer ini
+    pub layer_sync: bool,
+}
+
+impl PacketLayer {
+    /// Baca layer dari paket RTP
+    ///
+    /// H.264 tidak membawa indeks layer; hanya keyframe yang ditandai.
+    pub fn parse(codec: VideoCodec, packet: &RtpPacket) -> std::result::Result<Self, PacketError> {
+        let payload = &packet.payload;
+        match codec {
+            VideoCodec::Vp8 => {
+                let descriptor = Vp8Descriptor::parse(payload)?;
+                let start = descriptor.start_of_partition && descriptor.partition_index == 0;
+                Ok(Self {
+                    temporal_layer: descriptor.temporal_layer.unwrap_or(0),
+                    start_of_frame: start,
+                    end_of_frame: packet.marker,
+                    keyframe: start && is_vp8_keyframe(&payload[descriptor.len..]),
+                    layer_sync: descriptor.layer_sync,
+                    ..Self::default()
+                })
+            }
+            VideoCodec::Vp9 => {
+                let descriptor = Vp9Descriptor::parse(payload)?;
+                let spatial_layer = descriptor.spatial_layer.unwrap_or(0);
+                Ok(Self {
+                    spatial_layer,
+                    temporal_layer: descriptor.temporal_layer.unwrap_or(0),
+                    start_of_frame: descriptor.start_of_frame,
+                    end_of_frame: descriptor.end_of_frame,
+                    keyframe: descriptor.start_of_frame
+                        && !descriptor.inter_predicted
+                        && spatial_layer == 0,
+                    layer_sync: descriptor.layer_sync,
+                })
+            }
+            VideoCodec::H264 => {
+                let keyframe = is_h264_keyframe_packet(payload);
+                Ok(Self {
+                    start_of_frame: keyframe,
+                    end_of_frame: packet.marker,
+                    keyframe,
+                    ..Self::default()
+                })
+            }
+            VideoCodec::H265 | VideoCodec::Av1 => Err(PacketError::InvalidPayload(format!(
+                "layer {} belum didukung",
+                codec.as_str()
+            ))),
+        }
+    }
+}
+
+/// Penyaring layer di sisi penerima atau relay
+///
+/// Meneruskan satu spatial layer dan temporal layer sampai target
+/// sebagai satu stream kontinu: SSRC tetap, nomor urut tanpa celah untuk
+/// paket yang sengaja dibuang, dan timestamp disambung saat ganti stream
+/// simulcast. Naik spatial layer hanya di keyframe, naik temporal layer
+/// hanya di titik layer sync; turun bisa di awal frame mana pun.
+pub struct LayerForwarder {
+    codec: VideoCodec,
+    mode: LayerMode,
+    ssrcs: Vec<u32>,
+    target_spatial: u8,
+    target_temporal: u8,
+    spatial: Option<u8>,
+    temporal: u8,
+    source_ssrc: Option<u32>,
+    out_ssrc: Option<u32>,
+    sequence_offset: u16,
+    timestamp_offset: u32,
+    /// Nomor urut masuk tertinggi dari stream sumber
+    highest_in: Option<u16>,
+    last_out: Option<(u16, u32)>,
+    keyframe_ssrc: Option<u32>,
+    keyframe_requester: KeyframeRequester,
+    dropped: u64,
+}
+
+impl LayerForwarder {
+    /// Buat forwarder; `ssrcs` adalah SSRC tiap layer simulcast, urut dari terendah
+    pub fn new(codec: VideoCodec, mode: LayerMode, ssrcs: Vec<u32>) -> Self {
+        Self {
+            codec,
+            mode,
+            ssrcs,
+            target_spatial: 0,
+            target_temporal: (MAX_TEMPORAL_LAYERS - 1) as u8,
+            // Stream tunggal bisa langsung diteruskan, decoder meminta keyframe sendiri
+            spatial: (mode == LayerMode::Single).then_some(0),
+            temporal: (MAX_TEMPORAL_LAYERS - 1) as u8,
+            source_ssrc: None,
+            out_ssrc: None,
+            sequence_offset: 0,
+            timestamp_offset: 0,
+            highest_in: None,
+            last_out: None,
+            keyframe_ssrc: None,
+            keyframe_requester: KeyframeRequester::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Set layer target
+    pub fn set_target(&mut self, selection: &LayerSelection) {
+        let max_spatial = match self.mode {
+            LayerMode::Single => 0,
+            LayerMode::Simulcast => self.ssrcs.len().saturating_sub(1),
+            LayerMode::Svc => MAX_SPATIAL_LAYERS - 1,
+        };
+        self.target_spatial = (selection.spatial_layer as usize).min(max_spatial) as u8;
+        self.target_temporal = selection.temporal_layer.min(MAX_TEMPORAL_LAYERS - 1) as u8;
+    }
+
+    /// Layer yang sedang diteruskan: (spatial, temporal)
+    pub fn current(&self) -> Option<(u8, u8)> {
+        self.spatial.map(|spatial| (spatial, self.temporal))
+    }
+
+    /// SSRC stream masuk yang sedang diteruskan
+    pub fn source_ssrc(&self) -> Option<u32> {
+        self.source_ssrc
+    }
+
+    /// Jumlah paket yang dibuang karena di atas layer target
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// SSRC yang perlu dikirimi PLI agar switch layer bisa dilakukan
+    pub fn poll_keyframe_request(&mut self, now_ms: u64, rtt_ms: u32) -> Option<u32> {
+        let ssrc = self.keyframe_ssrc?;
+        self.keyframe_requester.poll(now_ms, rtt_ms).map(|_| ssrc)
+    }
+
+    fn need_keyframe(&mut self, ssrc: u32) {
+        if self.keyframe_ssrc != Some(ssrc) {
+            self.keyframe_ssrc = Some(ssrc);
+            self.keyframe_requester.request(false);
+        }
+    }
+
+    fn keyframe_arrived(&mut self) {
+        self.keyframe_ssrc = None;
+        self.keyframe_requester.on_frames(0, true);
+    }
+
+    /// Pilih stream simulcast; `false` jika paket bukan dari stream yang diteruskan
+    fn switch_stream(&mut self, packet: &RtpPacket, info: &PacketLayer) -> bool {
+        let Some(stream) = self.ssrcs.iter().position(|&ssrc| ssrc == packet.ssrc) else {
+            return false;
+        };
+        let stream = stream as u8;
+
+        if stream == self.target_spatial && self.spatial != Some(stream) {
+            if info.keyframe {
+                // Sambung nomor urut dan timestamp ke stream sebelumnya
+                if let Some((sequence, timestamp)) = self.last_out {
+                    self.sequence_offset =
+                        packet.sequence_number.wrapping_sub(sequence.wrapping_add(1));
+                    self.timestamp_offset =
+                        packet.timestamp.wrapping_sub(timestamp.wrapping_add(SWITCH_TIMESTAMP_STEP));
+                }
+                self.highest_in = None;
+                self.spatial = Some(stream);
+                self.source_ssrc = Some(packet.ssrc);
+                self.temporal = self.target_temporal;
+                self.keyframe_arrived();
+            } else {
+                self.need_keyframe(packet.ssrc);
+            }
+        }
+
+        self.spatial == Some(stream)
+    }
+
+    /// Putuskan apakah paket dari stream sumber ikut diteruskan
+    fn accept(&mut self, packet: &RtpPacket, info: &PacketLayer) -> bool {
+        let base_frame_start = info.start_of_frame && info.spatial_layer == 0;
+
+        if self.mode == LayerMode::Svc && base_frame_start {
+            match self.spatial {
+                Some(spatial) if self.target_spatial < spatial => {
+                    self.spatial = Some(self.target_spatial)
+                }
+                Some(spatial) if self.target_spatial == spatial => {}
+                _ if info.keyframe => {
+                    self.spatial = Some(self.target_spatial);
+                    self.temporal = self.target_temporal;
+                    self.keyframe_arrived();
+                }
+                _ => self.need_keyframe(packet.ssrc),
+            }
+        }
+
+        if base_frame_start {
+            if self.target_temporal < self.temporal {
+                self.temporal = self.target_temporal;
+            } else if self.target_temporal > self.temporal {
+                if info.keyframe {
+                    self.temporal = self.target_temporal;
+                } else if info.layer_sync && info.temporal_layer > self.temporal {
+                    self.temporal = info.temporal_layer.min(self.target_temporal);
+                }
+            }
+        }
+
+        match self.spatial {
+            Some(spatial) => info.spatial_layer <= spatial && info.temporal_layer <= self.temporal,
+            None => false,
+        }
+    }
+
+    /// Saring satu paket video masuk, kembalikan paket yang perlu diteruskan
+    pub fn forward(&mut self, packet: &RtpPacket) -> Option<RtpPacket> {
+        let info = PacketLayer::parse(self.codec, packet).unwrap_or_default();
+
+        if self.mode == LayerMode::Simulcast {
+            if !self.switch_stream(packet, &info) {
+                return None;
+            }
+        } else {
+            self.source_ssrc = Some(packet.ssrc);
+        }
+
+        let fresh = self
+            .highest_in
+            .is_none_or(|highest| (packet.sequence_number.wrapping_sub(highest) as i16) > 0);
+        if fresh {
+            self.highest_in = Some(packet.sequence_number);
+        }
+
+        if !self.accept(packet, &info) {
+            // Celah dari paket yang sengaja dibuang bukan loss bagi penerima;
+            // kiriman ulang atau duplikat paket lama tidak menggeser lagi
+            if fresh {
+                self.sequence_offset = self.sequence_offset.wrapping_add(1);
+            }
+            self.dropped += 1;
+            return None;
+        }
+
+        let mut out = packet.clone();
+        out.ssrc = *self.out_ssrc.get_or_insert(packet.ssrc);
+        out.sequence_number = packet.sequence_number.wrapping_sub(self.sequence_offset);
+        out.timestamp = packet.timestamp.wrapping_sub(self.timestamp_offset);
+        if self.mode == LayerMode::Svc && info.end_of_frame && self.spatial == Some(info.spatial_layer) {
+            // Layer di atasnya dibuang, akhir frame ada di layer ini
+            out.marker = true;
+        }
+
+        let newer = match self.last_out {
+            Some((sequence, _)) => (out.sequence_number.wrapping_sub(sequence) as i16) > 0,
+            None => true,
+        };
+        if newer {
+            self.last_out = Some((out.sequence_number, out.timestamp));
+        }
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(width: u32, bitrate: u32, temporal_layers: u32) -> VideoLayer {
+        VideoLayer {
+            width,
+            height: width * 9 / 16,
+            fps: 30,
+            bitrate,
+            temporal_layers,
+            ssrc: None,
+        }
+    }
+
+    /// Paket VP8 dengan descriptor TID/Y
+    fn vp8_packet(ssrc: u32, sequence_number: u16, timestamp: u32, temporal: u8, sync: bool, keyframe: bool) -> RtpPacket {
+        let tid = (temporal << 6) | if sync { 0x20 } else { 0 };
+        let mut payload = vec![0x90, 0x20, tid];
+        payload.push(if keyframe { 0x00 } else { 0x01 });
+        payload.extend_from_slice(&[0u8; 20]);
+        RtpPacket {
+            payload_type: 96,
+            sequence_number,
+            timestamp,
+            ssrc,
+            marker: true,
+            payload,
+        }
+    }
+
+    #[test]
+    fn selects_highest_layer_within_bandwidth() {
+        let layers = vec![layer(320, 150, 3), layer(640, 500, 3), layer(1280, 1500, 3)];
+        assert!(validate_layers(LayerMode::Simulcast, &layers).is_ok());
+
+        let high = select_layer(&layers, 2000).unwrap();
+        assert_eq!((high.spatial_layer, high.temporal_layer), (2, 2));
+
+        let mid = select_layer(&layers, 600).unwrap();
+        assert_eq!((mid.spatial_layer, mid.temporal_layer, mid.bitrate_kbps), (1, 2, 500));
+
+        // Di bawah layer terendah: frame rate diturunkan
+        let low = select_layer(&layers, 80).unwrap();
+        assert_eq!((low.spatial_layer, low.temporal_layer), (0, 0));
+
+        assert_eq!(active_layer_count(LayerMode::Simulcast, &layers, 1000), 2);
+        assert_eq!(active_layer_count(LayerMode::Simulcast, &layers, 50), 1);
+        assert!(validate_layers(LayerMode::Single, &layers).is_err());
+    }
+
+    #[test]
+    fn temporal_switch_waits_for_layer_sync() {
+        let mut forwarder = LayerForwarder::new(VideoCodec::Vp8, LayerMode::Single, Vec::new());
+        let pattern = [0u8, 2, 1, 2];
+
+        forwarder.set_target(&LayerSelection { spatial_layer: 0, temporal_layer: 0, bitrate_kbps: 0 });
+        let mut out = Vec::new();
+        for (i, &temporal) in pattern.iter().cycle().take(8).enumerate() {
+            let packet = vp8_packet(1, 100 + i as u16, i as u32 * 3000, temporal, false, i == 0);
+            out.extend(forwarder.forward(&packet));
+        }
+        // Hanya TL0, nomor urut tetap kontinu
+        assert_eq!(out.iter().map(|p| p.sequence_number).collect::<Vec<_>>(), vec![100, 101]);
+
+        // Naik ke TL2 hanya di paket dengan layer sync
+        forwarder.set_target(&LayerSelection { spatial_layer: 0, temporal_layer: 2, bitrate_kbps: 0 });
+        assert!(forwarder.forward(&vp8_packet(1, 108, 24000, 1, false, false)).is_none());
+        assert!(forwarder.forward(&vp8_packet(1, 109, 27000, 2, true, false)).is_some());
+        assert_eq!(forwarder.current(), Some((0, 2)));
+        assert_eq!(forwarder.dropped(), 7);
+    }
+
+    #[test]
+    fn repeated_dropped_packets_do_not_shift_sequence() {
+        let mut forwarder = LayerForwarder::new(VideoCodec::Vp8, LayerMode::Single, Vec::new());
+        forwarder.set_target(&LayerSelection { spatial_layer: 0, temporal_layer: 0, bitrate_kbps: 0 });
+
+        let packets = [
+            vp8_packet(1, 100, 0, 0, false, true),
+            vp8_packet(1, 101, 3000, 2, false, false),
+            // Duplikat dan retransmisi paket TL2 yang sudah dibuang
+            vp8_packet(1, 101, 3000, 2, false, false),
+            vp8_packet(1, 102, 6000, 1, false, false),
+            vp8_packet(1, 101, 3000, 2, false, false),
+            vp8_packet(1, 103, 9000, 0, false, false),
+        ];
+        let out: Vec<u16> = packets
+            .iter()
+            .filter_map(|packet| forwarder.forward(packet))
+            .map(|packet| packet.sequence_number)
+            .collect();
+        assert_eq!(out, vec![100, 101]);
+        assert_eq!(forwarder.dropped(), 4);
+    }
+
+    #[test]
+    fn simulcast_switch_on_keyframe_keeps_stream_continuous() {
+        let mut forwarder = LayerForwarder::new(VideoCodec::Vp8, LayerMode::Simulcast, vec![10, 20]);
+        forwarder.set_target(&LayerSelection { spatial_layer: 0, temporal_layer: 2, bitrate_kbps: 0 });
+
+        let first = forwarder.forward(&vp8_packet(10, 500, 9000, 0, false, true)).unwrap();
+        assert!(forwarder.forward(&vp8_packet(20, 7000, 1000, 0, false, true)).is_none());
+
+        // Naik ke layer 1: tunggu keyframe, minta PLI ke stream tersebut
+        forwarder.set_target(&LayerSelection { spatial_layer: 1, temporal_layer: 2, bitrate_kbps: 0 });
+        assert!(forwarder.forward(&vp8_packet(20, 7001, 4000, 0, false, false)).is_none());
+        assert_eq!(forwarder.poll_keyframe_request(0, 100), Some(20));
+        assert!(forwarder.forward(&vp8_packet(10, 501, 12000, 0, false, false)).is_some());
+
+        let switched = forwarder.forward(&vp8_packet(20, 7002, 7000, 0, false, true)).unwrap();
+        assert_eq!(switched.ssrc, first.ssrc);
+        assert_eq!(switched.sequence_number, 502);
+        assert_eq!(switched.timestamp, 12000 + SWITCH_TIMESTAMP_STEP);
+        assert_eq!(forwarder.source_ssrc(), Some(20));
+        assert_eq!(forwarder.poll_keyframe_request(1000, 100), None);
+
+        // Stream lama tidak diteruskan lagi
+        assert!(forwarder.forward(&vp8_packet(10, 502, 15000, 0, false, false)).is_none());
+    }
+}
@@ -19,10 +19,56 @@ use uuid::Uuid;
 mod session;
 mod media;
 mod transport;
+mod codec;
+mod rtp;
+mod h264;
+mod vpx;
+mod opus;
+mod jitter;
+mod rtcp;
+mod nack;
+mod fec;
+mod keyframe;
+mod report;
+mod sync;
+mod layers;
+mod allocator;
+mod pacer;
+mod mtu;
+mod state;
+mod token;
+mod datachannel;
+mod transfer;
+mod appmessage;
+mod receipt;
+mod signaling;
 
 pub use session::*;
 pub use media::*;
 pub use transport::*;
+pub use codec::*;
+pub use rtp::*;
+pub use h264::*;
+pub use vpx::*;
+pub use opus::*;
+pub use jitter::*;
+pub use rtcp::*;
+pub use nack::*;
+pub use fec::*;
+pub use keyframe::*;
+pub use report::*;
+pub use sync::*;
+pub use layers::*;
+pub use allocator::*;
+pub use pacer::*;
+pub use mtu::*;
+pub use state::*;
+pub use token::*;
+pub use datachannel::*;
+pub use transfer::*;
+pub use appmessage::*;
+pub use receipt::*;
+pub use signaling::*;
 
 /// Status koneksi ELARA
 #[napi]
@@ -139,7 +185,15 @@ impl ElaraNode {
     /// Buat sesi baru dengan peer
     #[napi]
     pub async fn create_session(&self, session_id: String, peer_id: String) -> Result<ElaraSession> {
-        let session = ElaraSession::new(session_id.clone(), self.node_id.clone(), peer_id);
+        let mut codecs = CodecCapabilities::default();
+        if !self.config.video_enabled {
+            codecs.video.clear();
+        }
+        if !self.config.audio_enabled {
+            codecs.audio.clear();
+        }
+
+        let session = ElaraSession::with_codecs(session_id.clone(), self.node_id.clone(), peer_id, codecs);
         
         let mut sessions = self.sessions.write().await;
         sessions.insert(session_id, session.clone());
@@ -154,13 +208,31 @@ impl ElaraNode {
         sessions.get(&session_id).cloned()
     }
 
+    /// Buat media engine yang dibatasi bitrate maksimum node
+    #[napi]
+    pub fn create_media_engine(
+        &self,
+        video_config: Option<VideoConfig>,
+        audio_config: Option<AudioConfig>,
+    ) -> MediaEngine {
+        MediaEngine::with_limits(
+            video_config,
+            audio_config,
+            self.config.max_video_bitrate,
+            self.config.max_audio_bitrate,
+        )
+    }
+
     /// Tutup sesi
     #[napi]
     pub async fn close_session(&self, session_id: String) -> Result<()> {
         let mut sessions = self.sessions.write().await;
         
         if let Some(mut session) = sessions.remove(&session_id) {
-            session.close().await?;
+            // Sesi yang sudah berakhir (mis. peer terputus) cukup dilepas
+            if session.get_status().await != SessionStatus::Ended {
+                session.close().await?;
+            }
         }
         
         Ok(())
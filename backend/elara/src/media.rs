@@ -3,7 +3,37 @@
 //! Mengelola stream audio dan video menggunakan codec native.
 
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::JsFunction;
 use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+use crate::{
+    ntp_time, AudioCodec, BitrateAllocation, BitrateAllocator, ClockReference, Depayloader, ElaraSession, FecDecoder, FecEncoder, FecSettings, FrameInfo, H264Depayloader,
+    KeyframeRequest, KeyframeRequester, KeyframeThrottle, LipSync, MediaKind, H264Payloader, JitterBuffer, JitterStats,
+    LayerForwarder, LayerMode, LayerSelection, VideoLayer, active_layer_count, select_layer, validate_layers,
+    NackGenerator, NegotiatedCodecs, OpusDepayloader, OpusPayloader, Pacer, PacketPriority, Packetizer,
+    RetransmissionHistory, PADDING_PAYLOAD_TYPE,
+    RtcpPacket, RtpPacket, StreamReporter, VideoCodec, Vp8Depayloader, Vp8Payloader, Vp9Depayloader, Vp9Payloader,
+    Payloader, RedDecoder, RedEncoder, AUDIO_RED_PAYLOAD_TYPE, DEFAULT_MTU, FEC_HEADER_LEN,
+    OPUS_CLOCK_RATE, OPUS_MIN_BITRATE, VIDEO_FEC_PAYLOAD_TYPE, MAX_PATH_MTU, MIN_PATH_MTU,
+    SessionEvents, TransportManager,
+};
+
+/// Porsi bitrate video yang boleh dipakai untuk retransmisi (1/4)
+const RETRANSMIT_BUDGET_DIVISOR: u32 = 4;
+
+/// RTT awal sebelum ada pengukuran (ms)
+const DEFAULT_RTT_MS: u32 = 100;
+
+/// Interval report RTCP (ms)
+const RTCP_REPORT_INTERVAL_MS: u64 = 1000;
 
 /// Konfigurasi video
 #[napi(object)]
@@ -18,7 +48,7 @@ pub struct VideoConfig {
     /// Bitrate (kbps)
     pub bitrate: u32,
     /// Codec (h264, h265, vp8, vp9, av1)
-    pub codec: String,
+    pub codec: VideoCodec,
 }
 
 impl Default for VideoConfig {
@@ -28,7 +58,7 @@ impl Default for VideoConfig {
             height: 720,
             fps: 30,
             bitrate: 2500,
-            codec: "h264".to_string(),
+            codec: VideoCodec::H264,
         }
     }
 }
@@ -44,7 +74,7 @@ pub struct AudioConfig {
     /// Bitrate (kbps)
     pub bitrate: u32,
     /// Codec (opus, aac)
-    pub codec: String,
+    pub codec: AudioCodec,
 }
 
 impl Default for AudioConfig {
@@ -53,14 +83,14 @@ impl Default for AudioConfig {
             sample_rate: 48000,
             channels: 1,
             bitrate: 64,
-            codec: "opus".to_string(),
+            codec: AudioCodec::Opus,
         }
     }
 }
 
 /// Statistik media
 #[napi(object)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct MediaStats {
     /// Bytes terkirim
     pub bytes_sent: u64,
@@ -70,17 +100,84 @@ pub struct MediaStats {
     pub packets_sent: u64,
     /// Packets diterima
     pub packets_received: u64,
-    /// Packets hilang
+    /// Packets hilang (tidak datang tepat waktu atau dibuang)
     pub packets_lost: u64,
+    /// Packets yang datang terlambat (sudah termasuk `packets_lost`)
+    pub packets_late: u64,
+    /// Packets duplikat
+    pub packets_duplicate: u64,
     /// Jitter (ms)
     pub jitter_ms: f32,
     /// Round-trip time (ms)
     pub rtt_ms: u32,
+    /// Loss stream kita menurut report peer (persen)
+    pub fraction_lost: f32,
+}
+
+/// Statistik media per stream untuk satu sesi
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct SessionMediaStats {
+    /// Stream video
+    pub video: MediaStats,
+    /// Stream audio
+    pub audio: MediaStats,
+    /// Selisih A/V saat ini (ms), positif berarti video tertinggal
+    pub av_offset_ms: Option<f64>,
+    /// Umur paket tertua di antrean pacer (ms)
+    pub pacer_queue_delay_ms: u32,
+}
+
+/// Pengaturan encoder Opus native
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct OpusEncoderSettings {
+    /// Bitrate target (kbps)
+    pub bitrate_kbps: u32,
+    /// Durasi frame (ms)
+    pub frame_duration_ms: u32,
+    /// Aktifkan in-band FEC
+    pub inband_fec: bool,
+    /// Aktifkan DTX
+    pub dtx: bool,
+    /// Perkiraan packet loss untuk tuning FEC (persen)
+    pub expected_packet_loss: u32,
+}
+
+/// Status FEC yang sedang aktif
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct FecStatus {
+    /// Jumlah paket video per paket FEC, 0 berarti mati
+    pub video_group_size: u32,
+    /// Jumlah paket audio sebelumnya di tiap paket RED, 0 berarti mati
+    pub audio_redundancy: u32,
+    /// Perkiraan overhead FEC (kbps)
+    pub overhead_kbps: u32,
+    /// Paket video yang dipulihkan dari FEC
+    pub video_packets_recovered: u32,
+    /// Paket audio yang dipulihkan dari RED
+    pub audio_packets_recovered: u32,
+}
+
+/// Metadata frame video yang diterima
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct VideoFrameInfo {
+    /// Keyframe
+    pub keyframe: bool,
+    /// Picture ID (VP8/VP9)
+    pub picture_id: Option<u32>,
+    /// Indeks temporal layer
+    pub temporal_layer: Option<u32>,
+    /// Indeks spatial layer
+    pub spatial_layer: Option<u32>,
 }
 
 /// Level kualitas video adaptif
 #[napi]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum VideoQualityLevel {
     /// 1080p atau lebih
     High,
@@ -94,12 +191,520 @@ pub enum VideoQualityLevel {
     AudioOnly,
 }
 
+/// Satu anak tangga pada quality ladder
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityRung {
+    /// Level kualitas yang diwakili rung ini
+    pub level: VideoQualityLevel,
+    /// Lebar frame
+    pub width: u32,
+    /// Tinggi frame
+    pub height: u32,
+    /// Frame rate
+    pub fps: u32,
+    /// Bitrate (kbps)
+    pub bitrate: u32,
+    /// Bandwidth minimum untuk memakai rung ini (kbps)
+    pub min_bandwidth_kbps: u32,
+    /// Packet loss maksimum untuk memakai rung ini (persen)
+    pub max_packet_loss: f64,
+}
+
+/// Kemampuan capture kamera device
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureCapability {
+    /// Lebar maksimum
+    pub max_width: u32,
+    /// Tinggi maksimum
+    pub max_height: u32,
+    /// Frame rate maksimum
+    pub max_fps: u32,
+}
+
+/// Quality ladder - daftar rung dari kualitas tertinggi ke terendah
+///
+/// Rung dipilih dari atas ke bawah; rung pertama yang syarat bandwidth
+/// dan packet loss-nya terpenuhi dipakai. Jika tidak ada yang cocok,
+/// sesi turun ke `AudioOnly`.
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityLadder {
+    /// Rung, urut dari kualitas tertinggi
+    pub rungs: Vec<QualityRung>,
+}
+
+impl Default for QualityLadder {
+    fn default() -> Self {
+        Self {
+            rungs: vec![
+                QualityRung {
+                    level: VideoQualityLevel::High,
+                    width: 1920,
+                    height: 1080,
+                    fps: 30,
+                    bitrate: 3000,
+                    min_bandwidth_kbps: 2500,
+                    max_packet_loss: 1.0,
+                },
+                QualityRung {
+                    level: VideoQualityLevel::Medium,
+                    width: 1280,
+                    height: 720,
+                    fps: 30,
+                    bitrate: 2000,
+                    min_bandwidth_kbps: 1500,
+                    max_packet_loss: 3.0,
+                },
+                QualityRung {
+                    level: VideoQualityLevel::Low,
+                    width: 854,
+                    height: 480,
+                    fps: 24,
+                    bitrate: 1000,
+                    min_bandwidth_kbps: 800,
+                    max_packet_loss: 5.0,
+                },
+                QualityRung {
+                    level: VideoQualityLevel::VeryLow,
+                    width: 640,
+                    height: 360,
+                    fps: 15,
+                    bitrate: 500,
+                    min_bandwidth_kbps: 300,
+                    max_packet_loss: 100.0,
+                },
+            ],
+        }
+    }
+}
+
+impl QualityLadder {
+    /// Parse ladder dari JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        let ladder: QualityLadder = serde_json::from_str(json).map_err(|e| {
+            Error::new(Status::InvalidArg, format!("Quality ladder tidak valid: {}", e))
+        })?;
+        ladder.validate()?;
+        Ok(ladder)
+    }
+
+    /// Validasi urutan dan isi rung
+    pub fn validate(&self) -> Result<()> {
+        if self.rungs.is_empty() {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "Quality ladder harus memiliki minimal satu rung".to_string(),
+            ));
+        }
+
+        for rung in &self.rungs {
+            if rung.level == VideoQualityLevel::AudioOnly {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    "AudioOnly adalah fallback implisit, bukan rung".to_string(),
+                ));
+            }
+            if rung.width == 0 || rung.height == 0 || rung.fps == 0 || rung.bitrate == 0 {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Rung {:?} memiliki resolusi, fps atau bitrate nol", rung.level),
+                ));
+            }
+        }
+
+        for pair in self.rungs.windows(2) {
+            if pair[0].min_bandwidth_kbps < pair[1].min_bandwidth_kbps {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    "Rung harus urut dari bandwidth minimum tertinggi".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Batasi ladder dengan bitrate maksimum node dan kemampuan capture device
+    ///
+    /// Resolusi di atas kemampuan kamera diturunkan dengan aspect ratio
+    /// tetap; bitrate tidak pernah melebihi `max_bitrate_kbps`.
+    pub fn constrained(&self, max_bitrate_kbps: u32, capture: Option<&CaptureCapability>) -> Self {
+        let rungs = self
+            .rungs
+            .iter()
+            .map(|rung| {
+                let mut rung = rung.clone();
+
+                if let Some(capture) = capture {
+                    if rung.width > capture.max_width || rung.height > capture.max_height {
+                        let scale = f64::min(
+                            capture.max_width as f64 / rung.width as f64,
+                            capture.max_height as f64 / rung.height as f64,
+                        );
+                        // Encoder butuh dimensi genap
+                        rung.width = ((rung.width as f64 * scale) as u32 & !1).max(2);
+                        rung.height = ((rung.height as f64 * scale) as u32 & !1).max(2);
+                    }
+                    rung.fps = rung.fps.min(capture.max_fps.max(1));
+                }
+
+                if max_bitrate_kbps > 0 {
+                    rung.bitrate = rung.bitrate.min(max_bitrate_kbps);
+                }
+
+                rung
+            })
+            .collect();
+
+        Self { rungs }
+    }
+
+    /// Pilih rung berdasarkan kondisi jaringan, `None` berarti audio only
+    pub fn select(&self, bandwidth_kbps: u32, packet_loss: f32) -> Option<&QualityRung> {
+        self.rungs.iter().find(|rung| {
+            bandwidth_kbps >= rung.min_bandwidth_kbps && (packet_loss as f64) < rung.max_packet_loss
+        })
+    }
+
+    /// Cari rung untuk level tertentu
+    pub fn rung(&self, level: &VideoQualityLevel) -> Option<&QualityRung> {
+        self.rungs.iter().find(|rung| &rung.level == level)
+    }
+
+    /// Rung untuk kualitas manual, `None` berarti audio only
+    ///
+    /// Jika level tidak ada di ladder, rung terendah yang dipakai.
+    pub fn manual_rung(&self, level: &VideoQualityLevel) -> Option<&QualityRung> {
+        match level {
+            VideoQualityLevel::AudioOnly => None,
+            _ => self.rung(level).or_else(|| self.rungs.last()),
+        }
+    }
+}
+
 /// Media Engine - Mengelola encoding/decoding media
 #[napi]
 pub struct MediaEngine {
     video_config: VideoConfig,
     audio_config: AudioConfig,
     current_quality: VideoQualityLevel,
+    ladder: QualityLadder,
+    max_video_bitrate: u32,
+    capture: Option<CaptureCapability>,
+    video_packetizer: Option<Packetizer>,
+    video_receiver: Option<JitterBuffer<Box<dyn Depayloader>>>,
+    video_sent: SendCounters,
+    video_history: RetransmissionHistory,
+    video_nack: NackGenerator,
+    rtt_ms: u32,
+    fec_allowed: bool,
+    fec: FecSettings,
+    video_fec: FecEncoder,
+    video_fec_decoder: FecDecoder,
+    keyframe_requester: KeyframeRequester,
+    keyframe_throttle: KeyframeThrottle,
+    keyframe_handler: Option<Box<dyn FnMut(bool) + Send>>,
+    video_reports: StreamReporter,
+    video_layer_mode: LayerMode,
+    video_layers: Vec<VideoLayer>,
+    simulcast: Vec<SimulcastStream>,
+    active_video_layers: u32,
+    remote_video_layers: Vec<VideoLayer>,
+    video_forwarder: Option<LayerForwarder>,
+    max_audio_bitrate: u32,
+    allocator: BitrateAllocator,
+    pacer: Pacer,
+    pacing_enabled: bool,
+    path_mtu: usize,
+    transport_mtu: Option<Arc<AtomicU32>>,
+    audio_packetizer: Option<Packetizer>,
+    audio_receiver: Option<JitterBuffer<OpusDepayloader>>,
+    audio_sent: SendCounters,
+    audio_red: RedEncoder,
+    audio_red_decoder: RedDecoder,
+    audio_reports: StreamReporter,
+    lip_sync: LipSync,
+    audio_fec: bool,
+    audio_dtx: bool,
+    last_packet_loss: f32,
+    last_report_ms: Option<u64>,
+    session_stats: Option<Arc<RwLock<SessionMediaStats>>>,
+    session_events: Option<SessionEvents>,
+    clock: Instant,
+    wall_clock: Duration,
+}
+
+/// Stream simulcast tambahan (layer 1 ke atas) di sisi pengirim
+struct SimulcastStream {
+    packetizer: Packetizer,
+    history: RetransmissionHistory,
+    reports: StreamReporter,
+    fec: FecEncoder,
+}
+
+/// Penghitung paket terkirim per stream
+#[derive(Debug, Default)]
+struct SendCounters {
+    packets: u64,
+    bytes: u64,
+}
+
+impl SendCounters {
+    fn record(&mut self, packets: &[Buffer]) {
+        self.packets += packets.len() as u64;
+        self.bytes += packets.iter().map(|p| p.len() as u64).sum::<u64>();
+    }
+
+    fn to_stats(&self, received: Option<JitterStats>, reports: &StreamReporter) -> MediaStats {
+        let received = received.unwrap_or_default();
+        MediaStats {
+            bytes_sent: self.bytes,
+            bytes_received: received.bytes_received,
+            packets_sent: self.packets,
+            packets_received: received.packets_received,
+            // Paket telat sudah terhitung hilang saat slotnya dilewati
+            packets_lost: received.packets_lost + received.packets_discarded,
+            packets_late: received.packets_late,
+            packets_duplicate: received.packets_duplicate,
+            jitter_ms: reports.jitter_ms() as f32,
+            rtt_ms: reports.rtt_ms().unwrap_or(0),
+            fraction_lost: reports.remote().map_or(0.0, |remote| remote.fraction_lost),
+        }
+    }
+}
+
+/// Payload type default per codec video (sama dengan `CodecCapabilities::default`)
+fn default_video_payload_type(codec: VideoCodec) -> u8 {
+    match codec {
+        VideoCodec::H264 => 102,
+        VideoCodec::Vp8 => 96,
+        VideoCodec::Vp9 => 98,
+        VideoCodec::H265 => 104,
+        VideoCodec::Av1 => 45,
+    }
+}
+
+/// Buat packetizer dan jalur terima (jitter buffer + depayloader) untuk codec video
+fn video_pipeline(
+    codec: VideoCodec,
+    payload_type: u8,
+) -> Option<(Packetizer, JitterBuffer<Box<dyn Depayloader>>)> {
+    let (payloader, depayloader): (Box<dyn Payloader>, Box<dyn Depayloader>) = match codec {
+        VideoCodec::H264 => (Box::new(H264Payloader::new()), Box::new(H264Depayloader::new())),
+        VideoCodec::Vp8 => (Box::new(Vp8Payloader::new()), Box::new(Vp8Depayloader::new())),
+        VideoCodec::Vp9 => (Box::new(Vp9Payloader::new()), Box::new(Vp9Depayloader::new())),
+        VideoCodec::H265 | VideoCodec::Av1 => return None,
+    };
+
+    Some((
+        Packetizer::new(payload_type, payloader),
+        JitterBuffer::new(codec.clock_rate(), depayloader),
+    ))
+}
+
+/// Buat packetizer dan jalur terima untuk codec audio
+fn audio_pipeline(
+    codec: AudioCodec,
+    payload_type: u8,
+) -> Option<(Packetizer, JitterBuffer<OpusDepayloader>)> {
+    match codec {
+        AudioCodec::Opus => Some((
+            Packetizer::new(payload_type, Box::new(OpusPayloader::new())),
+            JitterBuffer::new(OPUS_CLOCK_RATE, OpusDepayloader::new()),
+        )),
+        AudioCodec::Aac => None,
+    }
+}
+
+impl MediaEngine {
+    /// Buat media engine dengan batas bitrate dari `ElaraConfig`
+    pub fn with_limits(
+        video_config: Option<VideoConfig>,
+        audio_config: Option<AudioConfig>,
+        max_video_bitrate: u32,
+        max_audio_bitrate: u32,
+    ) -> Self {
+        let mut engine = Self::new(video_config, audio_config);
+        engine.max_video_bitrate = max_video_bitrate;
+        engine.max_audio_bitrate = max_audio_bitrate;
+        engine.allocator.set_audio_target(engine.audio_bitrate_kbps());
+        engine.allocator.set_max_video(max_video_bitrate);
+        engine
+    }
+
+    /// Waktu monotonic sejak engine dibuat (ms)
+    fn now_ms(&self) -> u64 {
+        self.clock.elapsed().as_millis() as u64
+    }
+
+    /// Set handler encoder native yang dipanggil untuk memaksa IDR
+    ///
+    /// Argumen handler `true` jika permintaan berasal dari FIR.
+    pub fn set_keyframe_handler(&mut self, handler: impl FnMut(bool) + Send + 'static) {
+        self.keyframe_handler = Some(Box::new(handler));
+    }
+
+    /// SSRC milik salah satu stream video yang dikirim engine ini
+    fn is_video_ssrc(&self, ssrc: u32) -> bool {
+        self.video_packetizer.as_ref().map(|p| p.ssrc()) == Some(ssrc)
+            || self.simulcast.iter().any(|stream| stream.packetizer.ssrc() == ssrc)
+    }
+
+    /// Waktu NTP monotonic (wall clock saat engine dibuat + waktu berjalan)
+    fn now_ntp(&self) -> u64 {
+        ntp_time(self.wall_clock + self.clock.elapsed())
+    }
+
+    /// Samakan waktu putar audio dan video
+    ///
+    /// Referensi clock dari sender report dipakai jika ada; tanpa SR,
+    /// referensi dari `set_clock_reference` (waktu ELARA) tetap berlaku.
+    fn update_lip_sync(&mut self) {
+        if let Some((ntp, rtp)) = self.audio_reports.sender_clock() {
+            let reference = ClockReference::from_ntp(ntp, rtp, OPUS_CLOCK_RATE);
+            self.lip_sync.set_reference(MediaKind::Audio, reference);
+        }
+        if let Some((ntp, rtp)) = self.video_reports.sender_clock() {
+            let reference = ClockReference::from_ntp(ntp, rtp, self.video_config.codec.clock_rate());
+            self.lip_sync.set_reference(MediaKind::Video, reference);
+        }
+
+        let audio = self.audio_receiver.as_ref().and_then(|r| r.last_playout());
+        let video = self.video_receiver.as_ref().and_then(|r| r.last_playout());
+        if self.lip_sync.update(self.now_ms(), audio, video) {
+            if let Some(receiver) = self.audio_receiver.as_mut() {
+                receiver.set_sync_delay_ms(self.lip_sync.delay_ms(MediaKind::Audio));
+            }
+            if let Some(receiver) = self.video_receiver.as_mut() {
+                receiver.set_sync_delay_ms(self.lip_sync.delay_ms(MediaKind::Video));
+            }
+        }
+    }
+
+    /// Kirim snapshot statistik ke sesi yang terikat
+    fn publish_stats(&self) {
+        if let Some(stats) = self.session_stats.as_ref() {
+            // Lewati jika sedang dibaca; report berikutnya akan memperbarui
+            if let Ok(mut stats) = stats.try_write() {
+                *stats = SessionMediaStats {
+                    video: self.get_video_stats(),
+                    audio: self.get_audio_stats(),
+                    av_offset_ms: self.lip_sync.offset_ms(),
+                    pacer_queue_delay_ms: self.pacer.queue_delay_ms(self.now_ms()) as u32,
+                };
+            }
+        }
+    }
+
+    /// Serahkan paket ke pacer jika aktif, selain itu langsung dikembalikan
+    fn pace(&mut self, priority: PacketPriority, packets: Vec<Buffer>) -> Vec<Buffer> {
+        if !self.pacing_enabled {
+            return packets;
+        }
+        let now = self.now_ms();
+        for packet in packets {
+            self.pacer.enqueue(priority, packet.to_vec(), now);
+        }
+        Vec::new()
+    }
+
+    /// Bitrate audio efektif (kbps) setelah batas node
+    fn audio_bitrate_kbps(&self) -> u32 {
+        let mut bitrate = self.audio_config.bitrate;
+        if self.max_audio_bitrate > 0 {
+            bitrate = bitrate.min(self.max_audio_bitrate);
+        }
+        bitrate.max(OPUS_MIN_BITRATE)
+    }
+
+    /// Terapkan pengaturan FEC ke encoder
+    fn apply_fec(&mut self, fec: FecSettings) {
+        self.fec = fec;
+        self.video_fec.set_group_size(fec.video_group_size);
+        for stream in self.simulcast.iter_mut() {
+            stream.fec.set_group_size(fec.video_group_size);
+        }
+        self.audio_red.set_redundancy(fec.audio_redundancy);
+        self.apply_mtu();
+    }
+
+    /// Terapkan path MTU ke semua packetizer
+    ///
+    /// Paket video utama diperkecil selama FEC aktif agar paket paritas
+    /// (header FEC + payload terpanjang) tetap muat dalam MTU.
+    fn apply_mtu(&mut self) {
+        let mtu = self.path_mtu;
+        let video_mtu = if self.fec.video_group_size > 0 {
+            mtu - FEC_HEADER_LEN
+        } else {
+            mtu
+        };
+        if let Some(packetizer) = self.video_packetizer.as_mut() {
+            packetizer.set_mtu(video_mtu);
+        }
+        for stream in self.simulcast.iter_mut() {
+            stream.packetizer.set_mtu(video_mtu);
+        }
+        if let Some(packetizer) = self.audio_packetizer.as_mut() {
+            packetizer.set_mtu(mtu);
+        }
+    }
+
+    /// Ambil path MTU terbaru dari transport yang terikat
+    fn refresh_path_mtu(&mut self) {
+        let mtu = self
+            .transport_mtu
+            .as_ref()
+            .map(|mtu| mtu.load(Ordering::Relaxed) as usize);
+        if let Some(mtu) = mtu {
+            if mtu != self.path_mtu {
+                self.set_path_mtu(mtu as u32);
+            }
+        }
+    }
+
+    fn update_video_min_delay(&mut self) {
+        let delay_ms = self.rtt_ms as f64 * 1.5;
+        if let Some(receiver) = self.video_receiver.as_mut() {
+            receiver.set_min_delay_ms(delay_ms);
+        }
+    }
+
+    /// Ladder setelah dibatasi bitrate node dan kemampuan capture
+    fn effective_ladder(&self) -> QualityLadder {
+        self.ladder.constrained(self.max_video_bitrate, self.capture.as_ref())
+    }
+
+    /// Terapkan rung ke konfigurasi video, `None` berarti audio only
+    fn apply_rung(&mut self, rung: Option<QualityRung>) {
+        let previous = self.current_quality;
+        match rung {
+            Some(rung) => {
+                self.video_config.width = rung.width;
+                self.video_config.height = rung.height;
+                self.video_config.fps = rung.fps;
+                self.video_config.bitrate = rung.bitrate;
+                self.video_history.set_max_bitrate(rung.bitrate / RETRANSMIT_BUDGET_DIVISOR);
+                self.current_quality = rung.level;
+            }
+            None => {
+                // PENTING: Tetap terhubung, hanya audio
+                self.video_config.bitrate = 0;
+                self.current_quality = VideoQualityLevel::AudioOnly;
+            }
+        }
+
+        if self.current_quality != previous {
+            if let Some(events) = self.session_events.as_ref() {
+                events.emit_quality(self.current_quality);
+            }
+        }
+    }
 }
 
 #[napi]
@@ -107,11 +712,67 @@ impl MediaEngine {
     /// Buat media engine baru
     #[napi(constructor)]
     pub fn new(video_config: Option<VideoConfig>, audio_config: Option<AudioConfig>) -> Self {
-        Self {
-            video_config: video_config.unwrap_or_default(),
-            audio_config: audio_config.unwrap_or_default(),
+        let video_config = video_config.unwrap_or_default();
+        let audio_config = audio_config.unwrap_or_default();
+        let (video_packetizer, video_receiver) =
+            video_pipeline(video_config.codec, default_video_payload_type(video_config.codec)).unzip();
+        let (audio_packetizer, audio_receiver) = audio_pipeline(audio_config.codec, 111).unzip();
+        let video_history =
+            RetransmissionHistory::new(video_config.bitrate / RETRANSMIT_BUDGET_DIVISOR);
+        let video_reports = StreamReporter::new(video_config.codec.clock_rate());
+        let allocator = BitrateAllocator::new(audio_config.bitrate.max(OPUS_MIN_BITRATE), 0);
+
+        let mut engine = Self {
+            video_config,
+            audio_config,
             current_quality: VideoQualityLevel::High,
-        }
+            ladder: QualityLadder::default(),
+            max_video_bitrate: 0,
+            capture: None,
+            video_packetizer,
+            video_receiver,
+            video_sent: SendCounters::default(),
+            video_history,
+            video_nack: NackGenerator::new(),
+            rtt_ms: DEFAULT_RTT_MS,
+            fec_allowed: true,
+            fec: FecSettings::default(),
+            video_fec: FecEncoder::new(VIDEO_FEC_PAYLOAD_TYPE),
+            video_fec_decoder: FecDecoder::new(),
+            keyframe_requester: KeyframeRequester::new(),
+            keyframe_throttle: KeyframeThrottle::new(),
+            keyframe_handler: None,
+            video_reports,
+            video_layer_mode: LayerMode::Single,
+            video_layers: Vec::new(),
+            simulcast: Vec::new(),
+            active_video_layers: 1,
+            remote_video_layers: Vec::new(),
+            video_forwarder: None,
+            max_audio_bitrate: 0,
+            allocator,
+            pacer: Pacer::new(),
+            pacing_enabled: false,
+            path_mtu: DEFAULT_MTU,
+            transport_mtu: None,
+            audio_packetizer,
+            audio_receiver,
+            audio_sent: SendCounters::default(),
+            audio_red: RedEncoder::new(AUDIO_RED_PAYLOAD_TYPE),
+            audio_red_decoder: RedDecoder::new(AUDIO_RED_PAYLOAD_TYPE),
+            audio_reports: StreamReporter::new(OPUS_CLOCK_RATE),
+            lip_sync: LipSync::new(),
+            audio_fec: true,
+            audio_dtx: true,
+            last_packet_loss: 0.0,
+            last_report_ms: None,
+            session_stats: None,
+            session_events: None,
+            clock: Instant::now(),
+            wall_clock: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default(),
+        };
+        engine.update_video_min_delay();
+        engine
     }
 
     /// Dapatkan konfigurasi video saat ini
@@ -126,12 +787,807 @@ impl MediaEngine {
         self.audio_config.clone()
     }
 
+    /// Terapkan codec hasil negosiasi sesi
+    #[napi]
+    pub fn apply_negotiated_codecs(&mut self, codecs: NegotiatedCodecs) {
+        match codecs.video {
+            Some(video) => {
+                self.video_config.codec = video.codec;
+                let (packetizer, receiver) =
+                    video_pipeline(video.codec, video.payload_type as u8).unzip();
+                self.video_packetizer = packetizer;
+                self.video_receiver = receiver;
+                self.video_history =
+                    RetransmissionHistory::new(self.video_config.bitrate / RETRANSMIT_BUDGET_DIVISOR);
+                self.video_nack = NackGenerator::new();
+                self.video_reports = StreamReporter::new(video.codec.clock_rate());
+                // Layer terikat ke codec lama, harus diset ulang setelah negosiasi
+                self.video_layer_mode = LayerMode::Single;
+                self.video_layers.clear();
+                self.simulcast.clear();
+                self.active_video_layers = 1;
+                self.remote_video_layers.clear();
+                self.video_forwarder = None;
+                self.update_video_min_delay();
+                self.apply_fec(self.fec);
+            }
+            None => self.apply_rung(None),
+        }
+
+        if let Some(audio) = codecs.audio {
+            self.audio_config.codec = audio.codec;
+            self.audio_fec = audio.fec;
+            self.audio_dtx = audio.dtx;
+            let (packetizer, receiver) = audio_pipeline(audio.codec, audio.payload_type as u8).unzip();
+            self.audio_packetizer = packetizer;
+            self.audio_receiver = receiver;
+            self.audio_reports = StreamReporter::new(OPUS_CLOCK_RATE);
+            self.audio_config.sample_rate = audio.clock_rate;
+            self.audio_config.channels = self.audio_config.channels.min(audio.channels as u8).max(1);
+        }
+
+        self.apply_mtu();
+
+        // Stream baru, referensi clock dan delay sinkronisasi lama tidak berlaku
+        self.lip_sync = LipSync::new();
+        for receiver in self.audio_receiver.iter_mut() {
+            receiver.set_sync_delay_ms(0.0);
+        }
+        for receiver in self.video_receiver.iter_mut() {
+            receiver.set_sync_delay_ms(0.0);
+        }
+    }
+
+    /// Pecah frame video terenkode menjadi paket RTP siap kirim
+    #[napi]
+    pub fn packetize_video(&mut self, frame: Buffer, timestamp: u32) -> Result<Vec<Buffer>> {
+        self.refresh_path_mtu();
+        let codec = self.video_config.codec;
+        let now = self.now_ms();
+        let packetizer = self.video_packetizer.as_mut().ok_or_else(|| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Packetizer untuk codec {} belum didukung", codec.as_str()),
+            )
+        })?;
+
+        let mut packets = Vec::new();
+        for packet in packetizer.packetize(&frame, timestamp)? {
+            let data = packet.serialize();
+            self.video_reports.on_sent(&packet, now);
+            self.video_history.store(packet.sequence_number, data.clone(), now);
+            packets.push(Buffer::from(data));
+            if let Some(fec) = self.video_fec.protect(&packet) {
+                packets.push(fec.serialize().into());
+            }
+        }
+        self.video_sent.record(&packets);
+        Ok(self.pace(PacketPriority::Video, packets))
+    }
+
+    /// Set layer untuk frame video berikutnya (VP8/VP9 SVC)
+    #[napi]
+    pub fn set_video_frame_layer(
+        &mut self,
+        temporal_layer: Option<u32>,
+        spatial_layer: Option<u32>,
+        layer_sync: bool,
+    ) {
+        if let Some(packetizer) = self.video_packetizer.as_mut() {
+            packetizer.set_frame_info(&FrameInfo {
+                temporal_layer: temporal_layer.map(|t| t as u8),
+                spatial_layer: spatial_layer.map(|s| s as u8),
+                layer_sync,
+                ..FrameInfo::default()
+            });
+        }
+    }
+
+    /// Set layer video yang dikirim, urut dari resolusi terendah
+    ///
+    /// Simulcast membuat satu stream (SSRC) per layer; layer 0 memakai
+    /// stream utama `packetize_video` dan menerima FEC. SVC hanya untuk
+    /// VP9 dan tetap satu stream. SSRC tiap layer bisa dibaca dari
+    /// `get_video_layers` untuk dikirim lewat signaling.
+    #[napi]
+    pub fn set_video_layers(&mut self, mode: LayerMode, layers: Vec<VideoLayer>) -> Result<()> {
+        validate_layers(mode, &layers)?;
+        let codec = self.video_config.codec;
+        if mode == LayerMode::Svc && codec != VideoCodec::Vp9 {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("SVC spatial belum didukung untuk codec {}", codec.as_str()),
+            ));
+        }
+        let (payload_type, base_ssrc) = self
+            .video_packetizer
+            .as_ref()
+            .map(|p| (p.payload_type(), p.ssrc()))
+            .ok_or_else(|| {
+                Error::new(
+                    Status::GenericFailure,
+                    format!("Packetizer untuk codec {} belum didukung", codec.as_str()),
+                )
+            })?;
+
+        let mut layers = layers;
+        self.simulcast.clear();
+        for (index, layer) in layers.iter_mut().enumerate() {
+            layer.ssrc = match (mode, index) {
+                (LayerMode::Simulcast, 1..) => {
+                    let (packetizer, _) = video_pipeline(codec, payload_type)
+                        .expect("codec sudah punya packetizer");
+                    let ssrc = packetizer.ssrc();
+                    let mut fec = FecEncoder::new(VIDEO_FEC_PAYLOAD_TYPE);
+                    fec.set_group_size(self.fec.video_group_size);
+                    self.simulcast.push(SimulcastStream {
+                        packetizer,
+                        history: RetransmissionHistory::new(layer.bitrate / RETRANSMIT_BUDGET_DIVISOR),
+                        reports: StreamReporter::new(codec.clock_rate()),
+                        fec,
+                    });
+                    Some(ssrc)
+                }
+                _ => Some(base_ssrc),
+            };
+        }
+
+        self.video_layer_mode = mode;
+        self.active_video_layers = layers.len() as u32;
+        self.video_layers = layers;
+        self.apply_mtu();
+        Ok(())
+    }
+
+    /// Dapatkan layer video yang dikirim beserta SSRC-nya
+    #[napi]
+    pub fn get_video_layers(&self) -> Vec<VideoLayer> {
+        self.video_layers.clone()
+    }
+
+    /// Jumlah spatial layer yang sebaiknya di-encode saat ini
+    ///
+    /// Diperbarui `adapt_quality`: layer atas dimatikan jika uplink
+    /// pengirim tidak cukup untuk semua layer.
+    #[napi]
+    pub fn get_active_video_layers(&self) -> u32 {
+        self.active_video_layers
+    }
+
+    /// Pecah frame satu layer simulcast menjadi paket RTP
+    ///
+    /// Layer 0 sama dengan `packetize_video`. Frame untuk layer yang
+    /// sedang nonaktif tidak dikirim.
+    #[napi]
+    pub fn packetize_video_layer(
+        &mut self,
+        layer: u32,
+        frame: Buffer,
+        timestamp: u32,
+    ) -> Result<Vec<Buffer>> {
+        if layer == 0 {
+            return self.packetize_video(frame, timestamp);
+        }
+        if layer >= self.active_video_layers {
+            return Ok(Vec::new());
+        }
+        self.refresh_path_mtu();
+
+        let now = self.now_ms();
+        let stream = self.simulcast.get_mut(layer as usize - 1).ok_or_else(|| {
+            Error::new(Status::InvalidArg, format!("Layer simulcast {} tidak ada", layer))
+        })?;
+
+        // Report dan FEC per stream, sama dengan jalur `packetize_video`
+        let mut packets = Vec::new();
+        for packet in stream.packetizer.packetize(&frame, timestamp)? {
+            let data = packet.serialize();
+            stream.reports.on_sent(&packet, now);
+            stream.history.store(packet.sequence_number, data.clone(), now);
+            packets.push(Buffer::from(data));
+            if let Some(fec) = stream.fec.protect(&packet) {
+                packets.push(fec.serialize().into());
+            }
+        }
+        self.video_sent.record(&packets);
+        Ok(self.pace(PacketPriority::Video, packets))
+    }
+
+    /// Masukkan paket RTP video yang diterima ke jitter buffer
+    #[napi]
+    pub fn receive_video_packet(&mut self, packet: Buffer) -> Result<()> {
+        let codec = self.video_config.codec;
+        let now = self.now_ms();
+        let receiver = self.video_receiver.as_mut().ok_or_else(|| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Depayloader untuk codec {} belum didukung", codec.as_str()),
+            )
+        })?;
+
+        let packet = RtpPacket::parse(&packet)?;
+        if packet.payload_type == PADDING_PAYLOAD_TYPE {
+            return Ok(());
+        }
+        let packets = if packet.payload_type == VIDEO_FEC_PAYLOAD_TYPE {
+            self.video_fec_decoder.push_fec(&packet)?
+        } else {
+            let source = self.video_forwarder.as_ref().and_then(|f| f.source_ssrc());
+            if source.is_none_or(|ssrc| ssrc == packet.ssrc) {
+                self.video_reports.on_received(&packet, now);
+            }
+            let mut recovered = self.video_fec_decoder.push_media(&packet);
+            recovered.insert(0, packet);
+            recovered
+        };
+
+        for packet in packets {
+            let Some(forwarder) = self.video_forwarder.as_mut() else {
+                self.video_nack.on_packet(&packet, now);
+                receiver.push(packet, now);
+                continue;
+            };
+
+            // NACK hanya untuk stream sumber, termasuk paket layer yang dibuang
+            let forwarded = forwarder.forward(&packet);
+            if forwarder.source_ssrc() == Some(packet.ssrc) {
+                self.video_nack.on_packet(&packet, now);
+            }
+            if let Some(packet) = forwarded {
+                receiver.push(packet, now);
+            }
+        }
+        Ok(())
+    }
+
+    /// Set layer yang dikirim peer (dari `get_video_layers` di sisi peer)
+    ///
+    /// Setelah diset, hanya satu layer yang diteruskan ke decoder sesuai
+    /// `select_video_layer`; awalnya layer terendah.
+    #[napi]
+    pub fn set_remote_video_layers(&mut self, mode: LayerMode, layers: Vec<VideoLayer>) -> Result<()> {
+        validate_layers(mode, &layers)?;
+        let ssrcs = match mode {
+            LayerMode::Simulcast => layers
+                .iter()
+                .map(|layer| layer.ssrc)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| {
+                    Error::new(
+                        Status::InvalidArg,
+                        "Setiap layer simulcast harus punya SSRC".to_string(),
+                    )
+                })?,
+            _ => Vec::new(),
+        };
+
+        let mut forwarder = LayerForwarder::new(self.video_config.codec, mode, ssrcs);
+        if let Some(selection) = select_layer(&layers, 0) {
+            forwarder.set_target(&selection);
+        }
+        self.video_forwarder = Some(forwarder);
+        self.remote_video_layers = layers;
+        Ok(())
+    }
+
+    /// Pilih layer peer yang diterima berdasarkan bandwidth sendiri (kbps)
+    ///
+    /// Naik layer menunggu keyframe (PLI dikirim lewat
+    /// `poll_keyframe_request`), turun layer langsung di frame berikutnya.
+    #[napi]
+    pub fn select_video_layer(&mut self, bandwidth_kbps: u32) -> Option<LayerSelection> {
+        let forwarder = self.video_forwarder.as_mut()?;
+        let selection = select_layer(&self.remote_video_layers, bandwidth_kbps)?;
+        forwarder.set_target(&selection);
+        Some(selection)
+    }
+
+    /// Saring paket video untuk diteruskan ke penerima lain (mode relay)
+    ///
+    /// Mengembalikan paket yang sudah ditulis ulang menjadi satu stream
+    /// kontinu, atau `None` jika layer-nya tidak diteruskan. Paket FEC
+    /// tidak diteruskan karena nomor urut yang dilindunginya berubah.
+    #[napi]
+    pub fn forward_video_packet(&mut self, packet: Buffer) -> Result<Option<Buffer>> {
+        let Some(forwarder) = self.video_forwarder.as_mut() else {
+            return Ok(Some(packet));
+        };
+
+        let packet = RtpPacket::parse(&packet)?;
+        if packet.payload_type == VIDEO_FEC_PAYLOAD_TYPE {
+            return Ok(None);
+        }
+        Ok(forwarder.forward(&packet).map(|out| out.serialize().into()))
+    }
+
+    /// Ambil paket RTCP NACK untuk paket video yang hilang, jika ada
+    ///
+    /// Panggil berkala (mis. tiap 10-20 ms) dan kirim hasilnya ke peer.
+    #[napi]
+    pub fn poll_video_nack(&mut self) -> Option<Buffer> {
+        let now = self.now_ms();
+        let media_ssrc = self.video_nack.media_ssrc()?;
+        let lost = self.video_nack.poll(now, self.rtt_ms);
+        if lost.is_empty() {
+            return None;
+        }
+
+        let sender_ssrc = self.video_packetizer.as_ref().map_or(0, |p| p.ssrc());
+        let nack = RtcpPacket::Nack {
+            sender_ssrc,
+            media_ssrc,
+            lost,
+        };
+        Some(nack.serialize().into())
+    }
+
+    /// Proses paket RTCP dari peer, kembalikan paket RTP yang perlu dikirim ulang
+    #[napi]
+    pub fn handle_rtcp(&mut self, packet: Buffer) -> Result<Vec<Buffer>> {
+        let now = self.now_ms();
+        let now_ntp = self.now_ntp();
+        let video_ssrc = self.video_packetizer.as_ref().map(|p| p.ssrc());
+        let audio_ssrc = self.audio_packetizer.as_ref().map(|p| p.ssrc());
+        let mut resend = Vec::new();
+
+        for rtcp in RtcpPacket::parse_compound(&packet)? {
+            if let Some(ssrc) = video_ssrc {
+                self.video_reports.on_rtcp(&rtcp, ssrc, now, now_ntp);
+            }
+            if let Some(ssrc) = audio_ssrc {
+                self.audio_reports.on_rtcp(&rtcp, ssrc, now, now_ntp);
+            }
+            for stream in self.simulcast.iter_mut() {
+                stream.reports.on_rtcp(&rtcp, stream.packetizer.ssrc(), now, now_ntp);
+            }
+
+            let (sender_ssrc, request) = match rtcp {
+                RtcpPacket::Nack { media_ssrc, lost, .. } => {
+                    let history = if Some(media_ssrc) == video_ssrc {
+                        Some(&mut self.video_history)
+                    } else {
+                        self.simulcast
+                            .iter_mut()
+                            .find(|stream| stream.packetizer.ssrc() == media_ssrc)
+                            .map(|stream| &mut stream.history)
+                    };
+                    if let Some(history) = history {
+                        resend.extend(
+                            history
+                                .on_nack(&lost, now, self.rtt_ms)
+                                .into_iter()
+                                .map(Buffer::from),
+                        );
+                    }
+                    continue;
+                }
+                RtcpPacket::Pli {
+                    sender_ssrc,
+                    media_ssrc,
+                } if self.is_video_ssrc(media_ssrc) => (sender_ssrc, KeyframeRequest::Pli),
+                RtcpPacket::Fir {
+                    sender_ssrc,
+                    media_ssrc,
+                    sequence,
+                } if self.is_video_ssrc(media_ssrc) => (sender_ssrc, KeyframeRequest::Fir(sequence)),
+                _ => continue,
+            };
+
+            if self.keyframe_throttle.on_request(sender_ssrc, request, now, self.rtt_ms) {
+                if let Some(handler) = self.keyframe_handler.as_mut() {
+                    handler(matches!(request, KeyframeRequest::Fir(_)));
+                }
+            }
+        }
+
+        self.video_sent.record(&resend);
+
+        // RTT terukur menggantikan nilai manual dari `set_rtt`
+        if let Some(rtt) = self.video_reports.rtt_ms().or(self.audio_reports.rtt_ms()) {
+            self.set_rtt(rtt);
+        }
+        self.publish_stats();
+        Ok(self.pace(PacketPriority::Retransmission, resend))
+    }
+
+    /// Aktifkan pacer di jalur kirim
+    ///
+    /// Saat aktif, `packetize_*` dan `handle_rtcp` tidak lagi
+    /// mengembalikan paket; semua paket keluar lewat `poll_pacer` sesuai
+    /// bandwidth estimasi dari `adapt_quality`.
+    #[napi]
+    pub fn set_pacing_enabled(&mut self, enabled: bool) {
+        self.pacing_enabled = enabled;
+    }
+
+    /// Ambil paket yang sudah waktunya dikirim dari pacer
+    ///
+    /// Panggil berkala (mis. tiap 5 ms). Setelah pacer dimatikan, sisa
+    /// antrean dikeluarkan sekaligus.
+    #[napi]
+    pub fn poll_pacer(&mut self) -> Vec<Buffer> {
+        let packets = if self.pacing_enabled {
+            self.pacer.poll(self.now_ms())
+        } else {
+            self.pacer.drain()
+        };
+        packets.into_iter().map(Buffer::from).collect()
+    }
+
+    /// Set target rate kirim untuk probing bandwidth (kbps), 0 mematikan
+    ///
+    /// Pacer mengisi selisih antara target dan media dengan paket padding
+    /// yang dibuang penerima.
+    #[napi]
+    pub fn set_probe_rate(&mut self, kbps: u32) {
+        self.pacer.set_padding_rate(kbps);
+    }
+
+    /// Umur paket tertua di antrean pacer (ms)
+    #[napi]
+    pub fn get_pacer_queue_delay_ms(&self) -> u32 {
+        self.pacer.queue_delay_ms(self.now_ms()) as u32
+    }
+
+    /// Ambil compound RTCP report (SR/RR + XR) jika sudah waktunya
+    ///
+    /// Panggil berkala; report dibuat sekitar sekali per detik untuk tiap
+    /// stream dan berisi loss serta jitter stream yang diterima dari peer.
+    #[napi]
+    pub fn poll_rtcp_report(&mut self) -> Option<Buffer> {
+        let now = self.now_ms();
+        if let Some(last) = self.last_report_ms {
+            if now.saturating_sub(last) < RTCP_REPORT_INTERVAL_MS {
+                return None;
+            }
+        }
+        self.last_report_ms = Some(now);
+
+        let now_ntp = self.now_ntp();
+        let mut compound = Vec::new();
+        if let Some(ssrc) = self.video_packetizer.as_ref().map(|p| p.ssrc()) {
+            for rtcp in self.video_reports.build_reports(ssrc, now, now_ntp) {
+                compound.extend(rtcp.serialize());
+            }
+        }
+        for stream in self.simulcast.iter_mut() {
+            // Layer tambahan cukup SR; RR dan XR sudah dikirim stream utama
+            let ssrc = stream.packetizer.ssrc();
+            for rtcp in stream.reports.build_reports(ssrc, now, now_ntp) {
+                if matches!(rtcp, RtcpPacket::SenderReport { .. }) {
+                    compound.extend(rtcp.serialize());
+                }
+            }
+        }
+        if let Some(ssrc) = self.audio_packetizer.as_ref().map(|p| p.ssrc()) {
+            for rtcp in self.audio_reports.build_reports(ssrc, now, now_ntp) {
+                compound.extend(rtcp.serialize());
+            }
+        }
+
+        self.publish_stats();
+        (!compound.is_empty()).then(|| compound.into())
+    }
+
+    /// Ikat engine ke sesi agar `ElaraSession.getMediaStats` ikut diperbarui
+    #[napi]
+    pub fn bind_session(&mut self, session: &ElaraSession) {
+        self.session_stats = Some(session.media_stats_handle());
+        self.session_events = Some(session.events());
+        self.publish_stats();
+    }
+
+    /// Set path MTU (ukuran paket UDP maksimum) untuk packetizer
+    ///
+    /// Biasanya tidak perlu dipanggil langsung; `bind_transport` mengikuti
+    /// hasil MTU discovery transport secara otomatis.
+    #[napi]
+    pub fn set_path_mtu(&mut self, mtu: u32) {
+        self.path_mtu = (mtu as usize).clamp(MIN_PATH_MTU, MAX_PATH_MTU);
+        self.apply_mtu();
+    }
+
+    /// Path MTU yang dipakai packetizer
+    #[napi]
+    pub fn get_path_mtu(&self) -> u32 {
+        self.path_mtu as u32
+    }
+
+    /// Ikat engine ke transport agar ukuran paket mengikuti path MTU
+    #[napi]
+    pub fn bind_transport(&mut self, transport: &TransportManager) {
+        self.transport_mtu = Some(transport.path_mtu_handle());
+        self.refresh_path_mtu();
+    }
+
+    /// Set RTT terukur ke peer (ms)
+    ///
+    /// Jitter buffer video menahan frame minimal ~1.5x RTT agar paket
+    /// hasil retransmisi masih sempat dipakai.
+    #[napi]
+    pub fn set_rtt(&mut self, rtt_ms: u32) {
+        self.rtt_ms = rtt_ms;
+        self.update_video_min_delay();
+    }
+
+    /// Ambil frame video terenkode yang sudah waktunya di-decode
+    ///
+    /// Frame yang kehilangan fragment tidak dikeluarkan agar decoder
+    /// tidak menerima data rusak.
+    #[napi]
+    pub fn poll_video_frames(&mut self) -> Vec<Buffer> {
+        self.update_lip_sync();
+        let now = self.now_ms();
+        let Some(receiver) = self.video_receiver.as_mut() else {
+            return Vec::new();
+        };
+
+        let frames = receiver.poll(now);
+        let depayloader = receiver.depayloader();
+        let keyframe = !frames.is_empty() && depayloader.last_frame_info().keyframe;
+        self.keyframe_requester.on_frames(depayloader.lost_frames(), keyframe);
+
+        frames.into_iter().map(Buffer::from).collect()
+    }
+
+    /// Minta keyframe dari peer, misalnya saat decoder kehilangan sinkronisasi
+    ///
+    /// `full` mengirim FIR (mis. untuk decoder yang baru mulai), selain itu PLI.
+    #[napi]
+    pub fn request_keyframe(&mut self, full: bool) {
+        self.keyframe_requester.request(full);
+    }
+
+    /// Ambil paket RTCP PLI/FIR yang perlu dikirim ke peer, jika ada
+    ///
+    /// Permintaan muncul otomatis saat frame rusak atau NACK menyerah dan
+    /// diulang sampai keyframe diterima. Panggil bersama `poll_video_nack`.
+    #[napi]
+    pub fn poll_keyframe_request(&mut self) -> Option<Buffer> {
+        if self.video_nack.take_keyframe_request() {
+            self.keyframe_requester.request(false);
+        }
+
+        let now = self.now_ms();
+        let sender_ssrc = self.video_packetizer.as_ref().map_or(0, |p| p.ssrc());
+
+        // Switch layer menunggu keyframe dari stream tujuan
+        let switch = self
+            .video_forwarder
+            .as_mut()
+            .and_then(|forwarder| forwarder.poll_keyframe_request(now, self.rtt_ms));
+        if let Some(media_ssrc) = switch {
+            let pli = RtcpPacket::Pli {
+                sender_ssrc,
+                media_ssrc,
+            };
+            return Some(pli.serialize().into());
+        }
+
+        let media_ssrc = self.video_nack.media_ssrc()?;
+        let packet = match self.keyframe_requester.poll(now, self.rtt_ms)? {
+            KeyframeRequest::Pli => RtcpPacket::Pli {
+                sender_ssrc,
+                media_ssrc,
+            },
+            KeyframeRequest::Fir(sequence) => RtcpPacket::Fir {
+                sender_ssrc,
+                media_ssrc,
+                sequence,
+            },
+        };
+        Some(packet.serialize().into())
+    }
+
+    /// Daftarkan callback encoder untuk memaksa keyframe (IDR)
+    ///
+    /// Dipanggil dengan `true` untuk FIR dan `false` untuk PLI, sudah
+    /// dibatasi agar encoder tidak dibanjiri permintaan.
+    #[napi]
+    pub fn on_keyframe_request(&mut self, callback: JsFunction) -> Result<()> {
+        let callback: ThreadsafeFunction<bool, ErrorStrategy::Fatal> = callback
+            .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<bool>| {
+                ctx.env.get_boolean(ctx.value).map(|full| vec![full])
+            })?;
+
+        self.set_keyframe_handler(move |full| {
+            callback.call(full, ThreadsafeFunctionCallMode::NonBlocking);
+        });
+        Ok(())
+    }
+
+    /// Bungkus paket Opus terenkode menjadi paket RTP
+    ///
+    /// Paket DTX (diam) hanya dikirim sesekali, jadi hasilnya bisa kosong.
+    #[napi]
+    pub fn packetize_audio(&mut self, frame: Buffer, timestamp: u32) -> Result<Vec<Buffer>> {
+        self.refresh_path_mtu();
+        let codec = self.audio_config.codec;
+        let now = self.now_ms();
+        let packetizer = self.audio_packetizer.as_mut().ok_or_else(|| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Packetizer untuk codec {} belum didukung", codec.as_str()),
+            )
+        })?;
+
+        let mut packets = Vec::new();
+        for mut packet in packetizer.packetize(&frame, timestamp)? {
+            self.audio_red.wrap(&mut packet);
+            self.audio_reports.on_sent(&packet, now);
+            packets.push(Buffer::from(packet.serialize()));
+        }
+        self.audio_sent.record(&packets);
+        Ok(self.pace(PacketPriority::Audio, packets))
+    }
+
+    /// Masukkan paket RTP audio yang diterima ke jitter buffer
+    #[napi]
+    pub fn receive_audio_packet(&mut self, packet: Buffer) -> Result<()> {
+        let codec = self.audio_config.codec;
+        let now = self.now_ms();
+        let receiver = self.audio_receiver.as_mut().ok_or_else(|| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Depayloader untuk codec {} belum didukung", codec.as_str()),
+            )
+        })?;
+
+        let packet = RtpPacket::parse(&packet)?;
+        if packet.payload_type == PADDING_PAYLOAD_TYPE {
+            return Ok(());
+        }
+        self.audio_reports.on_received(&packet, now);
+        for packet in self.audio_red_decoder.depacketize(packet)? {
+            receiver.push(packet, now);
+        }
+        Ok(())
+    }
+
+    /// Ambil paket Opus yang sudah waktunya di-decode
+    #[napi]
+    pub fn poll_audio_frames(&mut self) -> Vec<Buffer> {
+        self.update_lip_sync();
+        let now = self.now_ms();
+        self.audio_receiver
+            .as_mut()
+            .map(|receiver| receiver.poll(now).into_iter().map(Buffer::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// Set referensi wall clock pengirim untuk stream yang diterima
+    ///
+    /// Dipakai untuk lip sync jika peer membagikan waktu capture lewat
+    /// waktu ELARA; sender report RTCP akan menggantikannya begitu tiba.
+    #[napi]
+    pub fn set_clock_reference(&mut self, kind: MediaKind, rtp_timestamp: u32, wall_clock_ms: f64) {
+        let clock_rate = match kind {
+            MediaKind::Audio => OPUS_CLOCK_RATE,
+            MediaKind::Video => self.video_config.codec.clock_rate(),
+        };
+        let reference = ClockReference {
+            wall_clock_ms,
+            rtp_timestamp,
+            clock_rate,
+        };
+        self.lip_sync.set_reference(kind, reference);
+    }
+
+    /// Selisih A/V saat ini (ms), positif berarti video tertinggal dari audio
+    #[napi]
+    pub fn get_av_offset_ms(&self) -> Option<f64> {
+        self.lip_sync.offset_ms()
+    }
+
+    /// Statistik stream video
+    #[napi]
+    pub fn get_video_stats(&self) -> MediaStats {
+        self.video_sent.to_stats(
+            self.video_receiver.as_ref().map(|receiver| receiver.stats()),
+            &self.video_reports,
+        )
+    }
+
+    /// Statistik stream audio
+    #[napi]
+    pub fn get_audio_stats(&self) -> MediaStats {
+        self.audio_sent.to_stats(
+            self.audio_receiver.as_ref().map(|receiver| receiver.stats()),
+            &self.audio_reports,
+        )
+    }
+
+    /// Cek apakah paket audio terakhir bisa dipakai memulihkan paket
+    /// sebelumnya yang hilang (decode dengan FEC terlebih dulu)
+    #[napi]
+    pub fn is_audio_fec_recovery_pending(&self) -> bool {
+        self.audio_receiver
+            .as_ref()
+            .map(|receiver| receiver.depayloader().fec_recovery_pending())
+            .unwrap_or(false)
+    }
+
+    /// Pengaturan encoder Opus sesuai bitrate dan kondisi jaringan
+    ///
+    /// Bitrate dibatasi `AudioConfig.bitrate` dan `max_audio_bitrate` node.
+    /// Di mode `AudioOnly` FEC selalu aktif dan frame diperpanjang agar
+    /// overhead header lebih kecil di link yang sangat buruk.
+    #[napi]
+    pub fn get_opus_encoder_settings(&self) -> OpusEncoderSettings {
+        let audio_only = self.current_quality == VideoQualityLevel::AudioOnly;
+
+        OpusEncoderSettings {
+            bitrate_kbps: self.audio_bitrate_kbps(),
+            frame_duration_ms: if audio_only { 40 } else { 20 },
+            inband_fec: self.audio_fec && (audio_only || self.last_packet_loss >= 1.0),
+            dtx: self.audio_dtx,
+            expected_packet_loss: self.last_packet_loss.clamp(0.0, 100.0).ceil() as u32,
+        }
+    }
+
+    /// Metadata frame video terakhir dari `poll_video_frames`
+    #[napi]
+    pub fn get_last_video_frame_info(&self) -> Option<VideoFrameInfo> {
+        self.video_receiver.as_ref().map(|receiver| {
+            let info = receiver.depayloader().last_frame_info();
+            VideoFrameInfo {
+                keyframe: info.keyframe,
+                picture_id: info.picture_id.map(u32::from),
+                temporal_layer: info.temporal_layer.map(u32::from),
+                spatial_layer: info.spatial_layer.map(u32::from),
+            }
+        })
+    }
+
     /// Dapatkan level kualitas saat ini
     #[napi]
     pub fn get_quality_level(&self) -> VideoQualityLevel {
         self.current_quality.clone()
     }
 
+    /// Dapatkan quality ladder efektif (sudah dibatasi)
+    #[napi]
+    pub fn get_quality_ladder(&self) -> QualityLadder {
+        self.effective_ladder()
+    }
+
+    /// Ganti quality ladder
+    #[napi]
+    pub fn set_quality_ladder(&mut self, ladder: QualityLadder) -> Result<()> {
+        ladder.validate()?;
+        self.ladder = ladder;
+        Ok(())
+    }
+
+    /// Muat quality ladder dari JSON
+    #[napi]
+    pub fn load_quality_ladder(&mut self, json: String) -> Result<()> {
+        self.ladder = QualityLadder::from_json(&json)?;
+        Ok(())
+    }
+
+    /// Set kemampuan capture kamera device
+    #[napi]
+    pub fn set_capture_capability(&mut self, capability: CaptureCapability) {
+        self.capture = Some(capability);
+    }
+
+    /// Set bitrate video maksimum (kbps), 0 berarti tanpa batas
+    #[napi]
+    pub fn set_max_video_bitrate(&mut self, kbps: u32) {
+        self.max_video_bitrate = kbps;
+        self.allocator.set_max_video(kbps);
+    }
+
+    /// Pembagian bitrate terakhir dari `adapt_quality`
+    ///
+    /// `data_kbps` adalah batas kirim data channel (chat, gift) agar
+    /// tidak mengganggu audio dan video.
+    #[napi]
+    pub fn get_bitrate_allocation(&self) -> Option<BitrateAllocation> {
+        self.allocator.last().cloned()
+    }
+
     /// Adaptasi kualitas berdasarkan kondisi jaringan
     /// 
     /// ELARA secara otomatis menurunkan kualitas saat jaringan buruk
@@ -140,78 +1596,169 @@ impl MediaEngine {
     pub fn adapt_quality(&mut self, bandwidth_kbps: u32, packet_loss: f32) -> VideoQualityLevel {
         // Algoritma adaptasi kualitas ELARA
         // "Experience Degrades, Never Collapses"
-        
-        self.current_quality = if bandwidth_kbps >= 2500 && packet_loss < 1.0 {
-            // Kondisi optimal
-            self.video_config.width = 1920;
-            self.video_config.height = 1080;
-            self.video_config.fps = 30;
-            self.video_config.bitrate = 3000;
-            VideoQualityLevel::High
-        } else if bandwidth_kbps >= 1500 && packet_loss < 3.0 {
-            // Kondisi baik
-            self.video_config.width = 1280;
-            self.video_config.height = 720;
-            self.video_config.fps = 30;
-            self.video_config.bitrate = 2000;
-            VideoQualityLevel::Medium
-        } else if bandwidth_kbps >= 800 && packet_loss < 5.0 {
-            // Kondisi cukup
-            self.video_config.width = 854;
-            self.video_config.height = 480;
-            self.video_config.fps = 24;
-            self.video_config.bitrate = 1000;
-            VideoQualityLevel::Low
-        } else if bandwidth_kbps >= 300 {
-            // Kondisi buruk - turunkan kualitas drastis
-            self.video_config.width = 640;
-            self.video_config.height = 360;
-            self.video_config.fps = 15;
-            self.video_config.bitrate = 500;
-            VideoQualityLevel::VeryLow
+        self.last_packet_loss = packet_loss;
+
+        // Audio dijamin dulu, data dicadangkan, video memakai sisanya
+        let allocation = self.allocator.allocate(bandwidth_kbps);
+        self.pacer.set_rate(bandwidth_kbps);
+        self.audio_config.bitrate = allocation.audio_kbps;
+
+        // FEC menyala saat loss tinggi; overhead-nya dikurangkan dari
+        // porsi video sebelum memilih rung agar total tetap muat
+        let fec = if self.fec_allowed {
+            FecSettings::for_network(packet_loss, self.rtt_ms, self.fec)
         } else {
-            // Kondisi sangat buruk - audio only
-            // PENTING: Tetap terhubung, hanya audio
-            self.video_config.bitrate = 0;
-            VideoQualityLevel::AudioOnly
+            FecSettings::default()
         };
+        self.apply_fec(fec);
+        let audio_overhead = fec.audio_overhead_kbps(self.audio_bitrate_kbps());
+        let available = (allocation.video_kbps.saturating_sub(audio_overhead) as f64
+            * fec.video_share()) as u32;
+
+        let ladder = self.effective_ladder();
+        self.apply_rung(ladder.select(available, packet_loss).cloned());
+        if self.current_quality != VideoQualityLevel::AudioOnly {
+            self.video_config.bitrate = self.video_config.bitrate.min(available);
+        }
+        self.active_video_layers =
+            active_layer_count(self.video_layer_mode, &self.video_layers, available);
 
         self.current_quality.clone()
     }
 
+    /// Izinkan atau larang FEC otomatis dari `adapt_quality`
+    #[napi]
+    pub fn set_fec_allowed(&mut self, allowed: bool) {
+        self.fec_allowed = allowed;
+        if !allowed {
+            self.apply_fec(FecSettings::default());
+        }
+    }
+
+    /// Dapatkan status FEC saat ini
+    #[napi]
+    pub fn get_fec_status(&self) -> FecStatus {
+        let video_overhead = match self.fec.video_group_size {
+            0 => 0,
+            n => self.video_config.bitrate / n as u32,
+        };
+
+        FecStatus {
+            video_group_size: self.fec.video_group_size as u32,
+            audio_redundancy: self.fec.audio_redundancy as u32,
+            overhead_kbps: video_overhead + self.fec.audio_overhead_kbps(self.audio_bitrate_kbps()),
+            video_packets_recovered: self.video_fec_decoder.recovered() as u32,
+            audio_packets_recovered: self.audio_red_decoder.recovered() as u32,
+        }
+    }
+
     /// Set kualitas manual
+    ///
+    /// Jika level tidak ada di ladder, rung terendah yang dipakai.
     #[napi]
     pub fn set_quality(&mut self, level: VideoQualityLevel) {
-        self.current_quality = level.clone();
-        
-        match level {
-            VideoQualityLevel::High => {
-                self.video_config.width = 1920;
-                self.video_config.height = 1080;
-                self.video_config.fps = 30;
-                self.video_config.bitrate = 3000;
-            }
-            VideoQualityLevel::Medium => {
-                self.video_config.width = 1280;
-                self.video_config.height = 720;
-                self.video_config.fps = 30;
-                self.video_config.bitrate = 2000;
-            }
-            VideoQualityLevel::Low => {
-                self.video_config.width = 854;
-                self.video_config.height = 480;
-                self.video_config.fps = 24;
-                self.video_config.bitrate = 1000;
-            }
-            VideoQualityLevel::VeryLow => {
-                self.video_config.width = 640;
-                self.video_config.height = 360;
-                self.video_config.fps = 15;
-                self.video_config.bitrate = 500;
-            }
-            VideoQualityLevel::AudioOnly => {
-                self.video_config.bitrate = 0;
-            }
-        }
+        let rung = self.effective_ladder().manual_rung(&level).cloned();
+        self.apply_rung(rung);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_packet_is_counted_as_one_loss() {
+        // Satu paket hilang lalu datang terlambat, plus satu duplikat
+        let received = JitterStats {
+            packets_received: 3,
+            packets_lost: 1,
+            packets_late: 1,
+            packets_duplicate: 1,
+            ..JitterStats::default()
+        };
+        let stats = SendCounters::default().to_stats(Some(received), &StreamReporter::new(48_000));
+        assert_eq!(stats.packets_lost, 1);
+        assert_eq!((stats.packets_late, stats.packets_duplicate), (1, 1));
+    }
+
+    fn size(rung: &QualityRung) -> (u32, u32, u32, u32) {
+        (rung.width, rung.height, rung.fps, rung.bitrate)
+    }
+
+    #[test]
+    fn ladder_is_clamped_to_bitrate_and_capture() {
+        let ladder = QualityLadder::default();
+        let camera = CaptureCapability { max_width: 1280, max_height: 720, max_fps: 24 };
+        let constrained = ladder.constrained(1200, Some(&camera));
+        let sizes: Vec<_> = constrained.rungs.iter().map(size).collect();
+        assert_eq!(
+            sizes,
+            vec![(1280, 720, 24, 1200), (1280, 720, 24, 1200), (854, 480, 24, 1000), (640, 360, 15, 500)]
+        );
+
+        // Kamera 4:3 tetap menjaga aspect ratio rung dan dimensi genap
+        let camera = CaptureCapability { max_width: 640, max_height: 480, max_fps: 30 };
+        let constrained = ladder.constrained(0, Some(&camera));
+        assert_eq!(size(&constrained.rungs[0]), (640, 360, 30, 3000));
+        assert_eq!(size(&constrained.rungs[2]), (640, 358, 24, 1000));
+
+        // Tanpa batas apa pun ladder tidak berubah
+        let unchanged = ladder.constrained(0, None);
+        assert_eq!(
+            unchanged.rungs.iter().map(size).collect::<Vec<_>>(),
+            ladder.rungs.iter().map(size).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn malformed_ladders_are_rejected() {
+        assert!(QualityLadder::default().validate().is_ok());
+        assert!(QualityLadder { rungs: Vec::new() }.validate().is_err());
+
+        let mut audio_only = QualityLadder::default();
+        audio_only.rungs[3].level = VideoQualityLevel::AudioOnly;
+        assert!(audio_only.validate().is_err());
+
+        let mut zero_fps = QualityLadder::default();
+        zero_fps.rungs[1].fps = 0;
+        assert!(zero_fps.validate().is_err());
+
+        let mut unordered = QualityLadder::default();
+        unordered.rungs.swap(0, 1);
+        assert!(unordered.validate().is_err());
+    }
+
+    #[test]
+    fn ladder_is_loaded_from_json() {
+        let json = r#"{"rungs": [
+            {"level": "medium", "width": 1280, "height": 720, "fps": 30, "bitrate": 1800,
+             "minBandwidthKbps": 1200, "maxPacketLoss": 2.0},
+            {"level": "veryLow", "width": 480, "height": 270, "fps": 15, "bitrate": 300,
+             "minBandwidthKbps": 200, "maxPacketLoss": 100.0}
+        ]}"#;
+        let ladder = QualityLadder::from_json(json).unwrap();
+        assert_eq!(ladder.select(1500, 1.0).map(|rung| rung.level), Some(VideoQualityLevel::Medium));
+        assert_eq!(ladder.select(1500, 5.0).map(|rung| rung.level), Some(VideoQualityLevel::VeryLow));
+        assert!(ladder.select(100, 0.0).is_none());
+
+        assert!(QualityLadder::from_json(r#"{"rungs": 3}"#).is_err());
+        assert!(QualityLadder::from_json(r#"{"rungs": []}"#).is_err());
+        let unordered = json.replace("1200", "100");
+        assert!(QualityLadder::from_json(&unordered).is_err());
+    }
+
+    #[test]
+    fn manual_quality_falls_back_to_lowest_rung() {
+        let camera = CaptureCapability { max_width: 1280, max_height: 720, max_fps: 24 };
+        let ladder = QualityLadder::default().constrained(1500, Some(&camera));
+        let high = ladder.manual_rung(&VideoQualityLevel::High).unwrap();
+        assert_eq!(size(high), (1280, 720, 24, 1500));
+
+        // Level yang tidak ada di ladder jatuh ke rung terendah
+        let mut ladder = QualityLadder::default();
+        ladder.rungs.remove(1);
+        let fallback = ladder.manual_rung(&VideoQualityLevel::Medium).unwrap();
+        assert_eq!(fallback.level, VideoQualityLevel::VeryLow);
+        assert!(ladder.manual_rung(&VideoQualityLevel::AudioOnly).is_none());
     }
 }
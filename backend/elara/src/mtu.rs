@@ -0,0 +1,271 @@
+//! Modul MTU ELARA
+//!
+//! Path MTU discovery berbasis probe (mirip DPLPMTUD, RFC 8899). Probe
+//! berukuran tertentu dikirim lewat pasangan kandidat terpilih dengan DF
+//! dan peer membalas ack; ukuran terbesar yang sampai dipakai packetizer
+//! media. Tanpa ack, MTU tetap di default aman sehingga fragmentasi di
+//! jalur relay tidak diam-diam mematikan video.
+
+use crate::rtp::DEFAULT_MTU;
+
+/// Payload UDP maksimum di jalur Ethernet IPv4 (1500 - 20 - 8)
+pub const MAX_PATH_MTU: usize = 1472;
+
+/// Payload UDP minimum yang dijamin setiap jalur IPv4 (576 - 20 - 8)
+pub const MIN_PATH_MTU: usize = 548;
+
+/// Penanda awal paket probe; byte pertama 0xEE tidak bentrok dengan
+/// STUN, DTLS, TURN ChannelData maupun RTP/RTCP (RFC 7983)
+const PROBE_MAGIC: [u8; 4] = [0xEE, b'M', b'T', b'U'];
+
+/// Panjang header probe: magic, jenis, id, ukuran
+const PROBE_HEADER_LEN: usize = 11;
+
+const KIND_PROBE: u8 = 0;
+const KIND_ACK: u8 = 1;
+
+/// Probe dianggap hilang setelah waktu ini (ms)
+const PROBE_TIMEOUT_MS: u64 = 1000;
+
+/// Probe ukuran sama diulang sebelum dianggap gagal
+const MAX_PROBE_ATTEMPTS: u32 = 3;
+
+/// Pencarian berhenti saat selisih batas atas dan bawah sekecil ini
+const SEARCH_PRECISION: usize = 16;
+
+/// Ulangi pencarian berkala, jalur bisa berubah (RFC 8899 PMTU_RAISE_TIMER)
+const RESEARCH_INTERVAL_MS: u64 = 600_000;
+
+/// Pesan probe MTU
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MtuMessage {
+    /// Probe dari peer, harus dibalas ack
+    Probe { id: u32, size: usize },
+    /// Ack dari peer untuk probe kita
+    Ack { id: u32, size: usize },
+}
+
+impl MtuMessage {
+    /// Parse paket probe/ack, `None` jika bukan paket MTU
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < PROBE_HEADER_LEN || data[..4] != PROBE_MAGIC {
+            return None;
+        }
+        let id = u32::from_be_bytes([data[5], data[6], data[7], data[8]]);
+        let size = u16::from_be_bytes([data[9], data[10]]) as usize;
+        match data[4] {
+            KIND_PROBE => Some(MtuMessage::Probe { id, size: data.len() }),
+            KIND_ACK => Some(MtuMessage::Ack { id, size }),
+            _ => None,
+        }
+    }
+
+    /// Serialisasi; probe diisi nol sampai ukurannya
+    pub fn serialize(&self) -> Vec<u8> {
+        let (kind, id, size) = match *self {
+            MtuMessage::Probe { id, size } => (KIND_PROBE, id, size),
+            MtuMessage::Ack { id, size } => (KIND_ACK, id, size),
+        };
+
+        let mut buf = Vec::with_capacity(size.max(PROBE_HEADER_LEN));
+        buf.extend_from_slice(&PROBE_MAGIC);
+        buf.push(kind);
+        buf.extend_from_slice(&id.to_be_bytes());
+        buf.extend_from_slice(&(size as u16).to_be_bytes());
+        if kind == KIND_PROBE {
+            buf.resize(size.max(PROBE_HEADER_LEN), 0);
+        }
+        buf
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PendingProbe {
+    id: u32,
+    size: usize,
+    sent_ms: u64,
+    attempts: u32,
+}
+
+/// Pencari path MTU untuk satu pasangan kandidat
+#[derive(Debug)]
+pub struct PathMtuDiscovery {
+    enabled: bool,
+    confirmed: usize,
+    ceiling: usize,
+    pending: Option<PendingProbe>,
+    next_id: u32,
+    done_ms: Option<u64>,
+}
+
+impl Default for PathMtuDiscovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathMtuDiscovery {
+    /// Buat pencari baru, mulai dari MTU default aman
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            confirmed: DEFAULT_MTU,
+            ceiling: MAX_PATH_MTU + 1,
+            pending: None,
+            next_id: 0,
+            done_ms: None,
+        }
+    }
+
+    /// Mulai ulang untuk jalur baru
+    ///
+    /// `probing` false untuk jalur stream (TCP/TLS) yang tidak mengenal
+    /// fragmentasi IP; MTU tetap di default aman.
+    pub fn reset(&mut self, probing: bool) {
+        *self = Self {
+            enabled: probing,
+            next_id: self.next_id,
+            ..Self::new()
+        };
+    }
+
+    /// Path MTU terkonfirmasi (ukuran paket UDP maksimum)
+    pub fn mtu(&self) -> usize {
+        self.confirmed
+    }
+
+    /// Pencarian sedang berjalan
+    pub fn is_searching(&self) -> bool {
+        self.enabled && self.done_ms.is_none()
+    }
+
+    fn next_size(&self) -> Option<usize> {
+        if self.ceiling - self.confirmed <= SEARCH_PRECISION {
+            return None;
+        }
+        // Coba ukuran maksimum dulu, kebanyakan jalur lolos
+        Some(if self.ceiling > MAX_PATH_MTU {
+            MAX_PATH_MTU
+        } else {
+            (self.confirmed + self.ceiling) / 2
+        })
+    }
+
+    /// Probe yang perlu dikirim sekarang, jika ada
+    pub fn poll(&mut self, now_ms: u64) -> Option<Vec<u8>> {
+        if !self.enabled {
+            return None;
+        }
+        if let Some(done) = self.done_ms {
+            if now_ms.saturating_sub(done) < RESEARCH_INTERVAL_MS {
+                return None;
+            }
+            // Cari lagi ke atas, MTU terkonfirmasi tetap dipakai
+            self.ceiling = MAX_PATH_MTU + 1;
+            self.done_ms = None;
+        }
+
+        let probe = match self.pending {
+            Some(pending) if now_ms.saturating_sub(pending.sent_ms) < PROBE_TIMEOUT_MS => {
+                return None;
+            }
+            Some(pending) if pending.attempts < MAX_PROBE_ATTEMPTS => PendingProbe {
+                sent_ms: now_ms,
+                attempts: pending.attempts + 1,
+                ..pending
+            },
+            pending => {
+                if let Some(failed) = pending {
+                    // Ukuran ini tidak lolos jalur
+                    self.ceiling = failed.size;
+                }
+                let Some(size) = self.next_size() else {
+                    self.pending = None;
+                    self.done_ms = Some(now_ms);
+                    return None;
+                };
+                self.next_id = self.next_id.wrapping_add(1);
+                PendingProbe {
+                    id: self.next_id,
+                    size,
+                    sent_ms: now_ms,
+                    attempts: 1,
+                }
+            }
+        };
+
+        self.pending = Some(probe);
+        Some(MtuMessage::Probe { id: probe.id, size: probe.size }.serialize())
+    }
+
+    /// Proses ack dari peer; `true` jika MTU terkonfirmasi berubah
+    pub fn on_ack(&mut self, id: u32, size: usize, now_ms: u64) -> bool {
+        let Some(pending) = self.pending else {
+            return false;
+        };
+        if pending.id != id || size < pending.size {
+            return false;
+        }
+
+        self.pending = None;
+        let changed = pending.size > self.confirmed;
+        self.confirmed = self.confirmed.max(pending.size);
+        if self.next_size().is_none() {
+            self.done_ms = Some(now_ms);
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Jalankan pencarian di jalur dengan MTU `path_mtu`
+    fn run(discovery: &mut PathMtuDiscovery, path_mtu: usize) -> u64 {
+        let mut now = 0;
+        while discovery.is_searching() && now < 60_000 {
+            if let Some(probe) = discovery.poll(now) {
+                if probe.len() <= path_mtu {
+                    let Some(MtuMessage::Probe { id, size }) = MtuMessage::parse(&probe) else {
+                        panic!("bukan probe");
+                    };
+                    let ack = MtuMessage::Ack { id, size }.serialize();
+                    if let Some(MtuMessage::Ack { id, size }) = MtuMessage::parse(&ack) {
+                        discovery.on_ack(id, size, now + 50);
+                    }
+                }
+            }
+            now += 100;
+        }
+        now
+    }
+
+    #[test]
+    fn full_ethernet_path_is_confirmed_at_once() {
+        let mut discovery = PathMtuDiscovery::new();
+        run(&mut discovery, 1500);
+        assert_eq!(discovery.mtu(), MAX_PATH_MTU);
+    }
+
+    #[test]
+    fn binary_search_finds_smaller_relay_path() {
+        let mut discovery = PathMtuDiscovery::new();
+        run(&mut discovery, 1400);
+        assert!(discovery.mtu() <= 1400);
+        assert!(1400 - discovery.mtu() <= SEARCH_PRECISION);
+    }
+
+    #[test]
+    fn lost_probes_keep_safe_default() {
+        let mut discovery = PathMtuDiscovery::new();
+        run(&mut discovery, 1000);
+        assert_eq!(discovery.mtu(), DEFAULT_MTU);
+        assert!(!discovery.is_searching());
+
+        discovery.reset(false);
+        assert_eq!(discovery.poll(0), None);
+        assert_eq!(discovery.mtu(), DEFAULT_MTU);
+        assert!(MtuMessage::parse(&[0x80; 20]).is_none());
+    }
+}
@@ -0,0 +1,357 @@
+//! Modul NACK ELARA
+//!
+//! Retransmisi video berbasis NACK. Receiver mendeteksi celah nomor urut
+//! dan meminta ulang paket yang hilang; sender menyimpan riwayat paket
+//! terkirim dan mengirim ulang dalam batas budget bitrate retransmisi,
+//! sehingga loss sedang di jaringan seluler tidak membekukan video
+//! sampai keyframe berikutnya.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::rtp::{RtpPacket, Unwrapper};
+
+/// Tunggu sebentar sebelum NACK pertama, paket mungkin hanya tertukar urutan
+const REORDER_WAIT_MS: u64 = 10;
+
+/// Interval minimum antar NACK untuk paket yang sama
+const MIN_NACK_INTERVAL_MS: u64 = 20;
+
+/// Batas NACK per paket sebelum menyerah
+const MAX_NACK_RETRIES: u32 = 10;
+
+/// Paket lebih tua dari ini tidak berguna lagi bagi decoder
+const MAX_MISSING_AGE_MS: u64 = 1000;
+
+/// Celah lebih besar dari ini lebih murah dipulihkan dengan keyframe
+const MAX_MISSING_PACKETS: usize = 500;
+
+/// Umur riwayat retransmisi di sisi sender
+const HISTORY_AGE_MS: u64 = 1000;
+
+/// Kapasitas riwayat retransmisi
+const MAX_HISTORY_PACKETS: usize = 2048;
+
+/// Budget retransmisi boleh menabung hingga durasi ini
+const BUDGET_WINDOW_MS: f64 = 500.0;
+
+struct MissingPacket {
+    first_seen_ms: u64,
+    last_sent_ms: Option<u64>,
+    retries: u32,
+}
+
+/// Generator NACK di sisi receiver
+#[derive(Default)]
+pub struct NackGenerator {
+    sequence: Unwrapper,
+    highest: Option<i64>,
+    missing: BTreeMap<i64, MissingPacket>,
+    media_ssrc: Option<u32>,
+    keyframe_needed: bool,
+}
+
+impl NackGenerator {
+    /// Buat generator baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// SSRC stream remote yang dipantau
+    pub fn media_ssrc(&self) -> Option<u32> {
+        self.media_ssrc
+    }
+
+    /// Jumlah paket yang masih ditunggu
+    pub fn missing_count(&self) -> usize {
+        self.missing.len()
+    }
+
+    /// Catat paket yang diterima (termasuk hasil retransmisi)
+    pub fn on_packet(&mut self, packet: &RtpPacket, now_ms: u64) {
+        if self.media_ssrc.is_some_and(|ssrc| ssrc != packet.ssrc) {
+            // Stream baru (mis. ganti layer simulcast), nomor urut lama tidak berlaku
+            *self = Self::default();
+        }
+        self.media_ssrc = Some(packet.ssrc);
+        let sequence = self.sequence.unwrap(packet.sequence_number as u32, 16);
+
+        let highest = match self.highest {
+            None => {
+                self.highest = Some(sequence);
+                return;
+            }
+            Some(highest) => highest,
+        };
+
+        if sequence <= highest {
+            // Paket telat atau retransmisi
+            self.missing.remove(&sequence);
+            return;
+        }
+
+        let gap = (sequence - highest - 1) as usize;
+        if gap > MAX_MISSING_PACKETS || self.missing.len() + gap > MAX_MISSING_PACKETS {
+            self.missing.clear();
+            self.keyframe_needed = true;
+        } else {
+            for missing in highest + 1..sequence {
+                self.missing.insert(
+                    missing,
+                    MissingPacket {
+                        first_seen_ms: now_ms,
+                        last_sent_ms: None,
+                        retries: 0,
+                    },
+                );
+            }
+        }
+        self.highest = Some(sequence);
+    }
+
+    /// Nomor urut yang perlu di-NACK sekarang
+    ///
+    /// Paket yang sama tidak diminta ulang sebelum satu RTT berlalu, karena
+    /// retransmisi sebelumnya mungkin masih di jalan.
+    pub fn poll(&mut self, now_ms: u64, rtt_ms: u32) -> Vec<u16> {
+        let interval = (rtt_ms as u64).max(MIN_NACK_INTERVAL_MS);
+        let mut nacks = Vec::new();
+        let mut give_up = Vec::new();
+
+        for (&sequence, missing) in self.missing.iter_mut() {
+            let age = now_ms.saturating_sub(missing.first_seen_ms);
+            if age > MAX_MISSING_AGE_MS || missing.retries >= MAX_NACK_RETRIES {
+                give_up.push(sequence);
+                continue;
+            }
+
+            let due = match missing.last_sent_ms {
+                None => age >= REORDER_WAIT_MS,
+                Some(last) => now_ms.saturating_sub(last) >= interval,
+            };
+            if due {
+                missing.last_sent_ms = Some(now_ms);
+                missing.retries += 1;
+                nacks.push(sequence as u16);
+            }
+        }
+
+        if !give_up.is_empty() {
+            for sequence in give_up {
+                self.missing.remove(&sequence);
+            }
+            self.keyframe_needed = true;
+        }
+
+        nacks
+    }
+
+    /// Ambil dan reset tanda bahwa NACK menyerah dan keyframe dibutuhkan
+    pub fn take_keyframe_request(&mut self) -> bool {
+        std::mem::take(&mut self.keyframe_needed)
+    }
+}
+
+struct StoredPacket {
+    data: Vec<u8>,
+    sent_ms: u64,
+    last_retransmit_ms: Option<u64>,
+}
+
+/// Riwayat paket terkirim di sisi sender untuk retransmisi
+pub struct RetransmissionHistory {
+    packets: HashMap<u16, StoredPacket>,
+    order: VecDeque<u16>,
+    max_bitrate_kbps: u32,
+    budget_bytes: f64,
+    last_refill_ms: Option<u64>,
+    retransmitted_packets: u64,
+    retransmitted_bytes: u64,
+}
+
+impl RetransmissionHistory {
+    /// Buat riwayat dengan budget bitrate retransmisi (kbps)
+    pub fn new(max_bitrate_kbps: u32) -> Self {
+        Self {
+            packets: HashMap::new(),
+            order: VecDeque::new(),
+            max_bitrate_kbps,
+            budget_bytes: 0.0,
+            last_refill_ms: None,
+            retransmitted_packets: 0,
+            retransmitted_bytes: 0,
+        }
+    }
+
+    /// Ubah budget bitrate retransmisi (kbps)
+    pub fn set_max_bitrate(&mut self, kbps: u32) {
+        self.max_bitrate_kbps = kbps;
+    }
+
+    /// Total paket dan bytes yang dikirim ulang
+    pub fn retransmitted(&self) -> (u64, u64) {
+        (self.retransmitted_packets, self.retransmitted_bytes)
+    }
+
+    /// Simpan paket yang baru dikirim
+    pub fn store(&mut self, sequence_number: u16, data: Vec<u8>, now_ms: u64) {
+        let stored = StoredPacket {
+            data,
+            sent_ms: now_ms,
+            last_retransmit_ms: None,
+        };
+        if self.packets.insert(sequence_number, stored).is_none() {
+            self.order.push_back(sequence_number);
+        }
+        // Budget mulai terisi sejak paket pertama, agar NACK pertama dilayani
+        self.last_refill_ms.get_or_insert(now_ms);
+
+        while let Some(&oldest) = self.order.front() {
+            let expired = match self.packets.get(&oldest) {
+                Some(packet) => now_ms.saturating_sub(packet.sent_ms) > HISTORY_AGE_MS,
+                None => true,
+            };
+            if !expired && self.order.len() <= MAX_HISTORY_PACKETS {
+                break;
+            }
+            self.order.pop_front();
+            self.packets.remove(&oldest);
+        }
+    }
+
+    fn refill(&mut self, now_ms: u64) {
+        let rate_bytes_per_ms = self.max_bitrate_kbps as f64 / 8.0;
+        let elapsed = self
+            .last_refill_ms
+            .map_or(0, |last| now_ms.saturating_sub(last)) as f64;
+        self.budget_bytes = (self.budget_bytes + rate_bytes_per_ms * elapsed)
+            .min(rate_bytes_per_ms * BUDGET_WINDOW_MS);
+        self.last_refill_ms = Some(now_ms);
+    }
+
+    /// Proses NACK, kembalikan paket yang dikirim ulang
+    ///
+    /// Paket yang sudah dikirim ulang kurang dari satu RTT lalu dilewati,
+    /// dan retransmisi berhenti saat budget bitrate habis.
+    pub fn on_nack(&mut self, lost: &[u16], now_ms: u64, rtt_ms: u32) -> Vec<Vec<u8>> {
+        self.refill(now_ms);
+        let mut resend = Vec::new();
+
+        for sequence in lost {
+            let Some(stored) = self.packets.get_mut(sequence) else {
+                continue;
+            };
+
+            if let Some(last) = stored.last_retransmit_ms {
+                if now_ms.saturating_sub(last) < rtt_ms as u64 {
+                    continue;
+                }
+            }
+
+            let size = stored.data.len() as f64;
+            if size > self.budget_bytes {
+                break;
+            }
+
+            self.budget_bytes -= size;
+            stored.last_retransmit_ms = Some(now_ms);
+            self.retransmitted_packets += 1;
+            self.retransmitted_bytes += stored.data.len() as u64;
+            resend.push(stored.data.clone());
+        }
+
+        resend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(sequence_number: u16) -> RtpPacket {
+        RtpPacket {
+            payload_type: 96,
+            sequence_number,
+            timestamp: 0,
+            ssrc: 0x1234,
+            marker: false,
+            payload: vec![0; 100],
+        }
+    }
+
+    #[test]
+    fn gap_is_nacked_after_reorder_wait() {
+        let mut generator = NackGenerator::new();
+        generator.on_packet(&packet(65534), 0);
+        generator.on_packet(&packet(1), 0);
+
+        assert!(generator.poll(5, 100).is_empty());
+        assert_eq!(generator.poll(10, 100), vec![65535, 0]);
+
+        // Tidak diminta ulang sebelum satu RTT
+        assert!(generator.poll(50, 100).is_empty());
+        generator.on_packet(&packet(65535), 60);
+        assert_eq!(generator.poll(110, 100), vec![0]);
+    }
+
+    #[test]
+    fn reordered_packet_is_not_nacked() {
+        let mut generator = NackGenerator::new();
+        generator.on_packet(&packet(10), 0);
+        generator.on_packet(&packet(12), 1);
+        generator.on_packet(&packet(11), 3);
+
+        assert!(generator.poll(20, 100).is_empty());
+        assert_eq!(generator.missing_count(), 0);
+    }
+
+    #[test]
+    fn gives_up_and_requests_keyframe() {
+        let mut generator = NackGenerator::new();
+        generator.on_packet(&packet(0), 0);
+        generator.on_packet(&packet(2), 0);
+        assert!(!generator.poll(10, 100).is_empty());
+        assert!(generator.poll(MAX_MISSING_AGE_MS + 1, 100).is_empty());
+        assert!(generator.take_keyframe_request());
+        assert!(!generator.take_keyframe_request());
+
+        generator.on_packet(&packet(2 + MAX_MISSING_PACKETS as u16 + 2), 2000);
+        assert_eq!(generator.missing_count(), 0);
+        assert!(generator.take_keyframe_request());
+    }
+
+    #[test]
+    fn new_ssrc_resets_missing_packets() {
+        let mut generator = NackGenerator::new();
+        generator.on_packet(&packet(10), 0);
+        generator.on_packet(&packet(12), 0);
+        assert_eq!(generator.missing_count(), 1);
+
+        let mut other = packet(30000);
+        other.ssrc = 0x5678;
+        generator.on_packet(&other, 5);
+        assert_eq!(generator.missing_count(), 0);
+        assert_eq!(generator.media_ssrc(), Some(0x5678));
+        assert!(!generator.take_keyframe_request());
+    }
+
+    #[test]
+    fn history_respects_rtt_and_budget() {
+        // 80 kbps = 10 bytes/ms
+        let mut history = RetransmissionHistory::new(80);
+        for sequence in 0..10u16 {
+            history.store(sequence, vec![0; 500], 0);
+        }
+
+        // Budget 100 ms sejak paket pertama = 1000 bytes = dua paket
+        assert_eq!(history.on_nack(&[1, 2, 3], 100, 50).len(), 2);
+
+        // Paket 1 baru dikirim ulang, tunggu satu RTT
+        assert_eq!(history.on_nack(&[1, 3], 200, 150).len(), 1);
+        assert_eq!(history.on_nack(&[1], 300, 150).len(), 1);
+        assert_eq!(history.retransmitted(), (4, 2000));
+
+        // Paket kedaluwarsa dibuang dari riwayat
+        history.store(10, vec![0; 500], HISTORY_AGE_MS + 1);
+        assert!(history.on_nack(&[4], HISTORY_AGE_MS + 400, 50).is_empty());
+    }
+}
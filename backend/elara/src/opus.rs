@@ -0,0 +1,353 @@
+//! Modul Opus ELARA
+//!
+//! Payload format RTP untuk Opus (RFC 7587). Satu paket Opus per paket
+//! RTP; paket DTX (diam) tidak dikirim kecuali sesekali sebagai keep-alive,
+//! dan flag in-band FEC (LBRR) dibaca agar receiver bisa memulihkan paket
+//! yang hilang dari paket berikutnya.
+
+use crate::rtp::{Depayloader, FrameInfo, PacketError, Payloader, RtpPacket};
+
+/// Clock rate RTP Opus selalu 48 kHz (RFC 7587)
+pub const OPUS_CLOCK_RATE: u32 = 48_000;
+
+/// Bitrate minimum encoder Opus (kbps)
+pub const OPUS_MIN_BITRATE: u32 = 6;
+
+/// Paket DTX dikirim sekali tiap interval ini agar peer tahu stream hidup
+const DTX_KEEPALIVE_US: u32 = 400_000;
+
+/// Mode encoder Opus dari TOC
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpusMode {
+    /// SILK (speech)
+    Silk,
+    /// SILK + CELT
+    Hybrid,
+    /// CELT (music / low delay)
+    Celt,
+}
+
+/// Informasi paket Opus hasil parse TOC
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpusPacketInfo {
+    /// Mode encoder
+    pub mode: OpusMode,
+    /// Stereo
+    pub stereo: bool,
+    /// Jumlah frame dalam paket
+    pub frame_count: u32,
+    /// Durasi satu frame (mikrodetik)
+    pub frame_duration_us: u32,
+    /// Paket membawa in-band FEC (LBRR) untuk paket sebelumnya
+    pub has_fec: bool,
+    /// Paket DTX (diam, 1-2 byte)
+    pub is_dtx: bool,
+}
+
+impl OpusPacketInfo {
+    /// Durasi paket (mikrodetik)
+    pub fn duration_us(&self) -> u32 {
+        self.frame_count * self.frame_duration_us
+    }
+
+    /// Durasi paket dalam sampel 48 kHz
+    pub fn samples(&self) -> u32 {
+        self.duration_us() * (OPUS_CLOCK_RATE / 1000) / 1000
+    }
+}
+
+fn read_frame_length(data: &[u8], offset: usize) -> Result<(usize, usize), PacketError> {
+    let first = *data.get(offset).ok_or(PacketError::Truncated {
+        needed: offset + 1,
+        actual: data.len(),
+    })? as usize;
+
+    if first < 252 {
+        Ok((first, 1))
+    } else {
+        let second = *data.get(offset + 1).ok_or(PacketError::Truncated {
+            needed: offset + 2,
+            actual: data.len(),
+        })? as usize;
+        Ok((second * 4 + first, 2))
+    }
+}
+
+/// Parse TOC dan cari awal frame pertama (RFC 6716 bagian 3)
+pub fn parse_opus_packet(packet: &[u8]) -> Result<OpusPacketInfo, PacketError> {
+    let toc = *packet.first().ok_or(PacketError::Truncated { needed: 1, actual: 0 })?;
+    let config = toc >> 3;
+    let stereo = toc & 0x04 != 0;
+
+    let (mode, frame_duration_us) = match config {
+        0..=11 => (OpusMode::Silk, [10_000, 20_000, 40_000, 60_000][(config % 4) as usize]),
+        12..=15 => (OpusMode::Hybrid, [10_000, 20_000][(config % 2) as usize]),
+        _ => (OpusMode::Celt, [2_500, 5_000, 10_000, 20_000][(config % 4) as usize]),
+    };
+
+    let (frame_count, first_frame) = match toc & 0x03 {
+        0 => (1, 1),
+        1 => (2, 1),
+        2 => {
+            let (_, len) = read_frame_length(packet, 1)?;
+            (2, 1 + len)
+        }
+        _ => {
+            let header = *packet.get(1).ok_or(PacketError::Truncated { needed: 2, actual: packet.len() })?;
+            let count = (header & 0x3F) as u32;
+            if count == 0 || count * frame_duration_us > 120_000 {
+                return Err(PacketError::InvalidPayload(format!("jumlah frame Opus tidak valid: {}", count)));
+            }
+
+            let mut offset = 2;
+            if header & 0x40 != 0 {
+                // Panjang padding: byte 255 berarti lanjut
+                loop {
+                    let pad = *packet.get(offset).ok_or(PacketError::Truncated {
+                        needed: offset + 1,
+                        actual: packet.len(),
+                    })?;
+                    offset += 1;
+                    if pad != 255 {
+                        break;
+                    }
+                }
+            }
+            if header & 0x80 != 0 {
+                // VBR: panjang setiap frame kecuali yang terakhir
+                for _ in 0..count - 1 {
+                    let (_, len) = read_frame_length(packet, offset)?;
+                    offset += len;
+                }
+            }
+            (count, offset)
+        }
+    };
+
+    let is_dtx = packet.len() <= 2;
+
+    // Flag VAD dan LBRR adalah simbol pertama range coder dengan
+    // probabilitas seragam, jadi bisa dibaca langsung dari bit teratas
+    // byte pertama frame pertama
+    let has_fec = match (mode, packet.get(first_frame)) {
+        (OpusMode::Celt, _) | (_, None) => false,
+        (_, Some(&byte)) if !is_dtx => {
+            let silk_frames = (frame_duration_us / 20_000).max(1);
+            let channels = if stereo { 2 } else { 1 };
+            (0..channels).any(|n| {
+                let bit = (n + 1) * (silk_frames + 1) - 1;
+                bit < 8 && byte & (0x80 >> bit) != 0
+            })
+        }
+        _ => false,
+    };
+
+    Ok(OpusPacketInfo {
+        mode,
+        stereo,
+        frame_count,
+        frame_duration_us,
+        has_fec,
+        is_dtx,
+    })
+}
+
+/// Payloader Opus
+#[derive(Default)]
+pub struct OpusPayloader {
+    silence_us: u32,
+}
+
+impl OpusPayloader {
+    /// Buat payloader baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Payloader for OpusPayloader {
+    fn payload(&mut self, frame: &[u8], max_payload: usize) -> Result<Vec<Vec<u8>>, PacketError> {
+        if frame.len() > max_payload {
+            // Paket Opus tidak boleh dipecah
+            return Err(PacketError::MtuTooSmall(max_payload));
+        }
+
+        let info = parse_opus_packet(frame)?;
+        if info.is_dtx {
+            // Kirim DTX pertama lalu sesekali sebagai keep-alive
+            let send = self.silence_us == 0 || self.silence_us >= DTX_KEEPALIVE_US;
+            if self.silence_us >= DTX_KEEPALIVE_US {
+                self.silence_us = 0;
+            }
+            self.silence_us += info.duration_us();
+            if !send {
+                return Ok(Vec::new());
+            }
+        } else {
+            self.silence_us = 0;
+        }
+
+        Ok(vec![frame.to_vec()])
+    }
+}
+
+/// Depayloader Opus
+#[derive(Default)]
+pub struct OpusDepayloader {
+    last_sequence: Option<u16>,
+    expected_timestamp: Option<u32>,
+    last_packet: Option<OpusPacketInfo>,
+    lost_packets: u64,
+    dtx_samples: u64,
+    fec_recovery_pending: bool,
+}
+
+impl OpusDepayloader {
+    /// Buat depayloader baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Info paket terakhir
+    pub fn last_packet(&self) -> Option<&OpusPacketInfo> {
+        self.last_packet.as_ref()
+    }
+
+    /// Total sampel diam karena DTX (bukan loss; isi dengan comfort noise)
+    pub fn dtx_samples(&self) -> u64 {
+        self.dtx_samples
+    }
+
+    /// Tepat satu paket sebelum paket terakhir hilang dan paket terakhir
+    /// membawa FEC; decoder sebaiknya decode dengan FEC dulu untuk
+    /// memulihkan paket yang hilang
+    pub fn fec_recovery_pending(&self) -> bool {
+        self.fec_recovery_pending
+    }
+}
+
+impl Depayloader for OpusDepayloader {
+    fn push(&mut self, packet: &RtpPacket) -> Result<Option<Vec<u8>>, PacketError> {
+        let info = parse_opus_packet(&packet.payload)?;
+
+        let mut lost = 0u16;
+        if let Some(last) = self.last_sequence {
+            lost = packet.sequence_number.wrapping_sub(last).wrapping_sub(1);
+            // Paket lama / duplikat (seharusnya sudah disaring jitter buffer)
+            if lost >= 0x8000 {
+                return Ok(None);
+            }
+        }
+
+        if lost == 0 {
+            // Timestamp melompat tanpa celah sequence berarti pengirim DTX
+            if let Some(expected) = self.expected_timestamp {
+                let gap = packet.timestamp.wrapping_sub(expected);
+                if gap > 0 && gap < 0x8000_0000 {
+                    self.dtx_samples += gap as u64;
+                }
+            }
+        }
+
+        self.lost_packets += lost as u64;
+        self.fec_recovery_pending = lost == 1 && info.has_fec;
+        self.last_sequence = Some(packet.sequence_number);
+        self.expected_timestamp = Some(packet.timestamp.wrapping_add(info.samples()));
+        self.last_packet = Some(info);
+
+        Ok(Some(packet.payload.clone()))
+    }
+
+    fn reset(&mut self) {
+        self.expected_timestamp = None;
+        self.fec_recovery_pending = false;
+    }
+
+    fn lost_frames(&self) -> u64 {
+        self.lost_packets
+    }
+
+    fn last_frame_info(&self) -> FrameInfo {
+        // Setiap paket audio bisa di-decode sendiri
+        FrameInfo {
+            keyframe: true,
+            ..FrameInfo::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtp::Packetizer;
+
+    // TOC 0x48: SILK wideband 20 ms mono, code 0
+    const SILK_20MS: u8 = 0x48;
+    // TOC 0xFC: CELT fullband 20 ms stereo, code 0
+    const CELT_20MS_STEREO: u8 = 0xFC;
+
+    #[test]
+    fn toc_parsing() {
+        let info = parse_opus_packet(&[SILK_20MS, 0x0b, 0xe4, 0x12]).unwrap();
+        assert_eq!(info.mode, OpusMode::Silk);
+        assert_eq!(info.samples(), 960);
+        assert!(!info.stereo);
+
+        let info = parse_opus_packet(&[CELT_20MS_STEREO | 0x01, 0x11, 0x22]).unwrap();
+        assert_eq!(info.mode, OpusMode::Celt);
+        assert_eq!(info.frame_count, 2);
+        assert!(info.stereo);
+
+        // Code 3, 3 frame CBR
+        let info = parse_opus_packet(&[SILK_20MS | 0x03, 0x03, 0x40, 0x40]).unwrap();
+        assert_eq!(info.frame_count, 3);
+        assert_eq!(info.duration_us(), 60_000);
+    }
+
+    #[test]
+    fn lbrr_flag_is_detected() {
+        // Mono 20 ms: bit ke-0 VAD, bit ke-1 LBRR
+        assert!(parse_opus_packet(&[SILK_20MS, 0xc0, 0x55, 0x10]).unwrap().has_fec);
+        assert!(!parse_opus_packet(&[SILK_20MS, 0x80, 0x55, 0x10]).unwrap().has_fec);
+        // CELT tidak punya LBRR
+        assert!(!parse_opus_packet(&[CELT_20MS_STEREO, 0xff, 0x55]).unwrap().has_fec);
+    }
+
+    #[test]
+    fn dtx_gaps_are_not_counted_as_loss() {
+        let mut packetizer = Packetizer::new(111, Box::new(OpusPayloader::new()));
+        let mut depayloader = OpusDepayloader::new();
+        let speech = [SILK_20MS, 0xc0, 0x55, 0x10, 0x42];
+        let silence = [SILK_20MS];
+
+        let mut sent = 0;
+        for i in 0..40u32 {
+            let frame: &[u8] = if (5..30).contains(&i) { &silence } else { &speech };
+            for packet in packetizer.packetize(frame, i * 960).unwrap() {
+                depayloader.push(&packet).unwrap();
+                sent += 1;
+            }
+        }
+
+        // 25 frame diam: DTX pertama + satu keep-alive setelah 400 ms
+        assert_eq!(sent, 15 + 2);
+        assert_eq!(depayloader.lost_frames(), 0);
+        assert_eq!(depayloader.dtx_samples(), 23 * 960);
+    }
+
+    #[test]
+    fn single_loss_with_fec_is_recoverable() {
+        let mut packetizer = Packetizer::new(111, Box::new(OpusPayloader::new()));
+        let mut depayloader = OpusDepayloader::new();
+        let frame = [SILK_20MS, 0xc0, 0x55, 0x10];
+
+        let packets: Vec<_> = (0..3u32)
+            .flat_map(|i| packetizer.packetize(&frame, i * 960).unwrap())
+            .collect();
+        depayloader.push(&packets[0]).unwrap();
+        depayloader.push(&packets[2]).unwrap();
+
+        assert_eq!(depayloader.lost_frames(), 1);
+        assert!(depayloader.fec_recovery_pending());
+    }
+}
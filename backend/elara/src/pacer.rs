@@ -0,0 +1,274 @@
+//! Modul Pacer ELARA
+//!
+//! Penyebar paket di jalur kirim. Paket satu keyframe tidak dikirim
+//! sekaligus tapi dialirkan sesuai bandwidth estimasi, karena burst
+//! besar mudah hilang di link seluler. Audio dan retransmisi didahulukan
+//! dari video baru, dan padding bisa diisi untuk probing bandwidth.
+
+use std::collections::VecDeque;
+
+use uuid::Uuid;
+
+use crate::rtp::{RtpPacket, RTP_HEADER_LEN};
+
+/// Payload type paket padding (dibuang penerima)
+pub const PADDING_PAYLOAD_TYPE: u8 = 127;
+
+/// Pacer mengirim lebih cepat dari estimasi agar antrean cepat kosong
+const PACING_FACTOR: f64 = 2.5;
+
+/// Budget boleh menabung hingga durasi ini, agar poll yang telat tidak
+/// langsung menjadi burst besar
+const BUDGET_WINDOW_MS: f64 = 20.0;
+
+/// Antrean harus habis dalam waktu ini; rate dinaikkan jika perlu
+const QUEUE_TIME_LIMIT_MS: f64 = 2000.0;
+
+/// Ukuran padding per paket (maksimum 255 karena panjangnya satu byte)
+const PADDING_BYTES: usize = 224;
+
+/// Rate awal sebelum ada estimasi (kbps)
+const DEFAULT_PACING_KBPS: u32 = 300;
+
+/// Prioritas paket di pacer, urut dari yang paling didahulukan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PacketPriority {
+    /// Audio, tidak ditahan budget
+    Audio = 0,
+    /// Retransmisi NACK
+    Retransmission = 1,
+    /// Video baru dan FEC
+    Video = 2,
+}
+
+struct QueuedPacket {
+    data: Vec<u8>,
+    enqueued_ms: u64,
+}
+
+/// Pacer dengan antrean per prioritas
+pub struct Pacer {
+    queues: [VecDeque<QueuedPacket>; 3],
+    queued_bytes: usize,
+    rate_kbps: u32,
+    padding_kbps: u32,
+    budget_bytes: f64,
+    padding_budget_bytes: f64,
+    last_refill_ms: Option<u64>,
+    padding_ssrc: u32,
+    padding_sequence: u16,
+    padding_sent: u64,
+}
+
+impl Default for Pacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pacer {
+    /// Buat pacer baru
+    pub fn new() -> Self {
+        let random = Uuid::new_v4().as_u128();
+        Self {
+            queues: Default::default(),
+            queued_bytes: 0,
+            rate_kbps: DEFAULT_PACING_KBPS,
+            padding_kbps: 0,
+            budget_bytes: 0.0,
+            padding_budget_bytes: 0.0,
+            last_refill_ms: None,
+            padding_ssrc: random as u32,
+            padding_sequence: (random >> 32) as u16,
+            padding_sent: 0,
+        }
+    }
+
+    /// Set bandwidth estimasi (kbps)
+    pub fn set_rate(&mut self, kbps: u32) {
+        self.rate_kbps = kbps;
+    }
+
+    /// Set target total rate kirim untuk probing (kbps), 0 mematikan padding
+    ///
+    /// Padding hanya mengisi selisih antara target dan media yang terkirim.
+    pub fn set_padding_rate(&mut self, kbps: u32) {
+        self.padding_kbps = kbps;
+        if kbps == 0 {
+            self.padding_budget_bytes = 0.0;
+        }
+    }
+
+    /// Jumlah paket di antrean
+    pub fn queued_packets(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    /// Total bytes padding yang sudah dikirim
+    pub fn padding_sent(&self) -> u64 {
+        self.padding_sent
+    }
+
+    /// Umur paket tertua di antrean (ms)
+    pub fn queue_delay_ms(&self, now_ms: u64) -> u64 {
+        self.queues
+            .iter()
+            .filter_map(|queue| queue.front())
+            .map(|packet| now_ms.saturating_sub(packet.enqueued_ms))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Masukkan paket ke antrean
+    pub fn enqueue(&mut self, priority: PacketPriority, data: Vec<u8>, now_ms: u64) {
+        self.queued_bytes += data.len();
+        self.queues[priority as usize].push_back(QueuedPacket {
+            data,
+            enqueued_ms: now_ms,
+        });
+    }
+
+    fn refill(&mut self, now_ms: u64) {
+        let elapsed = self
+            .last_refill_ms
+            .map_or(0, |last| now_ms.saturating_sub(last)) as f64;
+        self.last_refill_ms = Some(now_ms);
+
+        // Rate dinaikkan jika antrean tidak akan habis dalam batas waktu
+        let drain_kbps = self.queued_bytes as f64 * 8.0 / QUEUE_TIME_LIMIT_MS;
+        let rate = (self.rate_kbps as f64 * PACING_FACTOR).max(drain_kbps) / 8.0;
+        self.budget_bytes = (self.budget_bytes + rate * elapsed).min(rate * BUDGET_WINDOW_MS);
+
+        let padding_rate = self.padding_kbps as f64 / 8.0;
+        self.padding_budget_bytes = (self.padding_budget_bytes + padding_rate * elapsed)
+            .min(padding_rate * BUDGET_WINDOW_MS);
+    }
+
+    fn padding_packet(&mut self) -> Vec<u8> {
+        let mut payload = vec![0; PADDING_BYTES];
+        payload[PADDING_BYTES - 1] = PADDING_BYTES as u8;
+        let packet = RtpPacket {
+            payload_type: PADDING_PAYLOAD_TYPE,
+            sequence_number: self.padding_sequence,
+            timestamp: 0,
+            ssrc: self.padding_ssrc,
+            marker: false,
+            payload,
+        };
+        self.padding_sequence = self.padding_sequence.wrapping_add(1);
+
+        let mut data = packet.serialize();
+        // Bit P: seluruh payload adalah padding
+        data[0] |= 0x20;
+        data
+    }
+
+    /// Keluarkan semua paket di antrean tanpa menunggu budget
+    pub fn drain(&mut self) -> Vec<Vec<u8>> {
+        self.queued_bytes = 0;
+        self.queues
+            .iter_mut()
+            .flat_map(|queue| queue.drain(..).map(|packet| packet.data))
+            .collect()
+    }
+
+    /// Ambil paket yang boleh dikirim sekarang
+    ///
+    /// Panggil berkala (mis. tiap 5 ms). Audio selalu keluar; paket lain
+    /// keluar selama budget masih positif, sehingga satu paket besar
+    /// boleh membuat budget sedikit minus.
+    pub fn poll(&mut self, now_ms: u64) -> Vec<Vec<u8>> {
+        self.refill(now_ms);
+        let mut out = Vec::new();
+
+        for priority in 0..self.queues.len() {
+            while let Some(packet) = self.queues[priority].front() {
+                if priority != PacketPriority::Audio as usize && self.budget_bytes <= 0.0 {
+                    break;
+                }
+                let size = packet.data.len() as f64;
+                self.budget_bytes -= size;
+                self.padding_budget_bytes -= size;
+                self.queued_bytes -= packet.data.len();
+                out.push(self.queues[priority].pop_front().unwrap().data);
+            }
+        }
+
+        if self.queued_bytes == 0 {
+            while self.padding_budget_bytes > 0.0 && self.budget_bytes > 0.0 {
+                let padding = self.padding_packet();
+                self.padding_budget_bytes -= padding.len() as f64;
+                self.budget_bytes -= padding.len() as f64;
+                self.padding_sent += (padding.len() - RTP_HEADER_LEN) as u64;
+                out.push(padding);
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyframe_burst_is_spread() {
+        let mut pacer = Pacer::new();
+        // 400 kbps x 2.5 = 125 bytes/ms
+        pacer.set_rate(400);
+        pacer.poll(0);
+        for _ in 0..20 {
+            pacer.enqueue(PacketPriority::Video, vec![0; 1000], 0);
+        }
+
+        let mut sent = Vec::new();
+        for now in (5..=200).step_by(5) {
+            sent.push(pacer.poll(now).len());
+        }
+        assert_eq!(sent.iter().sum::<usize>(), 20);
+        // Tiap 5 ms hanya ~625 bytes, tidak ada burst
+        assert!(sent.iter().all(|&count| count <= 1));
+        assert_eq!(pacer.queue_delay_ms(200), 0);
+    }
+
+    #[test]
+    fn audio_and_retransmissions_go_first() {
+        let mut pacer = Pacer::new();
+        pacer.set_rate(400);
+        pacer.poll(0);
+        pacer.enqueue(PacketPriority::Video, vec![2; 500], 0);
+        pacer.enqueue(PacketPriority::Retransmission, vec![1; 500], 0);
+        pacer.enqueue(PacketPriority::Audio, vec![0; 100], 0);
+
+        let sent = pacer.poll(4);
+        assert_eq!(sent.len(), 2);
+        assert_eq!((sent[0][0], sent[1][0]), (0, 1));
+        assert_eq!(pacer.queue_delay_ms(10), 10);
+
+        // Audio tetap keluar walau budget habis
+        pacer.enqueue(PacketPriority::Audio, vec![0; 100], 4);
+        assert_eq!(pacer.poll(4).len(), 1);
+        assert_eq!(pacer.queued_packets(), 1);
+    }
+
+    #[test]
+    fn padding_fills_idle_link() {
+        let mut pacer = Pacer::new();
+        pacer.set_rate(1000);
+        pacer.set_padding_rate(800);
+        pacer.poll(0);
+
+        let padding = pacer.poll(10);
+        assert!(!padding.is_empty());
+        let packet = RtpPacket::parse(&padding[0]).unwrap();
+        assert_eq!(packet.payload_type, PADDING_PAYLOAD_TYPE);
+        assert!(packet.payload.is_empty());
+
+        // Media mengurangi jatah padding
+        pacer.enqueue(PacketPriority::Video, vec![0; 1000], 10);
+        let sent = pacer.poll(20);
+        assert_eq!(sent.len(), 1);
+        assert!(pacer.padding_sent() > 0);
+    }
+}
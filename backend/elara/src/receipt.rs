@@ -0,0 +1,228 @@
+//! Modul Receipt Gift ELARA
+//!
+//! Verifikasi receipt gift yang ditandatangani backend
+//! (`generateGiftReceipt` di elara.service.ts) sebelum animasi gift
+//! ditampilkan, agar hanya gift yang benar-benar dipotong koinnya yang
+//! muncul. Memakai format dan secret yang sama dengan token sesi.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{verify_signed_data, TokenError};
+
+/// Umur maksimum receipt saat diterima (ms)
+pub const MAX_RECEIPT_AGE_MS: u64 = 5 * 60 * 1000;
+
+/// Toleransi jam peer yang lebih cepat dari backend (ms)
+const MAX_CLOCK_SKEW_MS: u64 = 30 * 1000;
+
+const RECEIPT_TYPE: &str = "gift-receipt";
+
+/// Error verifikasi receipt gift
+#[derive(Debug, Error, PartialEq)]
+pub enum ReceiptError {
+    /// Signature atau format tidak valid
+    #[error(transparent)]
+    Token(#[from] TokenError),
+    /// Isi receipt tidak cocok dengan sesi atau pesan gift
+    #[error("receipt tidak cocok: {0}")]
+    Mismatch(&'static str),
+    /// Receipt terlalu lama atau dari masa depan
+    #[error("receipt sudah kedaluwarsa")]
+    Stale,
+    /// Receipt sudah pernah dipakai
+    #[error("receipt sudah pernah dipakai")]
+    Replayed,
+}
+
+impl From<ReceiptError> for napi::Error {
+    fn from(err: ReceiptError) -> Self {
+        napi::Error::new(napi::Status::InvalidArg, err.to_string())
+    }
+}
+
+/// Isi receipt gift dari backend
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GiftReceipt {
+    #[serde(rename = "type")]
+    kind: String,
+    /// ID transaksi gift
+    pub receipt_id: String,
+    /// User pengirim
+    pub sender_id: String,
+    /// User penerima
+    pub receiver_id: String,
+    /// ID sesi
+    pub session_id: String,
+    /// ID gift dari katalog
+    pub gift_id: String,
+    /// Koin yang dipotong
+    pub coins: u32,
+    /// Waktu transaksi (ms sejak epoch)
+    pub timestamp: u64,
+}
+
+impl GiftReceipt {
+    /// Verifikasi signature dan tipe receipt
+    pub fn verify(token: &str, secret: &str) -> Result<Self, ReceiptError> {
+        let data = verify_signed_data(token, secret)?;
+        let receipt: Self = serde_json::from_slice(&data).map_err(|_| TokenError::Malformed)?;
+        // Token sesi ditandatangani key yang sama, jangan tertukar
+        if receipt.kind != RECEIPT_TYPE {
+            return Err(TokenError::Malformed.into());
+        }
+        Ok(receipt)
+    }
+}
+
+/// Pemeriksa receipt gift untuk satu sesi
+pub struct ReceiptVerifier {
+    secret: String,
+    session_id: String,
+    local_user_id: String,
+    remote_user_id: String,
+    /// Receipt yang sudah dipakai beserta timestamp-nya
+    seen: HashMap<String, u64>,
+}
+
+impl ReceiptVerifier {
+    /// Buat pemeriksa untuk gift dari `remote_user_id` ke `local_user_id`
+    pub fn new(secret: &str, session_id: &str, local_user_id: &str, remote_user_id: &str) -> Self {
+        Self {
+            secret: secret.to_string(),
+            session_id: session_id.to_string(),
+            local_user_id: local_user_id.to_string(),
+            remote_user_id: remote_user_id.to_string(),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Verifikasi receipt yang dibawa pesan gift dari peer
+    ///
+    /// Receipt harus cocok dengan sesi, arah, gift, dan jumlah koin di
+    /// pesan, masih baru, dan belum pernah dipakai.
+    pub fn verify(
+        &mut self,
+        token: &str,
+        gift_id: &str,
+        coins: u32,
+        receipt_id: &str,
+        now_ms: u64,
+    ) -> Result<GiftReceipt, ReceiptError> {
+        let receipt = GiftReceipt::verify(token, &self.secret)?;
+
+        if receipt.session_id != self.session_id {
+            return Err(ReceiptError::Mismatch("sesi"));
+        }
+        if receipt.sender_id != self.remote_user_id || receipt.receiver_id != self.local_user_id {
+            return Err(ReceiptError::Mismatch("pengirim/penerima"));
+        }
+        if receipt.gift_id != gift_id || receipt.coins != coins || receipt.receipt_id != receipt_id {
+            return Err(ReceiptError::Mismatch("gift"));
+        }
+        if receipt.timestamp + MAX_RECEIPT_AGE_MS < now_ms
+            || receipt.timestamp > now_ms + MAX_CLOCK_SKEW_MS
+        {
+            return Err(ReceiptError::Stale);
+        }
+
+        // Receipt yang sudah kedaluwarsa tidak perlu diingat lagi
+        self.seen
+            .retain(|_, timestamp| *timestamp + MAX_RECEIPT_AGE_MS >= now_ms);
+        if self.seen.contains_key(&receipt.receipt_id) {
+            return Err(ReceiptError::Replayed);
+        }
+        self.seen.insert(receipt.receipt_id.clone(), receipt.timestamp);
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gift_price, sign_token_data, AppPayload};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    /// Receipt dari `generateGiftReceipt` backend dengan secret "secret"
+    const BACKEND_RECEIPT: &str = "eyJ0eXBlIjoiZ2lmdC1yZWNlaXB0IiwicmVjZWlwdElkIjoici0xIiwic2VuZGVySWQiOiJhbGljZSIsInJlY2VpdmVySWQiOiJib2IiLCJzZXNzaW9uSWQiOiJzLTEiLCJnaWZ0SWQiOiJyb2NrZXQiLCJjb2lucyI6NTAwLCJ0aW1lc3RhbXAiOjE3MDAwMDAwMDAwMDB9.37513e0828a785210ddd2cc48ca039fdb2230e3f2f47cf5b1260116f8a871da0";
+    const NOW: u64 = 1_700_000_001_000;
+
+    fn verifier() -> ReceiptVerifier {
+        ReceiptVerifier::new("secret", "s-1", "bob", "alice")
+    }
+
+    #[test]
+    fn accepts_backend_receipt_once() {
+        let mut verifier = verifier();
+        let receipt = verifier.verify(BACKEND_RECEIPT, "rocket", 500, "r-1", NOW).unwrap();
+        assert_eq!((receipt.sender_id.as_str(), receipt.coins), ("alice", 500));
+
+        assert_eq!(
+            verifier.verify(BACKEND_RECEIPT, "rocket", 500, "r-1", NOW + 1000),
+            Err(ReceiptError::Replayed)
+        );
+    }
+
+    #[test]
+    fn rejects_forged_or_mismatched_receipts() {
+        let mut verifier = verifier();
+        assert_eq!(
+            verifier.verify(BACKEND_RECEIPT, "rocket", 1000, "r-1", NOW),
+            Err(ReceiptError::Mismatch("gift"))
+        );
+        assert_eq!(
+            ReceiptVerifier::new("secret", "s-1", "alice", "bob")
+                .verify(BACKEND_RECEIPT, "rocket", 500, "r-1", NOW),
+            Err(ReceiptError::Mismatch("pengirim/penerima"))
+        );
+        assert_eq!(
+            ReceiptVerifier::new("lain", "s-1", "bob", "alice")
+                .verify(BACKEND_RECEIPT, "rocket", 500, "r-1", NOW),
+            Err(ReceiptError::Token(TokenError::InvalidSignature))
+        );
+
+        // Token sesi dengan key yang sama bukan receipt
+        let data = br#"{"sessionId":"s-1","userId":"alice","expiresAt":1900000000}"#;
+        let token = format!("{}.{}", STANDARD.encode(data), sign_token_data(data, "secret"));
+        assert_eq!(
+            verifier.verify(&token, "rocket", 500, "r-1", NOW),
+            Err(ReceiptError::Token(TokenError::Malformed))
+        );
+    }
+
+    #[test]
+    fn backend_receipts_match_gift_catalog() {
+        // Diamond dari `generateGiftReceipt` dengan harga katalog backend
+        const DIAMOND_RECEIPT: &str = "eyJ0eXBlIjoiZ2lmdC1yZWNlaXB0IiwicmVjZWlwdElkIjoici0yIiwic2VuZGVySWQiOiJhbGljZSIsInJlY2VpdmVySWQiOiJib2IiLCJzZXNzaW9uSWQiOiJzLTEiLCJnaWZ0SWQiOiJkaWFtb25kIiwiY29pbnMiOjEwMCwidGltZXN0YW1wIjoxNzAwMDAwMDAwMDAwfQ==.d06b2a2f4d6114d501475afdba21ca5c67b3b671dfe6453e01264599dad67897";
+
+        for token in [BACKEND_RECEIPT, DIAMOND_RECEIPT] {
+            let receipt = GiftReceipt::verify(token, "secret").unwrap();
+            let payload = AppPayload::GiftSent {
+                gift_id: receipt.gift_id.clone(),
+                amount: receipt.coins,
+                receipt_id: receipt.receipt_id.clone(),
+                receipt: token.to_string(),
+            };
+            assert_eq!(payload.validate(), Ok(()));
+            assert_eq!(gift_price(&receipt.gift_id), Some(receipt.coins));
+        }
+    }
+
+    #[test]
+    fn rejects_stale_receipts() {
+        let mut verifier = verifier();
+        assert_eq!(
+            verifier.verify(BACKEND_RECEIPT, "rocket", 500, "r-1", NOW + MAX_RECEIPT_AGE_MS),
+            Err(ReceiptError::Stale)
+        );
+        assert_eq!(
+            verifier.verify(BACKEND_RECEIPT, "rocket", 500, "r-1", NOW - 60_000),
+            Err(ReceiptError::Stale)
+        );
+        assert!(verifier.verify(BACKEND_RECEIPT, "rocket", 500, "r-1", NOW).is_ok());
+    }
+}
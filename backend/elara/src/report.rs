@@ -0,0 +1,409 @@
+//! Modul Report ELARA
+//!
+//! Statistik RTCP per stream: report block penerimaan (loss dan jitter
+//! RFC 3550), sender report, dan pengukuran RTT lewat LSR/DLSR atau
+//! XR RRTR/DLRR untuk peer yang hanya menerima.
+
+use std::time::Duration;
+
+use crate::rtcp::{DlrrItem, ReportBlock, RtcpPacket, XrBlock};
+use crate::rtp::{RtpPacket, Unwrapper};
+
+/// Selisih epoch NTP (1900) dan Unix (1970) dalam detik
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+
+/// Konversi waktu sejak epoch Unix ke timestamp NTP 64 bit (32.32)
+pub fn ntp_time(since_unix: Duration) -> u64 {
+    let secs = since_unix.as_secs() + NTP_UNIX_OFFSET_SECS;
+    let fraction = ((since_unix.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    (secs << 32) | fraction
+}
+
+/// 32 bit tengah timestamp NTP (16.16) seperti dipakai LSR/DLSR
+pub fn compact_ntp(ntp: u64) -> u32 {
+    (ntp >> 16) as u32
+}
+
+fn ms_to_compact(ms: u64) -> u32 {
+    (ms * 65536 / 1000) as u32
+}
+
+/// Hitung RTT (ms) dari waktu NTP sekarang dan pasangan LSR/DLSR
+pub fn round_trip_ms(now_ntp: u64, last: u32, delay: u32) -> Option<u32> {
+    if last == 0 {
+        return None;
+    }
+
+    let rtt = compact_ntp(now_ntp).wrapping_sub(last).wrapping_sub(delay);
+    // Nilai "negatif" berarti report tidak konsisten
+    if rtt >= 0x8000_0000 {
+        return None;
+    }
+    Some((rtt as u64 * 1000 / 65536) as u32)
+}
+
+/// Kondisi stream kita menurut report dari peer
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RemoteReport {
+    /// Loss sejak report sebelumnya (persen)
+    pub fraction_lost: f32,
+    /// Total paket hilang
+    pub cumulative_lost: i32,
+    /// Interarrival jitter di sisi peer (ms)
+    pub jitter_ms: f64,
+}
+
+/// Statistik RTCP satu stream (kirim dan terima)
+pub struct StreamReporter {
+    clock_rate: u32,
+    sent_packets: u32,
+    sent_octets: u32,
+    last_sent: Option<(u32, u64)>,
+    remote_ssrc: Option<u32>,
+    sequence: Unwrapper,
+    base_sequence: Option<i64>,
+    highest_sequence: i64,
+    received: u64,
+    expected_prior: u64,
+    received_prior: u64,
+    jitter: f64,
+    last_transit: Option<u32>,
+    last_sr: Option<(u32, u64)>,
+    sender_clock: Option<(u64, u32)>,
+    last_rrtr: Option<(u32, u32, u64)>,
+    remote: Option<RemoteReport>,
+    rtt_ms: Option<u32>,
+}
+
+impl StreamReporter {
+    /// Buat reporter untuk stream dengan clock rate tertentu
+    pub fn new(clock_rate: u32) -> Self {
+        Self {
+            clock_rate,
+            sent_packets: 0,
+            sent_octets: 0,
+            last_sent: None,
+            remote_ssrc: None,
+            sequence: Unwrapper::default(),
+            base_sequence: None,
+            highest_sequence: 0,
+            received: 0,
+            expected_prior: 0,
+            received_prior: 0,
+            jitter: 0.0,
+            last_transit: None,
+            last_sr: None,
+            sender_clock: None,
+            last_rrtr: None,
+            remote: None,
+            rtt_ms: None,
+        }
+    }
+
+    /// SSRC stream remote yang diterima
+    pub fn remote_ssrc(&self) -> Option<u32> {
+        self.remote_ssrc
+    }
+
+    /// RTT terukur (ms), dihaluskan
+    pub fn rtt_ms(&self) -> Option<u32> {
+        self.rtt_ms
+    }
+
+    /// Pasangan (NTP, timestamp RTP) dari SR terakhir stream remote
+    pub fn sender_clock(&self) -> Option<(u64, u32)> {
+        self.sender_clock
+    }
+
+    /// Report terakhir dari peer tentang stream kita
+    pub fn remote(&self) -> Option<RemoteReport> {
+        self.remote
+    }
+
+    /// Interarrival jitter stream yang diterima (ms)
+    pub fn jitter_ms(&self) -> f64 {
+        self.jitter * 1000.0 / self.clock_rate as f64
+    }
+
+    /// Total paket stream yang diterima yang hilang
+    pub fn cumulative_lost(&self) -> i64 {
+        match self.base_sequence {
+            Some(base) => (self.highest_sequence - base + 1) - self.received as i64,
+            None => 0,
+        }
+    }
+
+    /// Catat paket RTP yang dikirim
+    pub fn on_sent(&mut self, packet: &RtpPacket, now_ms: u64) {
+        self.sent_packets = self.sent_packets.wrapping_add(1);
+        self.sent_octets = self.sent_octets.wrapping_add(packet.payload.len() as u32);
+        self.last_sent = Some((packet.timestamp, now_ms));
+    }
+
+    /// Catat paket RTP yang diterima
+    pub fn on_received(&mut self, packet: &RtpPacket, arrival_ms: u64) {
+        if self.remote_ssrc != Some(packet.ssrc) {
+            // Stream baru (mis. setelah renegosiasi), mulai ulang statistik
+            *self = Self {
+                remote_ssrc: Some(packet.ssrc),
+                sent_packets: self.sent_packets,
+                sent_octets: self.sent_octets,
+                last_sent: self.last_sent,
+                remote: self.remote,
+                rtt_ms: self.rtt_ms,
+                ..Self::new(self.clock_rate)
+            };
+        }
+
+        let sequence = self.sequence.unwrap(packet.sequence_number as u32, 16);
+        let base = *self.base_sequence.get_or_insert(sequence);
+        self.highest_sequence = self.highest_sequence.max(sequence).max(base);
+        self.received += 1;
+
+        // Jitter RFC 3550 dalam unit timestamp RTP
+        let arrival = (arrival_ms * self.clock_rate as u64 / 1000) as u32;
+        let transit = arrival.wrapping_sub(packet.timestamp);
+        if let Some(last) = self.last_transit {
+            let d = (transit.wrapping_sub(last) as i32).unsigned_abs() as f64;
+            self.jitter += (d - self.jitter) / 16.0;
+        }
+        self.last_transit = Some(transit);
+    }
+
+    fn report_block(&mut self, now_ms: u64) -> Option<ReportBlock> {
+        let ssrc = self.remote_ssrc?;
+        let base = self.base_sequence?;
+
+        let expected = (self.highest_sequence - base + 1) as u64;
+        let expected_interval = expected.saturating_sub(self.expected_prior);
+        let received_interval = self.received.saturating_sub(self.received_prior);
+        self.expected_prior = expected;
+        self.received_prior = self.received;
+
+        let lost_interval = expected_interval.saturating_sub(received_interval);
+        let fraction_lost = (lost_interval << 8)
+            .checked_div(expected_interval)
+            .map_or(0, |fraction| fraction.min(255) as u8);
+
+        let (last_sr, delay_since_last_sr) = match self.last_sr {
+            Some((last, at)) => (last, ms_to_compact(now_ms.saturating_sub(at))),
+            None => (0, 0),
+        };
+
+        Some(ReportBlock {
+            ssrc,
+            fraction_lost,
+            cumulative_lost: self.cumulative_lost() as i32,
+            highest_sequence: self.highest_sequence as u32,
+            jitter: self.jitter as u32,
+            last_sr,
+            delay_since_last_sr,
+        })
+    }
+
+    /// Buat report RTCP stream ini
+    ///
+    /// SR jika stream sudah mengirim paket, selain itu RR; ditambah XR
+    /// RRTR agar peer bisa mengukur RTT walau kita tidak mengirim, dan
+    /// DLRR untuk menjawab RRTR peer.
+    pub fn build_reports(&mut self, local_ssrc: u32, now_ms: u64, now_ntp: u64) -> Vec<RtcpPacket> {
+        let reports: Vec<ReportBlock> = self.report_block(now_ms).into_iter().collect();
+        let mut packets = Vec::new();
+
+        match self.last_sent {
+            Some((timestamp, sent_ms)) => {
+                let elapsed = now_ms.saturating_sub(sent_ms) * self.clock_rate as u64 / 1000;
+                packets.push(RtcpPacket::SenderReport {
+                    ssrc: local_ssrc,
+                    ntp_time: now_ntp,
+                    rtp_timestamp: timestamp.wrapping_add(elapsed as u32),
+                    packet_count: self.sent_packets,
+                    octet_count: self.sent_octets,
+                    reports,
+                });
+            }
+            None => packets.push(RtcpPacket::ReceiverReport {
+                ssrc: local_ssrc,
+                reports,
+            }),
+        }
+
+        let mut blocks = vec![XrBlock::ReceiverReferenceTime { ntp_time: now_ntp }];
+        if let Some((ssrc, last_rr, at)) = self.last_rrtr {
+            blocks.push(XrBlock::Dlrr(vec![DlrrItem {
+                ssrc,
+                last_rr,
+                delay_since_last_rr: ms_to_compact(now_ms.saturating_sub(at)),
+            }]));
+        }
+        packets.push(RtcpPacket::ExtendedReport {
+            ssrc: local_ssrc,
+            blocks,
+        });
+
+        packets
+    }
+
+    fn on_rtt(&mut self, sample: u32) {
+        self.rtt_ms = Some(match self.rtt_ms {
+            Some(rtt) => (rtt * 7 + sample) / 8,
+            None => sample,
+        });
+    }
+
+    fn on_report_blocks(&mut self, reports: &[ReportBlock], local_ssrc: u32, now_ntp: u64) {
+        for block in reports.iter().filter(|block| block.ssrc == local_ssrc) {
+            self.remote = Some(RemoteReport {
+                fraction_lost: block.fraction_lost as f32 * 100.0 / 256.0,
+                cumulative_lost: block.cumulative_lost,
+                jitter_ms: block.jitter as f64 * 1000.0 / self.clock_rate as f64,
+            });
+            if let Some(rtt) = round_trip_ms(now_ntp, block.last_sr, block.delay_since_last_sr) {
+                self.on_rtt(rtt);
+            }
+        }
+    }
+
+    /// Proses paket RTCP dari peer yang relevan untuk stream ini
+    pub fn on_rtcp(&mut self, packet: &RtcpPacket, local_ssrc: u32, now_ms: u64, now_ntp: u64) {
+        match packet {
+            RtcpPacket::SenderReport {
+                ssrc,
+                ntp_time,
+                rtp_timestamp,
+                reports,
+                ..
+            } => {
+                if Some(*ssrc) == self.remote_ssrc {
+                    self.last_sr = Some((compact_ntp(*ntp_time), now_ms));
+                    self.sender_clock = Some((*ntp_time, *rtp_timestamp));
+                }
+                self.on_report_blocks(reports, local_ssrc, now_ntp);
+            }
+            RtcpPacket::ReceiverReport { reports, .. } => {
+                self.on_report_blocks(reports, local_ssrc, now_ntp);
+            }
+            RtcpPacket::ExtendedReport { ssrc, blocks } => {
+                for block in blocks {
+                    match block {
+                        XrBlock::ReceiverReferenceTime { ntp_time } => {
+                            if Some(*ssrc) == self.remote_ssrc {
+                                self.last_rrtr = Some((*ssrc, compact_ntp(*ntp_time), now_ms));
+                            }
+                        }
+                        XrBlock::Dlrr(items) => {
+                            for item in items.iter().filter(|item| item.ssrc == local_ssrc) {
+                                if let Some(rtt) =
+                                    round_trip_ms(now_ntp, item.last_rr, item.delay_since_last_rr)
+                                {
+                                    self.on_rtt(rtt);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK_RATE: u32 = 90000;
+
+    fn packet(ssrc: u32, sequence_number: u16, timestamp: u32) -> RtpPacket {
+        RtpPacket {
+            payload_type: 96,
+            sequence_number,
+            timestamp,
+            ssrc,
+            marker: true,
+            payload: vec![0; 100],
+        }
+    }
+
+    fn ntp_at(ms: u64) -> u64 {
+        ntp_time(Duration::from_secs(1_700_000_000) + Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn loss_and_jitter_in_report_block() {
+        let mut receiver = StreamReporter::new(CLOCK_RATE);
+        // 10 paket per 33 ms, paket ke-3 dan ke-7 hilang, sebagian telat 10 ms
+        for i in 0..10u16 {
+            if i == 3 || i == 7 {
+                continue;
+            }
+            let arrival = i as u64 * 33 + if i % 2 == 0 { 10 } else { 0 };
+            receiver.on_received(&packet(5, 65530u16.wrapping_add(i), i as u32 * 2970), arrival);
+        }
+
+        let packets = receiver.build_reports(1, 400, ntp_at(400));
+        let RtcpPacket::ReceiverReport { reports, .. } = &packets[0] else {
+            panic!("harus RR karena belum mengirim");
+        };
+        let block = &reports[0];
+        assert_eq!(block.ssrc, 5);
+        assert_eq!(block.cumulative_lost, 2);
+        // 2 dari 10 paket = 51/256
+        assert_eq!(block.fraction_lost, 51);
+        assert_eq!(block.highest_sequence, 65530 + 9);
+        assert!(receiver.jitter_ms() > 2.0 && receiver.jitter_ms() < 10.0);
+
+        // Interval berikutnya tanpa paket baru: tidak ada loss baru
+        let packets = receiver.build_reports(1, 1400, ntp_at(1400));
+        let RtcpPacket::ReceiverReport { reports, .. } = &packets[0] else {
+            panic!("harus RR");
+        };
+        assert_eq!(reports[0].fraction_lost, 0);
+    }
+
+    #[test]
+    fn rtt_from_sender_report_roundtrip() {
+        let mut alice = StreamReporter::new(CLOCK_RATE);
+        let mut bob = StreamReporter::new(CLOCK_RATE);
+
+        // Alice mengirim media ke Bob, lalu SR pada t=1000
+        alice.on_sent(&packet(1, 0, 0), 0);
+        bob.on_received(&packet(1, 0, 0), 20);
+        let sr = alice.build_reports(1, 1000, ntp_at(1000));
+        assert!(matches!(sr[0], RtcpPacket::SenderReport { .. }));
+
+        // SR tiba di Bob 40 ms kemudian, Bob menahan 200 ms lalu kirim RR
+        for rtcp in &sr {
+            bob.on_rtcp(rtcp, 2, 1040, ntp_at(5040));
+        }
+        let rr = bob.build_reports(2, 1240, ntp_at(5240));
+
+        // RR tiba di Alice 40 ms kemudian: RTT = 80 ms
+        for rtcp in &rr {
+            alice.on_rtcp(rtcp, 1, 1280, ntp_at(1280));
+        }
+        let rtt = alice.rtt_ms().unwrap();
+        assert!((79..=81).contains(&rtt), "rtt {}", rtt);
+        assert_eq!(alice.remote().unwrap().cumulative_lost, 0);
+    }
+
+    #[test]
+    fn rtt_from_xr_for_receive_only_peer() {
+        let mut receiver = StreamReporter::new(CLOCK_RATE);
+        let mut sender = StreamReporter::new(CLOCK_RATE);
+        sender.on_received(&packet(2, 0, 0), 0);
+
+        // Receiver tidak mengirim media: hanya RR + XR RRTR
+        let report = receiver.build_reports(2, 1000, ntp_at(1000));
+        for rtcp in &report {
+            sender.on_rtcp(rtcp, 1, 1030, ntp_at(9030));
+        }
+        let reply = sender.build_reports(1, 1130, ntp_at(9130));
+        for rtcp in &reply {
+            receiver.on_rtcp(rtcp, 2, 1160, ntp_at(1160));
+        }
+
+        let rtt = receiver.rtt_ms().unwrap();
+        assert!((59..=61).contains(&rtt), "rtt {}", rtt);
+    }
+}
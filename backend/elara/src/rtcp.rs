@@ -0,0 +1,554 @@
+//! Modul RTCP ELARA
+//!
+//! Format paket kontrol RTCP (RFC 3550), extended report (RFC 3611) dan
+//! feedback (RFC 4585) yang dipertukarkan antar peer di samping stream media.
+
+use crate::rtp::PacketError;
+
+/// Payload type RTCP sender report
+pub const RTCP_SR: u8 = 200;
+
+/// Payload type RTCP receiver report
+pub const RTCP_RR: u8 = 201;
+
+/// Payload type RTCP transport-layer feedback
+pub const RTCP_RTPFB: u8 = 205;
+
+/// Payload type RTCP payload-specific feedback
+pub const RTCP_PSFB: u8 = 206;
+
+/// Payload type RTCP extended report (RFC 3611)
+pub const RTCP_XR: u8 = 207;
+
+/// Panjang report block SR/RR
+const REPORT_BLOCK_LEN: usize = 24;
+
+/// Jumlah report block maksimum per SR/RR (5 bit)
+const MAX_REPORT_BLOCKS: usize = 31;
+
+/// Block type XR Receiver Reference Time
+const XR_RRTR: u8 = 4;
+
+/// Block type XR DLRR
+const XR_DLRR: u8 = 5;
+
+/// FMT generic NACK
+const FMT_NACK: u8 = 1;
+
+/// FMT Picture Loss Indication
+const FMT_PLI: u8 = 1;
+
+/// FMT Full Intra Request
+const FMT_FIR: u8 = 4;
+
+/// Report block penerimaan untuk satu stream (SR/RR)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReportBlock {
+    /// SSRC stream yang dilaporkan
+    pub ssrc: u32,
+    /// Loss sejak report sebelumnya (per 256)
+    pub fraction_lost: u8,
+    /// Total paket hilang (24 bit bertanda)
+    pub cumulative_lost: i32,
+    /// Nomor urut tertinggi yang diterima (extended)
+    pub highest_sequence: u32,
+    /// Interarrival jitter (unit timestamp RTP)
+    pub jitter: u32,
+    /// 32 bit tengah NTP dari SR terakhir, 0 jika belum ada
+    pub last_sr: u32,
+    /// Delay sejak SR terakhir (1/65536 detik)
+    pub delay_since_last_sr: u32,
+}
+
+/// Sub-blok DLRR: jawaban atas RRTR dari receiver lain
+#[derive(Debug, Clone, PartialEq)]
+pub struct DlrrItem {
+    /// SSRC pengirim RRTR
+    pub ssrc: u32,
+    /// 32 bit tengah NTP dari RRTR
+    pub last_rr: u32,
+    /// Delay sejak RRTR diterima (1/65536 detik)
+    pub delay_since_last_rr: u32,
+}
+
+/// Blok extended report yang didukung
+#[derive(Debug, Clone, PartialEq)]
+pub enum XrBlock {
+    /// Waktu NTP receiver, agar receiver tanpa SR tetap bisa mengukur RTT
+    ReceiverReferenceTime {
+        /// Timestamp NTP 64 bit
+        ntp_time: u64,
+    },
+    /// Jawaban atas RRTR
+    Dlrr(Vec<DlrrItem>),
+}
+
+/// Paket RTCP yang didukung
+#[derive(Debug, Clone, PartialEq)]
+pub enum RtcpPacket {
+    /// Sender report
+    SenderReport {
+        /// SSRC pengirim
+        ssrc: u32,
+        /// Timestamp NTP 64 bit saat report dibuat
+        ntp_time: u64,
+        /// Timestamp RTP yang bersesuaian dengan `ntp_time`
+        rtp_timestamp: u32,
+        /// Total paket terkirim
+        packet_count: u32,
+        /// Total byte payload terkirim
+        octet_count: u32,
+        /// Report penerimaan stream lain
+        reports: Vec<ReportBlock>,
+    },
+    /// Receiver report
+    ReceiverReport {
+        /// SSRC pengirim report
+        ssrc: u32,
+        /// Report penerimaan
+        reports: Vec<ReportBlock>,
+    },
+    /// Extended report
+    ExtendedReport {
+        /// SSRC pengirim report
+        ssrc: u32,
+        /// Blok XR
+        blocks: Vec<XrBlock>,
+    },
+    /// Generic NACK: daftar nomor urut yang hilang
+    Nack {
+        /// SSRC pengirim feedback
+        sender_ssrc: u32,
+        /// SSRC stream media yang paketnya hilang
+        media_ssrc: u32,
+        /// Nomor urut yang diminta ulang
+        lost: Vec<u16>,
+    },
+    /// Picture Loss Indication: decoder kehilangan sinkronisasi
+    Pli {
+        /// SSRC pengirim feedback
+        sender_ssrc: u32,
+        /// SSRC stream media yang perlu keyframe
+        media_ssrc: u32,
+    },
+    /// Full Intra Request: minta keyframe dengan nomor urut perintah
+    Fir {
+        /// SSRC pengirim feedback
+        sender_ssrc: u32,
+        /// SSRC stream media yang perlu keyframe
+        media_ssrc: u32,
+        /// Nomor urut perintah, sama berarti pengulangan
+        sequence: u8,
+    },
+}
+
+fn write_header(buf: &mut Vec<u8>, count_or_fmt: u8, packet_type: u8) -> usize {
+    let start = buf.len();
+    buf.push(0x80 | (count_or_fmt & 0x1F));
+    buf.push(packet_type);
+    // Panjang diisi setelah body selesai
+    buf.extend_from_slice(&[0, 0]);
+    start
+}
+
+fn finish_length(buf: &mut [u8], start: usize) {
+    let words = ((buf.len() - start) / 4 - 1) as u16;
+    buf[start + 2..start + 4].copy_from_slice(&words.to_be_bytes());
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, PacketError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(PacketError::Truncated {
+            needed: offset + 4,
+            actual: data.len(),
+        })
+}
+
+fn write_report_blocks(buf: &mut Vec<u8>, reports: &[ReportBlock]) {
+    for report in reports.iter().take(MAX_REPORT_BLOCKS) {
+        buf.extend_from_slice(&report.ssrc.to_be_bytes());
+        buf.push(report.fraction_lost);
+        let lost = report.cumulative_lost.clamp(-0x80_0000, 0x7F_FFFF) as u32;
+        buf.extend_from_slice(&lost.to_be_bytes()[1..]);
+        buf.extend_from_slice(&report.highest_sequence.to_be_bytes());
+        buf.extend_from_slice(&report.jitter.to_be_bytes());
+        buf.extend_from_slice(&report.last_sr.to_be_bytes());
+        buf.extend_from_slice(&report.delay_since_last_sr.to_be_bytes());
+    }
+}
+
+fn read_report_blocks(data: &[u8], offset: usize, count: u8) -> Result<Vec<ReportBlock>, PacketError> {
+    let needed = offset + count as usize * REPORT_BLOCK_LEN;
+    if data.len() < needed {
+        return Err(PacketError::Truncated {
+            needed,
+            actual: data.len(),
+        });
+    }
+
+    Ok(data[offset..needed]
+        .chunks_exact(REPORT_BLOCK_LEN)
+        .map(|block| {
+            // Perluas 24 bit bertanda ke i32
+            let lost = i32::from_be_bytes([block[5], block[6], block[7], 0]) >> 8;
+            ReportBlock {
+                ssrc: u32::from_be_bytes([block[0], block[1], block[2], block[3]]),
+                fraction_lost: block[4],
+                cumulative_lost: lost,
+                highest_sequence: u32::from_be_bytes([block[8], block[9], block[10], block[11]]),
+                jitter: u32::from_be_bytes([block[12], block[13], block[14], block[15]]),
+                last_sr: u32::from_be_bytes([block[16], block[17], block[18], block[19]]),
+                delay_since_last_sr: u32::from_be_bytes([block[20], block[21], block[22], block[23]]),
+            }
+        })
+        .collect())
+}
+
+fn read_xr_blocks(data: &[u8]) -> Result<Vec<XrBlock>, PacketError> {
+    let mut blocks = Vec::new();
+    let mut offset = 8;
+
+    while offset + 4 <= data.len() {
+        let block_type = data[offset];
+        let length = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize * 4;
+        let body = data.get(offset + 4..offset + 4 + length).ok_or(PacketError::Truncated {
+            needed: offset + 4 + length,
+            actual: data.len(),
+        })?;
+
+        match block_type {
+            XR_RRTR if length >= 8 => blocks.push(XrBlock::ReceiverReferenceTime {
+                ntp_time: u64::from_be_bytes([
+                    body[0], body[1], body[2], body[3], body[4], body[5], body[6], body[7],
+                ]),
+            }),
+            XR_DLRR => blocks.push(XrBlock::Dlrr(
+                body.chunks_exact(12)
+                    .map(|item| DlrrItem {
+                        ssrc: u32::from_be_bytes([item[0], item[1], item[2], item[3]]),
+                        last_rr: u32::from_be_bytes([item[4], item[5], item[6], item[7]]),
+                        delay_since_last_rr: u32::from_be_bytes([item[8], item[9], item[10], item[11]]),
+                    })
+                    .collect(),
+            )),
+            // Blok XR lain dilewati
+            _ => {}
+        }
+        offset += 4 + length;
+    }
+
+    Ok(blocks)
+}
+
+/// Susun nomor urut menjadi entri FCI NACK (PID + bitmask BLP)
+fn nack_entries(lost: &[u16]) -> Vec<(u16, u16)> {
+    let mut sorted = lost.to_vec();
+    // Urut dengan memperhitungkan wrap: mulai dari nomor pertama
+    if let Some(&first) = lost.first() {
+        sorted.sort_by_key(|seq| seq.wrapping_sub(first));
+    }
+    sorted.dedup();
+
+    let mut entries: Vec<(u16, u16)> = Vec::new();
+    for seq in sorted {
+        match entries.last_mut() {
+            Some((pid, blp)) if seq.wrapping_sub(*pid) >= 1 && seq.wrapping_sub(*pid) <= 16 => {
+                *blp |= 1 << (seq.wrapping_sub(*pid) - 1);
+            }
+            _ => entries.push((seq, 0)),
+        }
+    }
+    entries
+}
+
+impl RtcpPacket {
+    /// Serialisasi ke bytes
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            RtcpPacket::SenderReport {
+                ssrc,
+                ntp_time,
+                rtp_timestamp,
+                packet_count,
+                octet_count,
+                reports,
+            } => {
+                let count = reports.len().min(MAX_REPORT_BLOCKS) as u8;
+                let start = write_header(&mut buf, count, RTCP_SR);
+                buf.extend_from_slice(&ssrc.to_be_bytes());
+                buf.extend_from_slice(&ntp_time.to_be_bytes());
+                buf.extend_from_slice(&rtp_timestamp.to_be_bytes());
+                buf.extend_from_slice(&packet_count.to_be_bytes());
+                buf.extend_from_slice(&octet_count.to_be_bytes());
+                write_report_blocks(&mut buf, reports);
+                finish_length(&mut buf, start);
+            }
+            RtcpPacket::ReceiverReport { ssrc, reports } => {
+                let count = reports.len().min(MAX_REPORT_BLOCKS) as u8;
+                let start = write_header(&mut buf, count, RTCP_RR);
+                buf.extend_from_slice(&ssrc.to_be_bytes());
+                write_report_blocks(&mut buf, reports);
+                finish_length(&mut buf, start);
+            }
+            RtcpPacket::ExtendedReport { ssrc, blocks } => {
+                let start = write_header(&mut buf, 0, RTCP_XR);
+                buf.extend_from_slice(&ssrc.to_be_bytes());
+                for block in blocks {
+                    match block {
+                        XrBlock::ReceiverReferenceTime { ntp_time } => {
+                            buf.extend_from_slice(&[XR_RRTR, 0, 0, 2]);
+                            buf.extend_from_slice(&ntp_time.to_be_bytes());
+                        }
+                        XrBlock::Dlrr(items) => {
+                            buf.extend_from_slice(&[XR_DLRR, 0]);
+                            buf.extend_from_slice(&((items.len() * 3) as u16).to_be_bytes());
+                            for item in items {
+                                buf.extend_from_slice(&item.ssrc.to_be_bytes());
+                                buf.extend_from_slice(&item.last_rr.to_be_bytes());
+                                buf.extend_from_slice(&item.delay_since_last_rr.to_be_bytes());
+                            }
+                        }
+                    }
+                }
+                finish_length(&mut buf, start);
+            }
+            RtcpPacket::Nack {
+                sender_ssrc,
+                media_ssrc,
+                lost,
+            } => {
+                let start = write_header(&mut buf, FMT_NACK, RTCP_RTPFB);
+                buf.extend_from_slice(&sender_ssrc.to_be_bytes());
+                buf.extend_from_slice(&media_ssrc.to_be_bytes());
+                for (pid, blp) in nack_entries(lost) {
+                    buf.extend_from_slice(&pid.to_be_bytes());
+                    buf.extend_from_slice(&blp.to_be_bytes());
+                }
+                finish_length(&mut buf, start);
+            }
+            RtcpPacket::Pli {
+                sender_ssrc,
+                media_ssrc,
+            } => {
+                let start = write_header(&mut buf, FMT_PLI, RTCP_PSFB);
+                buf.extend_from_slice(&sender_ssrc.to_be_bytes());
+                buf.extend_from_slice(&media_ssrc.to_be_bytes());
+                finish_length(&mut buf, start);
+            }
+            RtcpPacket::Fir {
+                sender_ssrc,
+                media_ssrc,
+                sequence,
+            } => {
+                // SSRC media di header FIR selalu 0, target ada di FCI
+                let start = write_header(&mut buf, FMT_FIR, RTCP_PSFB);
+                buf.extend_from_slice(&sender_ssrc.to_be_bytes());
+                buf.extend_from_slice(&0u32.to_be_bytes());
+                buf.extend_from_slice(&media_ssrc.to_be_bytes());
+                buf.extend_from_slice(&[*sequence, 0, 0, 0]);
+                finish_length(&mut buf, start);
+            }
+        }
+        buf
+    }
+
+    /// Parse compound RTCP; paket yang tidak dikenal dilewati
+    pub fn parse_compound(data: &[u8]) -> Result<Vec<RtcpPacket>, PacketError> {
+        let mut packets = Vec::new();
+        let mut offset = 0;
+
+        while offset < data.len() {
+            if data.len() < offset + 4 {
+                return Err(PacketError::Truncated {
+                    needed: offset + 4,
+                    actual: data.len(),
+                });
+            }
+            let version = data[offset] >> 6;
+            if version != 2 {
+                return Err(PacketError::InvalidVersion(version));
+            }
+
+            let count_or_fmt = data[offset] & 0x1F;
+            let packet_type = data[offset + 1];
+            let length = (u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize + 1) * 4;
+            if data.len() < offset + length {
+                return Err(PacketError::Truncated {
+                    needed: offset + length,
+                    actual: data.len(),
+                });
+            }
+            let body = &data[offset..offset + length];
+
+            if let Some(packet) = Self::parse_one(count_or_fmt, packet_type, body)? {
+                packets.push(packet);
+            }
+            offset += length;
+        }
+
+        Ok(packets)
+    }
+
+    fn parse_one(count_or_fmt: u8, packet_type: u8, body: &[u8]) -> Result<Option<RtcpPacket>, PacketError> {
+        match (packet_type, count_or_fmt) {
+            (RTCP_SR, count) => {
+                let ntp_high = read_u32(body, 8)?;
+                let ntp_low = read_u32(body, 12)?;
+                Ok(Some(RtcpPacket::SenderReport {
+                    ssrc: read_u32(body, 4)?,
+                    ntp_time: ((ntp_high as u64) << 32) | ntp_low as u64,
+                    rtp_timestamp: read_u32(body, 16)?,
+                    packet_count: read_u32(body, 20)?,
+                    octet_count: read_u32(body, 24)?,
+                    reports: read_report_blocks(body, 28, count)?,
+                }))
+            }
+            (RTCP_RR, count) => Ok(Some(RtcpPacket::ReceiverReport {
+                ssrc: read_u32(body, 4)?,
+                reports: read_report_blocks(body, 8, count)?,
+            })),
+            (RTCP_XR, _) => Ok(Some(RtcpPacket::ExtendedReport {
+                ssrc: read_u32(body, 4)?,
+                blocks: read_xr_blocks(body)?,
+            })),
+            (RTCP_RTPFB, FMT_NACK) => {
+                let sender_ssrc = read_u32(body, 4)?;
+                let media_ssrc = read_u32(body, 8)?;
+                let mut lost = Vec::new();
+                for entry in body[12..].chunks_exact(4) {
+                    let pid = u16::from_be_bytes([entry[0], entry[1]]);
+                    let blp = u16::from_be_bytes([entry[2], entry[3]]);
+                    lost.push(pid);
+                    for bit in 0..16 {
+                        if blp & (1 << bit) != 0 {
+                            lost.push(pid.wrapping_add(bit + 1));
+                        }
+                    }
+                }
+                Ok(Some(RtcpPacket::Nack {
+                    sender_ssrc,
+                    media_ssrc,
+                    lost,
+                }))
+            }
+            (RTCP_PSFB, FMT_PLI) => Ok(Some(RtcpPacket::Pli {
+                sender_ssrc: read_u32(body, 4)?,
+                media_ssrc: read_u32(body, 8)?,
+            })),
+            (RTCP_PSFB, FMT_FIR) => {
+                let sender_ssrc = read_u32(body, 4)?;
+                let media_ssrc = read_u32(body, 12)?;
+                let sequence = *body.get(16).ok_or(PacketError::Truncated {
+                    needed: 17,
+                    actual: body.len(),
+                })?;
+                Ok(Some(RtcpPacket::Fir {
+                    sender_ssrc,
+                    media_ssrc,
+                    sequence,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nack_roundtrip_with_wraparound() {
+        let lost = vec![65530, 65535, 0, 9, 40];
+        let nack = RtcpPacket::Nack {
+            sender_ssrc: 1,
+            media_ssrc: 2,
+            lost: lost.clone(),
+        };
+        let data = nack.serialize();
+        // Header + 2 SSRC + 2 entri FCI
+        assert_eq!(data.len(), 12 + 2 * 4);
+
+        let parsed = RtcpPacket::parse_compound(&data).unwrap();
+        assert_eq!(parsed, vec![nack]);
+    }
+
+    #[test]
+    fn keyframe_requests_roundtrip() {
+        let pli = RtcpPacket::Pli {
+            sender_ssrc: 1,
+            media_ssrc: 2,
+        };
+        let fir = RtcpPacket::Fir {
+            sender_ssrc: 1,
+            media_ssrc: 2,
+            sequence: 9,
+        };
+        let mut data = pli.serialize();
+        data.extend(fir.serialize());
+        assert_eq!(data.len(), 12 + 20);
+
+        let parsed = RtcpPacket::parse_compound(&data).unwrap();
+        assert_eq!(parsed, vec![pli, fir]);
+    }
+
+    #[test]
+    fn reports_roundtrip() {
+        let block = ReportBlock {
+            ssrc: 7,
+            fraction_lost: 25,
+            cumulative_lost: -3,
+            highest_sequence: 0x1_0005,
+            jitter: 90,
+            last_sr: 0x1234_5678,
+            delay_since_last_sr: 65536,
+        };
+        let packets = vec![
+            RtcpPacket::SenderReport {
+                ssrc: 1,
+                ntp_time: 0xDEAD_BEEF_0000_0001,
+                rtp_timestamp: 90000,
+                packet_count: 10,
+                octet_count: 12000,
+                reports: vec![block.clone()],
+            },
+            RtcpPacket::ReceiverReport {
+                ssrc: 1,
+                reports: vec![block],
+            },
+            RtcpPacket::ExtendedReport {
+                ssrc: 1,
+                blocks: vec![
+                    XrBlock::ReceiverReferenceTime { ntp_time: 42 },
+                    XrBlock::Dlrr(vec![DlrrItem {
+                        ssrc: 2,
+                        last_rr: 3,
+                        delay_since_last_rr: 4,
+                    }]),
+                ],
+            },
+        ];
+
+        let data: Vec<u8> = packets.iter().flat_map(|p| p.serialize()).collect();
+        assert_eq!(RtcpPacket::parse_compound(&data).unwrap(), packets);
+    }
+
+    #[test]
+    fn unknown_packets_are_skipped() {
+        let mut data = vec![0x80, 203, 0, 1, 0, 0, 0, 7];
+        data.extend(
+            RtcpPacket::Nack {
+                sender_ssrc: 1,
+                media_ssrc: 2,
+                lost: vec![5],
+            }
+            .serialize(),
+        );
+
+        let parsed = RtcpPacket::parse_compound(&data).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert!(RtcpPacket::parse_compound(&data[..6]).is_err());
+    }
+}
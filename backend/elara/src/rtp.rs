@@ -0,0 +1,270 @@
+//! Modul RTP ELARA
+//!
+//! Format paket RTP (RFC 3550) dan kerangka packetizer yang dipakai
+//! oleh payloader per codec.
+
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Panjang header RTP tanpa CSRC dan extension
+pub const RTP_HEADER_LEN: usize = 12;
+
+/// MTU aman default (muat di jalur TURN/TLS dan VPN)
+pub const DEFAULT_MTU: usize = 1200;
+
+/// Error parsing/packetizing media
+#[derive(Debug, Error, PartialEq)]
+pub enum PacketError {
+    /// Paket terlalu pendek
+    #[error("paket terpotong: butuh {needed} byte, ada {actual}")]
+    Truncated { needed: usize, actual: usize },
+    /// Versi RTP bukan 2
+    #[error("versi RTP tidak didukung: {0}")]
+    InvalidVersion(u8),
+    /// Payload tidak sesuai format codec
+    #[error("payload tidak valid: {0}")]
+    InvalidPayload(String),
+    /// Frame tidak lengkap karena ada paket/fragment yang hilang
+    #[error("frame tidak lengkap, fragment hilang (timestamp {timestamp})")]
+    IncompleteFrame { timestamp: u32 },
+    /// MTU terlalu kecil untuk payload apa pun
+    #[error("MTU terlalu kecil: {0}")]
+    MtuTooSmall(usize),
+}
+
+impl From<PacketError> for napi::Error {
+    fn from(err: PacketError) -> Self {
+        napi::Error::new(napi::Status::InvalidArg, err.to_string())
+    }
+}
+
+/// Pengubah nilai RTP yang wrap (u16/u32) menjadi nilai monotonic i64
+#[derive(Debug, Default)]
+pub(crate) struct Unwrapper {
+    last: Option<i64>,
+}
+
+impl Unwrapper {
+    pub(crate) fn unwrap(&mut self, value: u32, bits: u32) -> i64 {
+        let modulus = 1i64 << bits;
+        let value = value as i64;
+
+        let extended = match self.last {
+            None => value,
+            Some(last) => {
+                let mut diff = (value - last).rem_euclid(modulus);
+                if diff >= modulus / 2 {
+                    diff -= modulus;
+                }
+                last + diff
+            }
+        };
+
+        // Simpan yang terbesar agar paket telat tidak menggeser referensi
+        if self.last < Some(extended) {
+            self.last = Some(extended);
+        }
+        extended
+    }
+}
+
+/// Paket RTP
+#[derive(Debug, Clone, PartialEq)]
+pub struct RtpPacket {
+    /// Payload type
+    pub payload_type: u8,
+    /// Nomor urut
+    pub sequence_number: u16,
+    /// Timestamp media
+    pub timestamp: u32,
+    /// Sumber stream
+    pub ssrc: u32,
+    /// Marker (akhir frame untuk video)
+    pub marker: bool,
+    /// Payload codec
+    pub payload: Vec<u8>,
+}
+
+impl RtpPacket {
+    /// Serialisasi ke bytes
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(RTP_HEADER_LEN + self.payload.len());
+        buf.push(0x80);
+        buf.push(((self.marker as u8) << 7) | (self.payload_type & 0x7F));
+        buf.extend_from_slice(&self.sequence_number.to_be_bytes());
+        buf.extend_from_slice(&self.timestamp.to_be_bytes());
+        buf.extend_from_slice(&self.ssrc.to_be_bytes());
+        buf.extend_from_slice(&self.payload);
+        buf
+    }
+
+    /// Parse dari bytes (CSRC, extension dan padding dilewati)
+    pub fn parse(data: &[u8]) -> Result<Self, PacketError> {
+        if data.len() < RTP_HEADER_LEN {
+            return Err(PacketError::Truncated { needed: RTP_HEADER_LEN, actual: data.len() });
+        }
+
+        let version = data[0] >> 6;
+        if version != 2 {
+            return Err(PacketError::InvalidVersion(version));
+        }
+
+        let padding = data[0] & 0x20 != 0;
+        let extension = data[0] & 0x10 != 0;
+        let csrc_count = (data[0] & 0x0F) as usize;
+
+        let mut offset = RTP_HEADER_LEN + csrc_count * 4;
+        if extension {
+            if data.len() < offset + 4 {
+                return Err(PacketError::Truncated { needed: offset + 4, actual: data.len() });
+            }
+            let words = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
+            offset += 4 + words * 4;
+        }
+
+        let mut end = data.len();
+        if padding && end > offset {
+            end -= data[end - 1] as usize;
+        }
+        if end < offset {
+            return Err(PacketError::Truncated { needed: offset, actual: end });
+        }
+
+        Ok(Self {
+            payload_type: data[1] & 0x7F,
+            sequence_number: u16::from_be_bytes([data[2], data[3]]),
+            timestamp: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            ssrc: u32::from_be_bytes([data[8], data[9], data[10], data[11]]),
+            marker: data[1] & 0x80 != 0,
+            payload: data[offset..end].to_vec(),
+        })
+    }
+}
+
+/// Metadata frame video yang dibawa payload descriptor
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameInfo {
+    /// Frame adalah keyframe (bisa di-decode tanpa frame sebelumnya)
+    pub keyframe: bool,
+    /// Picture ID (VP8/VP9)
+    pub picture_id: Option<u16>,
+    /// Indeks temporal layer
+    pub temporal_layer: Option<u8>,
+    /// Indeks spatial layer
+    pub spatial_layer: Option<u8>,
+    /// Titik switch-up ke temporal layer ini
+    pub layer_sync: bool,
+}
+
+/// Pemecah frame codec menjadi payload RTP
+pub trait Payloader: Send {
+    /// Pecah satu frame menjadi payload dengan ukuran maksimum `max_payload`
+    fn payload(&mut self, frame: &[u8], max_payload: usize) -> Result<Vec<Vec<u8>>, PacketError>;
+
+    /// Set informasi layer untuk frame berikutnya (diabaikan codec tanpa layer)
+    fn set_frame_info(&mut self, _info: &FrameInfo) {}
+}
+
+/// Penyusun ulang frame codec dari paket RTP berurutan
+pub trait Depayloader: Send {
+    /// Masukkan satu paket, kembalikan frame jika sudah lengkap
+    fn push(&mut self, packet: &RtpPacket) -> Result<Option<Vec<u8>>, PacketError>;
+
+    /// Buang state frame yang sedang disusun
+    fn reset(&mut self);
+
+    /// Jumlah frame yang dibuang karena paket/fragment hilang
+    fn lost_frames(&self) -> u64;
+
+    /// Metadata frame terakhir yang berhasil disusun
+    fn last_frame_info(&self) -> FrameInfo;
+}
+
+impl<D: Depayloader + ?Sized> Depayloader for Box<D> {
+    fn push(&mut self, packet: &RtpPacket) -> Result<Option<Vec<u8>>, PacketError> {
+        (**self).push(packet)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn lost_frames(&self) -> u64 {
+        (**self).lost_frames()
+    }
+
+    fn last_frame_info(&self) -> FrameInfo {
+        (**self).last_frame_info()
+    }
+}
+
+/// Packetizer - membungkus payloader dengan header RTP
+pub struct Packetizer {
+    payload_type: u8,
+    ssrc: u32,
+    sequence_number: u16,
+    mtu: usize,
+    payloader: Box<dyn Payloader>,
+}
+
+impl Packetizer {
+    /// Buat packetizer baru dengan SSRC dan nomor urut awal acak
+    pub fn new(payload_type: u8, payloader: Box<dyn Payloader>) -> Self {
+        let random = Uuid::new_v4().as_u128();
+
+        Self {
+            payload_type,
+            ssrc: random as u32,
+            sequence_number: (random >> 32) as u16,
+            mtu: DEFAULT_MTU,
+            payloader,
+        }
+    }
+
+    /// SSRC stream
+    pub fn ssrc(&self) -> u32 {
+        self.ssrc
+    }
+
+    /// Payload type stream
+    pub fn payload_type(&self) -> u8 {
+        self.payload_type
+    }
+
+    /// Ubah MTU (ukuran paket RTP maksimum)
+    pub fn set_mtu(&mut self, mtu: usize) {
+        self.mtu = mtu;
+    }
+
+    /// Set informasi layer untuk frame berikutnya
+    pub fn set_frame_info(&mut self, info: &FrameInfo) {
+        self.payloader.set_frame_info(info);
+    }
+
+    /// Pecah frame menjadi paket RTP; marker diset pada paket terakhir
+    pub fn packetize(&mut self, frame: &[u8], timestamp: u32) -> Result<Vec<RtpPacket>, PacketError> {
+        if self.mtu <= RTP_HEADER_LEN {
+            return Err(PacketError::MtuTooSmall(self.mtu));
+        }
+
+        let payloads = self.payloader.payload(frame, self.mtu - RTP_HEADER_LEN)?;
+        let count = payloads.len();
+
+        Ok(payloads
+            .into_iter()
+            .enumerate()
+            .map(|(i, payload)| {
+                let packet = RtpPacket {
+                    payload_type: self.payload_type,
+                    sequence_number: self.sequence_number,
+                    timestamp,
+                    ssrc: self.ssrc,
+                    marker: i + 1 == count,
+                    payload,
+                };
+                self.sequence_number = self.sequence_number.wrapping_add(1);
+                packet
+            })
+            .collect())
+    }
+}
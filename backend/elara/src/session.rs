@@ -3,26 +3,38 @@
 //! Mengelola sesi komunikasi 1-on-1 antara dua peer.
 
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::JsFunction;
 use napi_derive::napi;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 
-use crate::{ConnectionQuality, ConnectionStatus};
+use crate::{
+    AppMessage, AppPayload, ChannelMessage, ChannelReliability, CodecCapabilities,
+    ConnectionQuality, ConnectionStatus, DataChannelConfig, DataChannelError, DataChannels,
+    FileTransferInfo, FileTransfers, NegotiatedCodecs, ReceiptVerifier, SessionDurations,
+    SessionMediaStats, SessionStateMachine, SessionStatus, SessionToken, StateTransition,
+    TimeLimit, TimeLimitEvent, TransferEvent, VideoQualityLevel, APP_CHANNEL, DEFAULT_CHANNEL,
+    FILE_TRANSFER_CHANNEL,
+};
 
-/// Status sesi
-#[napi]
-#[derive(Debug, Clone, PartialEq)]
-pub enum SessionStatus {
-    /// Menunggu peer
-    Waiting,
-    /// Sedang connecting
-    Connecting,
-    /// Aktif
-    Active,
-    /// Di-pause
-    Paused,
-    /// Berakhir
-    Ended,
+/// Peringatan default sebelum batas durasi habis (detik tersisa)
+const DEFAULT_TIME_WARNINGS: [u32; 2] = [300, 60];
+
+/// Alasan berakhir karena batas durasi
+const TIME_LIMIT_REASON: &str = "time_limit";
+
+/// Peringatan batas durasi dari JS, default `DEFAULT_TIME_WARNINGS`
+fn time_warnings(warning_secs: Option<Vec<u32>>) -> Vec<Duration> {
+    warning_secs
+        .unwrap_or_else(|| DEFAULT_TIME_WARNINGS.to_vec())
+        .into_iter()
+        .map(|secs| Duration::from_secs(secs as u64))
+        .collect()
 }
 
 /// Event sesi
@@ -45,6 +57,129 @@ pub enum SessionEvent {
     QualityChanged,
     /// Pesan diterima
     MessageReceived,
+    /// Batas durasi hampir habis
+    TimeWarning,
+    /// Batas durasi habis, sesi berakhir
+    TimeLimit,
+    /// Peer menawarkan file
+    FileOffered,
+    /// Progress kirim/terima file
+    FileProgress,
+    /// File dari peer diterima utuh
+    FileReceived,
+    /// File terkirim ke peer
+    FileSent,
+    /// Transfer file dibatalkan
+    FileCancelled,
+    /// Pesan aplikasi (chat, reaksi, gift, ...) diterima
+    AppMessage,
+}
+
+/// Event sesi beserta datanya, dikirim ke listener JS
+#[napi(object)]
+#[derive(Clone)]
+pub struct SessionEventData {
+    /// Jenis event
+    pub event: SessionEvent,
+    /// ID sesi
+    pub session_id: String,
+    /// Waktu event (ms sejak epoch)
+    pub timestamp: f64,
+    /// Level kualitas baru (`QualityChanged`)
+    pub quality: Option<VideoQualityLevel>,
+    /// Isi pesan string (`MessageReceived`)
+    pub message: Option<String>,
+    /// Isi pesan binary (`MessageReceived`, `FileReceived`)
+    pub data: Option<Buffer>,
+    /// Nama data channel asal pesan (`MessageReceived`)
+    pub channel: Option<String>,
+    /// Alasan terputus/berakhir/batal (`PeerDisconnected`, `TimeLimit`, `FileCancelled`)
+    pub reason: Option<String>,
+    /// Sisa waktu dalam detik (`TimeWarning`)
+    pub remaining_secs: Option<u32>,
+    /// Info transfer file (event `File*`)
+    pub file: Option<FileTransferInfo>,
+    /// Pesan aplikasi (`AppMessage`)
+    pub app_message: Option<AppMessage>,
+}
+
+impl SessionEventData {
+    /// Buat event tanpa data tambahan
+    pub fn new(event: SessionEvent, session_id: &str) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as f64;
+
+        Self {
+            event,
+            session_id: session_id.to_string(),
+            timestamp,
+            quality: None,
+            message: None,
+            data: None,
+            channel: None,
+            reason: None,
+            remaining_secs: None,
+            file: None,
+            app_message: None,
+        }
+    }
+}
+
+type EventListener = ThreadsafeFunction<SessionEventData, ErrorStrategy::Fatal>;
+
+/// Daftar listener event satu sesi, bisa dipakai bersama media engine
+#[derive(Clone, Default)]
+pub struct SessionEvents {
+    session_id: String,
+    listeners: Arc<Mutex<Vec<EventListener>>>,
+}
+
+impl SessionEvents {
+    /// Kirim event ke semua listener tanpa menunggu JS
+    pub fn emit(&self, data: SessionEventData) {
+        if let Ok(listeners) = self.listeners.lock() {
+            for listener in listeners.iter() {
+                listener.call(data.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        }
+    }
+
+    /// Kirim event tanpa data tambahan
+    pub fn emit_event(&self, event: SessionEvent) {
+        self.emit(SessionEventData::new(event, &self.session_id));
+    }
+
+    /// Kirim `QualityChanged` dengan level baru
+    pub fn emit_quality(&self, quality: VideoQualityLevel) {
+        self.emit(SessionEventData {
+            quality: Some(quality),
+            ..SessionEventData::new(SessionEvent::QualityChanged, &self.session_id)
+        });
+    }
+
+    fn add(&self, listener: EventListener) {
+        if let Ok(mut listeners) = self.listeners.lock() {
+            listeners.push(listener);
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut listeners) = self.listeners.lock() {
+            listeners.clear();
+        }
+    }
+}
+
+/// Batas durasi sesi
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct SessionTimeLimit {
+    /// Durasi maksimum sejak sesi aktif (detik)
+    pub max_duration_secs: u32,
+    /// Sisa waktu (detik) saat `TimeWarning` dikirim, default 300 dan 60
+    pub warning_secs: Option<Vec<u32>>,
 }
 
 /// ELARA Session - Sesi komunikasi real-time
@@ -54,24 +189,57 @@ pub struct ElaraSession {
     session_id: String,
     local_peer_id: String,
     remote_peer_id: String,
-    status: Arc<RwLock<SessionStatus>>,
+    state: Arc<RwLock<SessionStateMachine>>,
     quality: Arc<RwLock<ConnectionQuality>>,
     video_enabled: Arc<RwLock<bool>>,
     audio_enabled: Arc<RwLock<bool>>,
-    start_time: u64,
+    local_codecs: Arc<RwLock<CodecCapabilities>>,
+    negotiated_codecs: Arc<RwLock<Option<NegotiatedCodecs>>>,
+    media_stats: Arc<RwLock<SessionMediaStats>>,
+    events: SessionEvents,
+    time_limit_watch: Arc<AtomicBool>,
+    data_channels: Arc<RwLock<DataChannels>>,
+    transfers: Arc<RwLock<FileTransfers>>,
+    receipts: Arc<RwLock<Option<ReceiptVerifier>>>,
+    clock: Instant,
 }
 
 #[napi]
 impl ElaraSession {
     /// Buat sesi baru
     pub fn new(session_id: String, local_peer_id: String, remote_peer_id: String) -> Self {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        
+        Self::with_codecs(session_id, local_peer_id, remote_peer_id, CodecCapabilities::default())
+    }
+
+    /// Buat sesi baru dengan kapabilitas codec device
+    pub fn with_codecs(
+        session_id: String,
+        local_peer_id: String,
+        remote_peer_id: String,
+        codecs: CodecCapabilities,
+    ) -> Self {
+        let events = SessionEvents {
+            session_id: session_id.clone(),
+            ..SessionEvents::default()
+        };
+
+        let mut data_channels = DataChannels::default();
+        for name in [FILE_TRANSFER_CHANNEL, APP_CHANNEL] {
+            data_channels
+                .open(&DataChannelConfig {
+                    name: name.to_string(),
+                    reliability: ChannelReliability::ReliableOrdered,
+                    max_retransmits: None,
+                    max_lifetime_ms: None,
+                })
+                .expect("konfigurasi channel bawaan valid");
+        }
+
         Self {
             session_id,
             local_peer_id,
             remote_peer_id,
-            status: Arc::new(RwLock::new(SessionStatus::Waiting)),
+            state: Arc::new(RwLock::new(SessionStateMachine::new())),
             quality: Arc::new(RwLock::new(ConnectionQuality {
                 score: 100,
                 latency_ms: 0,
@@ -80,10 +248,15 @@ impl ElaraSession {
             })),
             video_enabled: Arc::new(RwLock::new(true)),
             audio_enabled: Arc::new(RwLock::new(true)),
-            start_time: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            local_codecs: Arc::new(RwLock::new(codecs)),
+            negotiated_codecs: Arc::new(RwLock::new(None)),
+            media_stats: Arc::new(RwLock::new(SessionMediaStats::default())),
+            events,
+            time_limit_watch: Arc::new(AtomicBool::new(false)),
+            data_channels: Arc::new(RwLock::new(data_channels)),
+            transfers: Arc::new(RwLock::new(FileTransfers::new())),
+            receipts: Arc::new(RwLock::new(None)),
+            clock: Instant::now(),
         }
     }
 
@@ -108,7 +281,19 @@ impl ElaraSession {
     /// Dapatkan status sesi
     #[napi]
     pub async fn get_status(&self) -> SessionStatus {
-        self.status.read().await.clone()
+        self.state.read().await.status()
+    }
+
+    /// Dapatkan riwayat transisi status, dari yang terlama
+    #[napi]
+    pub async fn get_state_history(&self) -> Vec<StateTransition> {
+        self.state.read().await.history()
+    }
+
+    /// Lama sesi berada di status saat ini (ms)
+    #[napi]
+    pub async fn get_time_in_state(&self) -> u32 {
+        self.state.read().await.time_in_state_ms().min(u32::MAX as u64) as u32
     }
 
     /// Dapatkan kualitas koneksi
@@ -117,24 +302,170 @@ impl ElaraSession {
         self.quality.read().await.clone()
     }
 
-    /// Dapatkan durasi sesi dalam detik
+    /// Dapatkan durasi aktif sesi dalam detik
+    ///
+    /// Hanya menghitung waktu `Active`; connecting dan pause tidak ikut.
     #[napi]
-    pub fn get_duration(&self) -> u64 {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        
-        now - self.start_time
+    pub async fn get_duration(&self) -> u64 {
+        (self.state.read().await.durations().active_ms / 1000.0) as u64
+    }
+
+    /// Dapatkan rincian durasi per status (ms)
+    #[napi]
+    pub async fn get_durations(&self) -> SessionDurations {
+        self.state.read().await.durations()
+    }
+
+    /// Pasang batas durasi sesi
+    ///
+    /// Dihitung sejak sesi pertama `Active`, termasuk pause. Saat habis
+    /// sesi diakhiri dengan event `TimeLimit`, tanpa bergantung pada JS.
+    #[napi]
+    pub async fn set_time_limit(&self, limit: SessionTimeLimit) -> Result<()> {
+        if limit.max_duration_secs == 0 {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "maxDurationSecs harus lebih dari 0".to_string(),
+            ));
+        }
+
+        let limit = TimeLimit::new(
+            Duration::from_secs(limit.max_duration_secs as u64),
+            &time_warnings(limit.warning_secs),
+        );
+        self.install_time_limit(limit).await;
+        Ok(())
+    }
+
+    /// Pasang batas waktu dari token sesi backend (`expiresAt`)
+    ///
+    /// Token diverifikasi dengan secret backend dan harus untuk sesi ini
+    /// dan `user_id` lokal. Sesi berakhir tepat saat token kedaluwarsa,
+    /// termasuk waktu menunggu dan connecting. Mengembalikan sisa waktu
+    /// dalam detik.
+    #[napi]
+    pub async fn set_time_limit_from_token(
+        &self,
+        token: String,
+        secret: String,
+        user_id: String,
+        warning_secs: Option<Vec<u32>>,
+    ) -> Result<u32> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let token = SessionToken::verify(&token, &secret, now.as_secs())?;
+        if token.session_id != self.session_id {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Token untuk sesi lain: {}", token.session_id),
+            ));
+        }
+        if token.user_id != user_id {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Token untuk user lain: {}", token.user_id),
+            ));
+        }
+
+        let remaining = Duration::from_secs(token.expires_at).saturating_sub(now);
+        let limit = TimeLimit::new(remaining, &time_warnings(warning_secs))
+            .with_deadline(Instant::now() + remaining);
+        self.install_time_limit(limit).await;
+        Ok(remaining.as_secs_f64().ceil() as u32)
+    }
+
+    /// Sisa waktu sebelum batas durasi (detik), `None` jika tanpa batas
+    #[napi]
+    pub async fn get_remaining_time(&self) -> Option<u32> {
+        let remaining = self.state.read().await.remaining_time_at(Instant::now())?;
+        Some(remaining.as_secs_f64().ceil() as u32)
+    }
+
+    /// Set kapabilitas codec lokal (urut preferensi)
+    #[napi]
+    pub async fn set_local_codecs(&self, codecs: CodecCapabilities) {
+        *self.local_codecs.write().await = codecs;
+    }
+
+    /// Buat offer codec untuk dikirim ke peer lewat signaling
+    #[napi]
+    pub async fn create_codec_offer(&self) -> CodecCapabilities {
+        self.local_codecs.read().await.clone()
+    }
+
+    /// Proses offer codec dari peer dan kembalikan answer
+    ///
+    /// Gagal jika kedua peer mengaktifkan video/audio tapi tidak punya
+    /// codec yang sama.
+    #[napi]
+    pub async fn handle_codec_offer(&self, offer: CodecCapabilities) -> Result<NegotiatedCodecs> {
+        let answer = offer.negotiate(&*self.local_codecs.read().await)?;
+        *self.negotiated_codecs.write().await = Some(answer.clone());
+        Ok(answer)
+    }
+
+    /// Proses answer codec dari peer
+    #[napi]
+    pub async fn handle_codec_answer(&self, answer: NegotiatedCodecs) -> Result<()> {
+        self.local_codecs.read().await.accepts(&answer)?;
+        *self.negotiated_codecs.write().await = Some(answer);
+        Ok(())
+    }
+
+    /// Dapatkan codec hasil negosiasi
+    #[napi]
+    pub async fn get_negotiated_codecs(&self) -> Option<NegotiatedCodecs> {
+        self.negotiated_codecs.read().await.clone()
+    }
+
+    /// Dapatkan statistik media per stream (diisi `MediaEngine.bindSession`)
+    #[napi]
+    pub async fn get_media_stats(&self) -> SessionMediaStats {
+        self.media_stats.read().await.clone()
+    }
+
+    /// Handle statistik media untuk diisi media engine
+    pub(crate) fn media_stats_handle(&self) -> Arc<RwLock<SessionMediaStats>> {
+        self.media_stats.clone()
+    }
+
+    /// Listener event sesi, dipakai media engine untuk `QualityChanged`
+    pub(crate) fn events(&self) -> SessionEvents {
+        self.events.clone()
+    }
+
+    /// Daftarkan listener event sesi
+    ///
+    /// Callback menerima `SessionEventData`; dipanggil dari thread mana
+    /// pun tanpa memblokir media. Listener dilepas saat sesi ditutup.
+    #[napi]
+    pub fn on_event(&self, callback: JsFunction) -> Result<()> {
+        let listener: EventListener = callback.create_threadsafe_function(
+            0,
+            |ctx: ThreadSafeCallContext<SessionEventData>| Ok(vec![ctx.value]),
+        )?;
+        self.events.add(listener);
+        Ok(())
+    }
+
+    /// Lepas semua listener event
+    #[napi]
+    pub fn remove_event_listeners(&self) {
+        self.events.clear();
+    }
+
+    /// Laporkan peer terputus dengan alasannya, sesi berakhir
+    #[napi]
+    pub async fn handle_peer_disconnected(&self, reason: String) -> Result<()> {
+        self.end(SessionEvent::PeerDisconnected, &reason).await
     }
 
     /// Mulai koneksi ke peer
+    ///
+    /// Hanya dari `Waiting`, atau dari `Active` untuk reconnect.
     #[napi]
     pub async fn connect(&self) -> Result<()> {
-        let mut status = self.status.write().await;
-        *status = SessionStatus::Connecting;
+        let mut state = self.state.write().await;
+        state.transition(SessionStatus::Connecting, None)?;
         
         // TODO: Implementasi koneksi ELARA
         // 1. Exchange SDP-like offer/answer via signaling
@@ -142,7 +473,8 @@ impl ElaraSession {
         // 3. Establish direct P2P connection
         // 4. Start media streams
         
-        *status = SessionStatus::Active;
+        state.transition(SessionStatus::Active, None)?;
+        self.events.emit_event(SessionEvent::PeerConnected);
         Ok(())
     }
 
@@ -154,6 +486,11 @@ impl ElaraSession {
         
         // TODO: Implementasi toggle video stream
         
+        self.events.emit_event(if *enabled {
+            SessionEvent::VideoStarted
+        } else {
+            SessionEvent::VideoStopped
+        });
         Ok(*enabled)
     }
 
@@ -165,6 +502,11 @@ impl ElaraSession {
         
         // TODO: Implementasi toggle audio stream
         
+        self.events.emit_event(if *enabled {
+            SessionEvent::AudioStarted
+        } else {
+            SessionEvent::AudioStopped
+        });
         Ok(*enabled)
     }
 
@@ -180,46 +522,325 @@ impl ElaraSession {
         *self.audio_enabled.read().await
     }
 
-    /// Kirim pesan data channel
+    /// Kirim pesan lewat channel bawaan (reliable, berurutan)
+    ///
+    /// Mengembalikan bytes yang belum di-ack peer untuk backpressure;
+    /// gagal jika pesan terlalu besar atau buffer kirim penuh. Paket
+    /// diambil dengan `pollDataPackets`.
     #[napi]
-    pub async fn send_message(&self, message: String) -> Result<()> {
-        // TODO: Implementasi data channel message
-        // Gunakan ELARA wire protocol untuk encode message
-        
+    pub async fn send_message(&self, message: String) -> Result<u32> {
+        self.send_on_channel(DEFAULT_CHANNEL, message.as_bytes(), false).await
+    }
+
+    /// Buka data channel bernama
+    ///
+    /// Kedua peer harus membuka channel dengan nama dan konfigurasi yang
+    /// sama; paket untuk channel yang belum dibuka diabaikan.
+    #[napi]
+    pub async fn open_data_channel(&self, config: DataChannelConfig) -> Result<()> {
+        self.data_channels.write().await.open(&config)?;
         Ok(())
     }
 
-    /// Pause sesi
+    /// Tutup data channel; `false` jika tidak ada atau channel bawaan
     #[napi]
-    pub async fn pause(&self) -> Result<()> {
-        let mut status = self.status.write().await;
-        if *status == SessionStatus::Active {
-            *status = SessionStatus::Paused;
+    pub async fn close_data_channel(&self, channel: String) -> bool {
+        self.data_channels.write().await.close(&channel)
+    }
+
+    /// Kirim pesan string lewat data channel bernama
+    #[napi]
+    pub async fn send_channel_message(&self, channel: String, message: String) -> Result<u32> {
+        self.send_on_channel(&channel, message.as_bytes(), false).await
+    }
+
+    /// Kirim data binary lewat data channel bernama
+    #[napi]
+    pub async fn send_channel_data(&self, channel: String, data: Buffer) -> Result<u32> {
+        self.send_on_channel(&channel, &data, true).await
+    }
+
+    /// Kirim pesan aplikasi bertipe (chat, reaksi, gift, typing, ...)
+    ///
+    /// Gagal jika pesan melanggar skema, mis. gift di luar katalog.
+    #[napi]
+    pub async fn send_app_message(&self, message: AppMessage) -> Result<u32> {
+        let frame = AppPayload::try_from(message)?.encode()?;
+        self.send_on_channel(APP_CHANNEL, &frame, true).await
+    }
+
+    /// Aktifkan verifikasi receipt gift dengan secret token backend
+    ///
+    /// Gift dari peer hanya diteruskan sebagai `AppMessage` jika
+    /// receipt-nya valid, cocok dengan sesi dan user, serta belum pernah
+    /// dipakai. Tanpa ini semua pesan gift dari peer dibuang.
+    #[napi]
+    pub async fn enable_gift_receipts(
+        &self,
+        secret: String,
+        local_user_id: String,
+        remote_user_id: String,
+    ) {
+        *self.receipts.write().await = Some(ReceiptVerifier::new(
+            &secret,
+            &self.session_id,
+            &local_user_id,
+            &remote_user_id,
+        ));
+    }
+
+    /// Tawarkan file/foto ke peer, kembalikan ID transfer
+    ///
+    /// Data baru dikirim setelah peer memanggil `acceptFile`; progress
+    /// dilaporkan lewat `FileProgress` dan selesai dengan `FileSent`.
+    #[napi]
+    pub async fn send_file(&self, name: String, mime_type: String, data: Buffer) -> Result<u32> {
+        if self.state.read().await.status() == SessionStatus::Ended {
+            return Err(Error::new(Status::GenericFailure, "Sesi sudah berakhir".to_string()));
+        }
+
+        let id = self.transfers.write().await.send_file(&name, &mime_type, data.to_vec())?;
+        Ok(id)
+    }
+
+    /// Terima file yang ditawarkan peer (`FileOffered`)
+    #[napi]
+    pub async fn accept_file(&self, id: u32) -> Result<()> {
+        let events = self.transfers.write().await.accept(id)?;
+        for event in events {
+            self.emit_transfer_event(event);
         }
         Ok(())
     }
 
-    /// Resume sesi
+    /// Batalkan transfer file di arah mana pun; `false` jika tidak ada
     #[napi]
-    pub async fn resume(&self) -> Result<()> {
-        let mut status = self.status.write().await;
-        if *status == SessionStatus::Paused {
-            *status = SessionStatus::Active;
+    pub async fn cancel_file(&self, id: u32) -> bool {
+        let event = self.transfers.write().await.cancel(id);
+        event.map(|event| self.emit_transfer_event(event)).is_some()
+    }
+
+    /// Lanjutkan transfer file yang terputus, panggil setelah reconnect
+    #[napi]
+    pub async fn resume_file_transfers(&self) {
+        self.transfers.write().await.resume();
+    }
+
+    /// Ambil paket data channel yang perlu dikirim lewat transport
+    ///
+    /// Panggil berkala (mis. tiap 10-20 ms) untuk retransmisi, ack, dan
+    /// chunk transfer file.
+    #[napi]
+    pub async fn poll_data_packets(&self) -> Vec<Buffer> {
+        let now = self.clock.elapsed().as_millis() as u64;
+        let mut channels = self.data_channels.write().await;
+
+        let buffered = channels.buffered_amount(FILE_TRANSFER_CHANNEL).unwrap_or(0);
+        let (messages, events) = self.transfers.write().await.poll(buffered);
+        for message in messages {
+            // Chunk dibatasi jauh di bawah buffer channel, jadi tidak penuh
+            let _ = channels.send(FILE_TRANSFER_CHANNEL, &message, true, now);
+        }
+        let packets = channels.poll(now).into_iter().map(Buffer::from).collect();
+        drop(channels);
+
+        for event in events {
+            self.emit_transfer_event(event);
         }
+        packets
+    }
+
+    /// Proses paket data channel dari peer
+    ///
+    /// Pesan yang lengkap dikirim ke listener sebagai `MessageReceived`,
+    /// pesan aplikasi sebagai `AppMessage`, dan pesan transfer file
+    /// sebagai event `File*`. Paket yang bukan data channel diabaikan.
+    #[napi]
+    pub async fn handle_data_packet(&self, packet: Buffer) {
+        let now = self.clock.elapsed().as_millis() as u64;
+        let messages = self.data_channels.write().await.handle_packet(&packet, now);
+        for message in messages {
+            match message.channel.as_str() {
+                FILE_TRANSFER_CHANNEL => {
+                    let events = self.transfers.write().await.handle_message(&message.payload);
+                    for event in events {
+                        self.emit_transfer_event(event);
+                    }
+                }
+                // Pesan yang melanggar skema atau gift tanpa receipt valid dibuang
+                APP_CHANNEL => {
+                    let Ok(payload) = AppPayload::decode(&message.payload) else {
+                        continue;
+                    };
+                    if self.verify_gift(&payload).await {
+                        self.events.emit(SessionEventData {
+                            app_message: Some(payload.into()),
+                            ..SessionEventData::new(SessionEvent::AppMessage, &self.session_id)
+                        });
+                    }
+                }
+                _ => self.emit_channel_message(message),
+            }
+        }
+    }
+
+    /// Bytes pesan yang belum di-ack peer di channel (default: bawaan)
+    #[napi]
+    pub async fn get_data_buffered_amount(&self, channel: Option<String>) -> Result<u32> {
+        let channel = channel.as_deref().unwrap_or(DEFAULT_CHANNEL);
+        let buffered = self
+            .data_channels
+            .read()
+            .await
+            .buffered_amount(channel)
+            .ok_or_else(|| DataChannelError::UnknownChannel(channel.to_string()))?;
+        Ok(buffered as u32)
+    }
+
+    /// Pause sesi, gagal jika sesi tidak `Active`
+    #[napi]
+    pub async fn pause(&self) -> Result<()> {
+        self.state.write().await.transition(SessionStatus::Paused, None)?;
         Ok(())
     }
 
-    /// Tutup sesi
+    /// Resume sesi, gagal jika sesi tidak `Paused`
+    #[napi]
+    pub async fn resume(&self) -> Result<()> {
+        self.state.write().await.transition(SessionStatus::Active, None)?;
+        Ok(())
+    }
+
+    /// Tutup sesi, gagal jika sesi sudah berakhir
     #[napi]
     pub async fn close(&mut self) -> Result<()> {
-        let mut status = self.status.write().await;
-        *status = SessionStatus::Ended;
+        self.end(SessionEvent::PeerDisconnected, "closed").await
+    }
+}
+
+impl ElaraSession {
+    async fn send_on_channel(&self, channel: &str, payload: &[u8], binary: bool) -> Result<u32> {
+        if self.state.read().await.status() == SessionStatus::Ended {
+            return Err(Error::new(Status::GenericFailure, "Sesi sudah berakhir".to_string()));
+        }
+
+        let now = self.clock.elapsed().as_millis() as u64;
+        let buffered = self
+            .data_channels
+            .write()
+            .await
+            .send(channel, payload, binary, now)?;
+        Ok(buffered as u32)
+    }
+
+    fn emit_channel_message(&self, message: ChannelMessage) {
+        let mut data = SessionEventData {
+            channel: Some(message.channel),
+            ..SessionEventData::new(SessionEvent::MessageReceived, &self.session_id)
+        };
+        if message.binary {
+            data.data = Some(message.payload.into());
+        } else {
+            data.message = Some(String::from_utf8_lossy(&message.payload).into_owned());
+        }
+        self.events.emit(data);
+    }
+
+    async fn verify_gift(&self, payload: &AppPayload) -> bool {
+        let AppPayload::GiftSent { gift_id, amount, receipt_id, receipt } = payload else {
+            return true;
+        };
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        match self.receipts.write().await.as_mut() {
+            Some(verifier) => verifier
+                .verify(receipt, gift_id, *amount, receipt_id, now_ms)
+                .is_ok(),
+            None => false,
+        }
+    }
+
+    fn emit_transfer_event(&self, event: TransferEvent) {
+        let (event, file, payload, reason) = match event {
+            TransferEvent::Offered(info) => (SessionEvent::FileOffered, info, None, None),
+            TransferEvent::Progress(info) => (SessionEvent::FileProgress, info, None, None),
+            TransferEvent::Received(info, data) => {
+                (SessionEvent::FileReceived, info, Some(data.into()), None)
+            }
+            TransferEvent::Sent(info) => (SessionEvent::FileSent, info, None, None),
+            TransferEvent::Cancelled(info, reason) => {
+                (SessionEvent::FileCancelled, info, None, Some(reason.as_str().to_string()))
+            }
+        };
+        self.events.emit(SessionEventData {
+            file: Some(file),
+            data: payload,
+            reason,
+            ..SessionEventData::new(event, &self.session_id)
+        });
+    }
+
+    /// Akhiri sesi, kirim event terakhir lalu lepas listener
+    async fn end(&self, event: SessionEvent, reason: &str) -> Result<()> {
+        self.state
+            .write()
+            .await
+            .transition(SessionStatus::Ended, Some(reason))?;
         
         // TODO: Cleanup resources
         // - Stop media streams
         // - Close transport connection
         // - Notify peer
         
+        self.events.emit(SessionEventData {
+            reason: Some(reason.to_string()),
+            ..SessionEventData::new(event, &self.session_id)
+        });
+        self.events.clear();
         Ok(())
     }
+
+    async fn install_time_limit(&self, limit: TimeLimit) {
+        self.state.write().await.set_time_limit(limit);
+        self.watch_time_limit();
+    }
+
+    /// Jalankan pengecek batas durasi (sekali per sesi)
+    fn watch_time_limit(&self) {
+        if self.time_limit_watch.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let session = self.clone();
+        spawn(async move {
+            let mut tick = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                tick.tick().await;
+                let event = {
+                    let mut state = session.state.write().await;
+                    if state.status() == SessionStatus::Ended {
+                        break;
+                    }
+                    state.poll_time_limit_at(Instant::now())
+                };
+
+                match event {
+                    Some(TimeLimitEvent::Warning { remaining_secs }) => {
+                        session.events.emit(SessionEventData {
+                            remaining_secs: Some(remaining_secs),
+                            ..SessionEventData::new(SessionEvent::TimeWarning, &session.session_id)
+                        });
+                    }
+                    Some(TimeLimitEvent::Expired) => {
+                        let _ = session.end(SessionEvent::TimeLimit, TIME_LIMIT_REASON).await;
+                        break;
+                    }
+                    None => {}
+                }
+            }
+        });
+    }
 }
@@ -0,0 +1,381 @@
+//! Modul Signaling ELARA
+//!
+//! Tipe pesan signaling (`offer`/`answer`/`candidate`/`ready`/`bye`)
+//! dengan payload bertipe. Bentuk JSON sama dengan `SignalingMessage` di
+//! elara.service.ts, jadi node native dan backend memakai skema yang sama.
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{is_codec_payload_type, CodecCapabilities};
+
+/// Error pesan signaling
+#[derive(Debug, Error, PartialEq)]
+pub enum SignalingError {
+    /// Bukan JSON pesan signaling yang dikenal
+    #[error("format pesan signaling tidak valid: {0}")]
+    Malformed(String),
+    /// Isi pesan melanggar skema
+    #[error("pesan signaling tidak valid: {0}")]
+    Invalid(String),
+}
+
+impl From<SignalingError> for napi::Error {
+    fn from(err: SignalingError) -> Self {
+        napi::Error::new(napi::Status::InvalidArg, err.to_string())
+    }
+}
+
+fn invalid<T>(message: impl Into<String>) -> Result<T, SignalingError> {
+    Err(SignalingError::Invalid(message.into()))
+}
+
+/// Kredensial ICE (RFC 8839)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IceCredentials {
+    /// Username fragment, 4-256 karakter
+    pub ufrag: String,
+    /// Password, 22-256 karakter
+    pub pwd: String,
+}
+
+impl IceCredentials {
+    fn validate(&self) -> Result<(), SignalingError> {
+        // ice-char = ALPHA / DIGIT / "+" / "/"
+        let ice_chars = |value: &str| {
+            value
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+        };
+        if !(4..=256).contains(&self.ufrag.len()) || !ice_chars(&self.ufrag) {
+            return invalid("ufrag ICE tidak valid");
+        }
+        if !(22..=256).contains(&self.pwd.len()) || !ice_chars(&self.pwd) {
+            return invalid("password ICE tidak valid");
+        }
+        Ok(())
+    }
+}
+
+/// Fingerprint sertifikat DTLS
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DtlsFingerprint {
+    /// Algoritma hash: "sha-256", "sha-384", atau "sha-512"
+    pub algorithm: String,
+    /// Digest dalam hex berpasangan dipisah titik dua, mis. "AB:CD:..."
+    pub value: String,
+}
+
+impl DtlsFingerprint {
+    fn validate(&self) -> Result<(), SignalingError> {
+        let digest_len = match self.algorithm.to_ascii_lowercase().as_str() {
+            "sha-256" => 32,
+            "sha-384" => 48,
+            "sha-512" => 64,
+            other => return invalid(format!("algoritma fingerprint tidak didukung: {}", other)),
+        };
+        let bytes: Vec<&str> = self.value.split(':').collect();
+        let well_formed = bytes.len() == digest_len
+            && bytes
+                .iter()
+                .all(|byte| byte.len() == 2 && byte.bytes().all(|b| b.is_ascii_hexdigit()));
+        if !well_formed {
+            return invalid("nilai fingerprint tidak cocok dengan algoritma");
+        }
+        Ok(())
+    }
+}
+
+/// Protokol kandidat
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CandidateProtocol {
+    Udp,
+    Tcp,
+}
+
+/// Tipe kandidat, sama dengan `IceCandidate.candidateType`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CandidateType {
+    Host,
+    Srflx,
+    Prflx,
+    Relay,
+}
+
+/// Kandidat ICE dalam pesan signaling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalingCandidate {
+    /// Foundation, 1-32 karakter
+    pub foundation: String,
+    /// Komponen (1 = RTP, media di-mux dalam satu komponen)
+    pub component: u32,
+    /// Protokol
+    pub protocol: CandidateProtocol,
+    /// Prioritas
+    pub priority: u32,
+    /// Alamat IP
+    pub address: String,
+    /// Port
+    pub port: u16,
+    /// Tipe kandidat
+    pub candidate_type: CandidateType,
+}
+
+impl SignalingCandidate {
+    fn validate(&self) -> Result<(), SignalingError> {
+        if self.foundation.is_empty() || self.foundation.len() > 32 {
+            return invalid("foundation kandidat tidak valid");
+        }
+        if self.component != 1 {
+            return invalid(format!("komponen kandidat tidak didukung: {}", self.component));
+        }
+        if self.address.parse::<IpAddr>().is_err() {
+            return invalid(format!("alamat kandidat tidak valid: {}", self.address));
+        }
+        if self.port == 0 {
+            return invalid("port kandidat tidak valid");
+        }
+        Ok(())
+    }
+}
+
+/// Session description untuk `offer`/`answer`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDescription {
+    /// Kredensial ICE
+    pub ice: IceCredentials,
+    /// Fingerprint DTLS
+    pub fingerprint: DtlsFingerprint,
+    /// Codec yang didukung, urut preferensi
+    pub codecs: CodecCapabilities,
+    /// Kandidat yang sudah terkumpul; sisanya dikirim lewat `candidate`
+    #[serde(default)]
+    pub candidates: Vec<SignalingCandidate>,
+}
+
+impl SessionDescription {
+    fn validate(&self) -> Result<(), SignalingError> {
+        self.ice.validate()?;
+        self.fingerprint.validate()?;
+
+        if self.codecs.video.is_empty() && self.codecs.audio.is_empty() {
+            return invalid("session description tanpa codec");
+        }
+        let payload_types = self
+            .codecs
+            .video
+            .iter()
+            .map(|codec| codec.payload_type)
+            .chain(self.codecs.audio.iter().map(|codec| codec.payload_type));
+        let mut seen = HashSet::new();
+        for payload_type in payload_types {
+            if !is_codec_payload_type(payload_type) || !seen.insert(payload_type) {
+                return invalid(format!("payload type tidak valid: {}", payload_type));
+            }
+        }
+
+        self.candidates.iter().try_for_each(SignalingCandidate::validate)
+    }
+}
+
+/// Alasan `bye`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ByePayload {
+    /// Alasan keluar, mis. "skip" atau "closed"
+    pub reason: Option<String>,
+}
+
+/// Jenis pesan beserta payload-nya
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload", rename_all = "lowercase")]
+pub enum SignalingPayload {
+    /// Penawaran sesi
+    Offer(SessionDescription),
+    /// Jawaban atas offer
+    Answer(SessionDescription),
+    /// Kandidat ICE tambahan (trickle)
+    Candidate(SignalingCandidate),
+    /// Peer siap menerima offer
+    Ready,
+    /// Peer meninggalkan sesi
+    Bye(#[serde(default)] Option<ByePayload>),
+}
+
+/// Pesan signaling, kompatibel dengan `SignalingMessage` di backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalingMessage {
+    /// ID sesi
+    pub session_id: String,
+    /// User pengirim
+    pub from: String,
+    /// User tujuan
+    pub to: String,
+    /// `type` dan `payload`
+    #[serde(flatten)]
+    pub payload: SignalingPayload,
+}
+
+impl SignalingMessage {
+    /// Cek pesan terhadap skema
+    pub fn validate(&self) -> Result<(), SignalingError> {
+        if self.session_id.is_empty() || self.from.is_empty() || self.to.is_empty() {
+            return invalid("sessionId, from, dan to wajib diisi");
+        }
+        if self.from == self.to {
+            return invalid("pengirim dan tujuan sama");
+        }
+
+        match &self.payload {
+            SignalingPayload::Offer(description) | SignalingPayload::Answer(description) => {
+                description.validate()
+            }
+            SignalingPayload::Candidate(candidate) => candidate.validate(),
+            SignalingPayload::Ready | SignalingPayload::Bye(_) => Ok(()),
+        }
+    }
+
+    /// Parse dan validasi JSON dari backend
+    pub fn from_json(json: &str) -> Result<Self, SignalingError> {
+        let message: Self =
+            serde_json::from_str(json).map_err(|err| SignalingError::Malformed(err.to_string()))?;
+        message.validate()?;
+        Ok(message)
+    }
+
+    /// Validasi lalu serialisasi ke JSON untuk backend
+    pub fn to_json(&self) -> Result<String, SignalingError> {
+        self.validate()?;
+        serde_json::to_string(self).map_err(|err| SignalingError::Malformed(err.to_string()))
+    }
+}
+
+/// Validasi pesan signaling JSON, kembalikan bentuk kanoniknya
+#[napi]
+pub fn validate_signaling_message(json: String) -> napi::Result<String> {
+    Ok(SignalingMessage::from_json(&json)?.to_json()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fingerprint() -> String {
+        vec!["AB"; 32].join(":")
+    }
+
+    fn offer() -> serde_json::Value {
+        json!({
+            "type": "offer",
+            "sessionId": "s-1",
+            "from": "alice",
+            "to": "bob",
+            "payload": {
+                "ice": { "ufrag": "aB3d", "pwd": "0123456789abcdefghij+/" },
+                "fingerprint": { "algorithm": "sha-256", "value": fingerprint() },
+                "codecs": {
+                    "video": [{ "codec": "h264", "payloadType": 102, "profile": "42e01f", "hardwareAccelerated": true }],
+                    "audio": [{ "codec": "opus", "payloadType": 111, "clockRate": 48000, "channels": 2, "fec": true, "dtx": true }]
+                },
+                "candidates": [{
+                    "foundation": "1", "component": 1, "protocol": "udp", "priority": 2130706431,
+                    "address": "192.168.1.10", "port": 50000, "candidateType": "host"
+                }]
+            }
+        })
+    }
+
+    #[test]
+    fn parses_messages_from_backend() {
+        let message = SignalingMessage::from_json(&offer().to_string()).unwrap();
+        let SignalingPayload::Offer(description) = &message.payload else {
+            panic!("bukan offer");
+        };
+        assert_eq!(description.candidates[0].candidate_type, CandidateType::Host);
+        assert_eq!(description.codecs.audio[0].payload_type, 111);
+
+        // Bentuk JSON tetap sama setelah roundtrip
+        let json: serde_json::Value = serde_json::from_str(&message.to_json().unwrap()).unwrap();
+        assert_eq!(json, offer());
+
+        let ready = r#"{"type":"ready","sessionId":"s-1","from":"bob","to":"alice","payload":null}"#;
+        assert!(matches!(SignalingMessage::from_json(ready).unwrap().payload, SignalingPayload::Ready));
+        let bye = r#"{"type":"bye","sessionId":"s-1","from":"bob","to":"alice","payload":{"reason":"skip"}}"#;
+        assert!(matches!(
+            SignalingMessage::from_json(bye).unwrap().payload,
+            SignalingPayload::Bye(Some(ByePayload { reason: Some(ref reason) })) if reason == "skip"
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_shapes() {
+        let mut message = offer();
+        message["type"] = json!("renegotiate");
+        assert!(matches!(
+            SignalingMessage::from_json(&message.to_string()),
+            Err(SignalingError::Malformed(_))
+        ));
+
+        let mut message = offer();
+        message["payload"]["ice"]["pwd"] = json!("pendek");
+        assert!(matches!(
+            SignalingMessage::from_json(&message.to_string()),
+            Err(SignalingError::Invalid(_))
+        ));
+
+        let mut message = offer();
+        message["payload"]["fingerprint"]["algorithm"] = json!("sha-1");
+        assert!(matches!(
+            SignalingMessage::from_json(&message.to_string()),
+            Err(SignalingError::Invalid(_))
+        ));
+
+        let mut message = offer();
+        message["payload"]["codecs"]["audio"][0]["payloadType"] = json!(102);
+        assert!(matches!(
+            SignalingMessage::from_json(&message.to_string()),
+            Err(SignalingError::Invalid(_))
+        ));
+
+        // PT padding pacer dan FEC video dicadangkan
+        for reserved in [crate::PADDING_PAYLOAD_TYPE, crate::VIDEO_FEC_PAYLOAD_TYPE] {
+            let mut message = offer();
+            message["payload"]["codecs"]["video"][0]["payloadType"] = json!(reserved);
+            assert!(matches!(
+                SignalingMessage::from_json(&message.to_string()),
+                Err(SignalingError::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn validates_trickle_candidates() {
+        let candidate = |address: &str| {
+            json!({
+                "type": "candidate", "sessionId": "s-1", "from": "alice", "to": "bob",
+                "payload": {
+                    "foundation": "2", "component": 1, "protocol": "tcp", "priority": 100,
+                    "address": address, "port": 443, "candidateType": "relay"
+                }
+            })
+            .to_string()
+        };
+        assert!(SignalingMessage::from_json(&candidate("2001:db8::1")).is_ok());
+        assert!(matches!(
+            SignalingMessage::from_json(&candidate("bukan-ip")),
+            Err(SignalingError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,468 @@
+//! Modul State ELARA
+//!
+//! State machine sesi di atas `SessionStatus`. Transisi yang tidak sah
+//! ditolak dengan error bertipe, dan setiap transisi dicatat beserta
+//! waktunya agar laporan seperti "macet di Connecting" bisa ditelusuri.
+//! Waktu per status dihitung dengan clock monotonic sehingga durasi aktif
+//! (dasar trust score dan reward koin) tidak terpengaruh lompatan jam.
+//! Batas durasi sesi juga dihitung di sini, mulai saat pertama `Active`.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use napi_derive::napi;
+use thiserror::Error;
+
+/// Jumlah transisi yang disimpan di riwayat
+const MAX_HISTORY: usize = 64;
+
+/// Status sesi
+#[napi]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionStatus {
+    /// Menunggu peer
+    Waiting,
+    /// Sedang connecting
+    Connecting,
+    /// Aktif
+    Active,
+    /// Di-pause
+    Paused,
+    /// Berakhir
+    Ended,
+}
+
+impl SessionStatus {
+    /// Cek apakah transisi ke `to` sah
+    ///
+    /// Active boleh kembali ke Connecting untuk reconnect, Connecting
+    /// boleh kembali ke Waiting jika percobaan gagal. Ended adalah akhir.
+    pub fn can_transition(self, to: SessionStatus) -> bool {
+        use SessionStatus::*;
+        matches!(
+            (self, to),
+            (Waiting, Connecting)
+                | (Connecting, Active)
+                | (Connecting, Waiting)
+                | (Active, Paused)
+                | (Active, Connecting)
+                | (Paused, Active)
+                | (Waiting | Connecting | Active | Paused, Ended)
+        )
+    }
+}
+
+/// Error transisi state sesi
+#[derive(Debug, Error, PartialEq)]
+pub enum SessionStateError {
+    /// Transisi tidak sah dari state saat ini
+    #[error("transisi sesi tidak valid: {from:?} -> {to:?}")]
+    InvalidTransition { from: SessionStatus, to: SessionStatus },
+    /// Sesi sudah berakhir, tidak bisa diubah lagi
+    #[error("sesi sudah berakhir")]
+    AlreadyEnded,
+}
+
+impl From<SessionStateError> for napi::Error {
+    fn from(err: SessionStateError) -> Self {
+        napi::Error::new(napi::Status::GenericFailure, err.to_string())
+    }
+}
+
+/// Satu transisi state sesi
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateTransition {
+    /// State sebelumnya
+    pub from: SessionStatus,
+    /// State baru
+    pub to: SessionStatus,
+    /// Waktu transisi (ms sejak epoch)
+    pub timestamp: f64,
+    /// Waktu sejak sesi dibuat (ms, monotonic)
+    pub elapsed_ms: f64,
+    /// Alasan transisi, jika ada
+    pub reason: Option<String>,
+}
+
+/// Durasi sesi per status (ms)
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionDurations {
+    /// Waktu menunggu peer
+    pub waiting_ms: f64,
+    /// Waktu connecting, termasuk reconnect
+    pub connecting_ms: f64,
+    /// Waktu aktif, dasar durasi sesi yang dihitung
+    pub active_ms: f64,
+    /// Waktu di-pause
+    pub paused_ms: f64,
+    /// Total sejak sesi dibuat sampai berakhir
+    pub total_ms: f64,
+}
+
+/// Event batas waktu sesi
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeLimitEvent {
+    /// Waktu hampir habis
+    Warning { remaining_secs: u32 },
+    /// Batas waktu tercapai, sesi harus diakhiri
+    Expired,
+}
+
+/// Batas durasi sesi beserta peringatan sebelum habis
+#[derive(Debug, Clone)]
+pub struct TimeLimit {
+    max: Duration,
+    deadline: Option<Instant>,
+    warnings: Vec<Duration>,
+    next_warning: usize,
+    expired: bool,
+}
+
+impl TimeLimit {
+    /// Buat batas `max` dengan peringatan saat sisa waktu `warnings`
+    pub fn new(max: Duration, warnings: &[Duration]) -> Self {
+        let mut warnings: Vec<Duration> =
+            warnings.iter().copied().filter(|&warning| warning < max).collect();
+        warnings.sort_unstable_by(|a, b| b.cmp(a));
+        warnings.dedup();
+        Self {
+            max,
+            deadline: None,
+            warnings,
+            next_warning: 0,
+            expired: false,
+        }
+    }
+
+    /// Tambah batas waktu absolut, mis. `expiresAt` token; sesi berakhir
+    /// pada yang lebih dulu tercapai, termasuk saat belum pernah aktif
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Batas durasi
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Sisa waktu setelah sesi berjalan `elapsed`, pada waktu `now`
+    pub fn remaining(&self, elapsed: Duration, now: Instant) -> Duration {
+        let remaining = self.max.saturating_sub(elapsed);
+        match self.deadline {
+            Some(deadline) => remaining.min(deadline.saturating_duration_since(now)),
+            None => remaining,
+        }
+    }
+
+    /// Cek batas setelah sesi berjalan `elapsed`, pada waktu `now`
+    ///
+    /// Peringatan yang terlewat sekaligus (mis. poll telat) digabung
+    /// menjadi satu dengan sisa waktu sebenarnya.
+    pub fn poll(&mut self, elapsed: Duration, now: Instant) -> Option<TimeLimitEvent> {
+        if self.expired {
+            return None;
+        }
+        let remaining = self.remaining(elapsed, now);
+        if remaining.is_zero() {
+            self.expired = true;
+            return Some(TimeLimitEvent::Expired);
+        }
+
+        let mut due = false;
+        while self.next_warning < self.warnings.len()
+            && remaining <= self.warnings[self.next_warning]
+        {
+            self.next_warning += 1;
+            due = true;
+        }
+        due.then(|| TimeLimitEvent::Warning {
+            remaining_secs: remaining.as_secs_f64().ceil() as u32,
+        })
+    }
+}
+
+/// State machine satu sesi
+#[derive(Debug)]
+pub struct SessionStateMachine {
+    status: SessionStatus,
+    history: VecDeque<StateTransition>,
+    created: Instant,
+    changed: Instant,
+    spent: [Duration; 5],
+    started: Option<Instant>,
+    ended: Option<Instant>,
+    time_limit: Option<TimeLimit>,
+}
+
+impl Default for SessionStateMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionStateMachine {
+    /// Buat state machine baru di state `Waiting`
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            status: SessionStatus::Waiting,
+            history: VecDeque::new(),
+            created: now,
+            changed: now,
+            spent: [Duration::ZERO; 5],
+            started: None,
+            ended: None,
+            time_limit: None,
+        }
+    }
+
+    /// State saat ini
+    pub fn status(&self) -> SessionStatus {
+        self.status
+    }
+
+    /// Riwayat transisi, dari yang terlama
+    pub fn history(&self) -> Vec<StateTransition> {
+        self.history.iter().cloned().collect()
+    }
+
+    /// Lama berada di state saat ini (ms)
+    pub fn time_in_state_ms(&self) -> u64 {
+        self.changed.elapsed().as_millis() as u64
+    }
+
+    /// Durasi per status sampai sekarang
+    pub fn durations(&self) -> SessionDurations {
+        self.durations_at(Instant::now())
+    }
+
+    /// Durasi per status sampai `now`; berhenti bertambah setelah Ended
+    pub fn durations_at(&self, now: Instant) -> SessionDurations {
+        let mut spent = self.spent;
+        if self.status != SessionStatus::Ended {
+            spent[self.status as usize] += now.saturating_duration_since(self.changed);
+        }
+        let ms = |status: SessionStatus| spent[status as usize].as_millis() as f64;
+
+        SessionDurations {
+            waiting_ms: ms(SessionStatus::Waiting),
+            connecting_ms: ms(SessionStatus::Connecting),
+            active_ms: ms(SessionStatus::Active),
+            paused_ms: ms(SessionStatus::Paused),
+            total_ms: spent.iter().sum::<Duration>().as_millis() as f64,
+        }
+    }
+
+    /// Waktu sejak pertama `Active` sampai `now` atau sampai berakhir,
+    /// termasuk pause dan reconnect
+    pub fn elapsed_since_start_at(&self, now: Instant) -> Duration {
+        self.started.map_or(Duration::ZERO, |started| {
+            self.ended.unwrap_or(now).saturating_duration_since(started)
+        })
+    }
+
+    /// Pasang batas durasi, menggantikan batas sebelumnya
+    pub fn set_time_limit(&mut self, limit: TimeLimit) {
+        self.time_limit = Some(limit);
+    }
+
+    /// Sisa waktu sebelum batas tercapai
+    pub fn remaining_time_at(&self, now: Instant) -> Option<Duration> {
+        let limit = self.time_limit.as_ref()?;
+        Some(limit.remaining(self.elapsed_since_start_at(now), now))
+    }
+
+    /// Cek batas durasi pada waktu `now`
+    pub fn poll_time_limit_at(&mut self, now: Instant) -> Option<TimeLimitEvent> {
+        if self.status == SessionStatus::Ended {
+            return None;
+        }
+        let elapsed = self.elapsed_since_start_at(now);
+        self.time_limit.as_mut()?.poll(elapsed, now)
+    }
+
+    /// Pindah ke state `to`, tolak jika tidak sah
+    pub fn transition(
+        &mut self,
+        to: SessionStatus,
+        reason: Option<&str>,
+    ) -> Result<StateTransition, SessionStateError> {
+        self.transition_at(to, reason, Instant::now())
+    }
+
+    /// Pindah ke state `to` pada waktu `now`
+    pub fn transition_at(
+        &mut self,
+        to: SessionStatus,
+        reason: Option<&str>,
+        now: Instant,
+    ) -> Result<StateTransition, SessionStateError> {
+        let from = self.status;
+        if from == SessionStatus::Ended {
+            return Err(SessionStateError::AlreadyEnded);
+        }
+        if !from.can_transition(to) {
+            return Err(SessionStateError::InvalidTransition { from, to });
+        }
+
+        let transition = StateTransition {
+            from,
+            to,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as f64,
+            elapsed_ms: now.saturating_duration_since(self.created).as_millis() as f64,
+            reason: reason.map(str::to_string),
+        };
+
+        self.spent[from as usize] += now.saturating_duration_since(self.changed);
+        match to {
+            SessionStatus::Active if self.started.is_none() => self.started = Some(now),
+            SessionStatus::Ended => self.ended = Some(now),
+            _ => {}
+        }
+        self.status = to;
+        self.changed = now;
+        if self.history.len() == MAX_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(transition.clone());
+        Ok(transition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_lifecycle_is_recorded() {
+        let mut machine = SessionStateMachine::new();
+        machine.transition(SessionStatus::Connecting, None).unwrap();
+        machine.transition(SessionStatus::Active, None).unwrap();
+        machine.transition(SessionStatus::Paused, None).unwrap();
+        machine.transition(SessionStatus::Active, None).unwrap();
+        machine.transition(SessionStatus::Ended, Some("closed")).unwrap();
+
+        let history = machine.history();
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[0].from, SessionStatus::Waiting);
+        assert_eq!(history[4].reason.as_deref(), Some("closed"));
+        assert!(history.windows(2).all(|pair| pair[0].elapsed_ms <= pair[1].elapsed_ms));
+    }
+
+    #[test]
+    fn illegal_transitions_are_rejected() {
+        let mut machine = SessionStateMachine::new();
+        assert_eq!(
+            machine.transition(SessionStatus::Paused, None),
+            Err(SessionStateError::InvalidTransition {
+                from: SessionStatus::Waiting,
+                to: SessionStatus::Paused,
+            })
+        );
+        assert_eq!(machine.status(), SessionStatus::Waiting);
+        assert!(machine.history().is_empty());
+
+        machine.transition(SessionStatus::Ended, None).unwrap();
+        assert_eq!(
+            machine.transition(SessionStatus::Connecting, None),
+            Err(SessionStateError::AlreadyEnded)
+        );
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut machine = SessionStateMachine::new();
+        machine.transition(SessionStatus::Connecting, None).unwrap();
+        for _ in 0..MAX_HISTORY {
+            machine.transition(SessionStatus::Active, None).unwrap();
+            machine.transition(SessionStatus::Connecting, Some("reconnect")).unwrap();
+        }
+        let history = machine.history();
+        assert_eq!(history.len(), MAX_HISTORY);
+        assert_eq!(history.last().unwrap().to, SessionStatus::Connecting);
+    }
+
+    #[test]
+    fn durations_exclude_connecting_and_pause() {
+        let mut machine = SessionStateMachine::new();
+        let start = machine.created;
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        machine.transition_at(SessionStatus::Connecting, None, at(5)).unwrap();
+        machine.transition_at(SessionStatus::Active, None, at(8)).unwrap();
+        machine.transition_at(SessionStatus::Paused, None, at(68)).unwrap();
+        machine.transition_at(SessionStatus::Active, None, at(98)).unwrap();
+
+        let durations = machine.durations_at(at(128));
+        assert_eq!(durations.waiting_ms, 5_000.0);
+        assert_eq!(durations.connecting_ms, 3_000.0);
+        assert_eq!(durations.active_ms, 90_000.0);
+        assert_eq!(durations.paused_ms, 30_000.0);
+
+        // Setelah Ended durasi tidak bertambah lagi
+        machine.transition_at(SessionStatus::Ended, None, at(128)).unwrap();
+        let ended = machine.durations_at(at(500));
+        assert_eq!(ended.active_ms, 90_000.0);
+        assert_eq!(ended.total_ms, 128_000.0);
+    }
+
+    #[test]
+    fn time_limit_warns_then_expires() {
+        let mut machine = SessionStateMachine::new();
+        let start = machine.created;
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        machine.set_time_limit(TimeLimit::new(
+            Duration::from_secs(600),
+            &[Duration::from_secs(60), Duration::from_secs(300), Duration::from_secs(900)],
+        ));
+
+        // Waktu connecting tidak dihitung
+        machine.transition_at(SessionStatus::Connecting, None, at(0)).unwrap();
+        machine.transition_at(SessionStatus::Active, None, at(20)).unwrap();
+        assert_eq!(machine.poll_time_limit_at(at(300)), None);
+        assert_eq!(machine.remaining_time_at(at(300)), Some(Duration::from_secs(320)));
+
+        // Pause tetap dihitung
+        machine.transition_at(SessionStatus::Paused, None, at(310)).unwrap();
+        assert_eq!(
+            machine.poll_time_limit_at(at(320)),
+            Some(TimeLimitEvent::Warning { remaining_secs: 300 })
+        );
+        assert_eq!(machine.poll_time_limit_at(at(321)), None);
+
+        // Poll telat, yang dilaporkan sisa waktu sebenarnya
+        assert_eq!(
+            machine.poll_time_limit_at(at(590)),
+            Some(TimeLimitEvent::Warning { remaining_secs: 30 })
+        );
+        assert_eq!(machine.poll_time_limit_at(at(620)), Some(TimeLimitEvent::Expired));
+        assert_eq!(machine.poll_time_limit_at(at(621)), None);
+    }
+
+    #[test]
+    fn deadline_counts_time_before_active() {
+        let mut machine = SessionStateMachine::new();
+        let start = machine.created;
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        machine.set_time_limit(
+            TimeLimit::new(Duration::from_secs(600), &[Duration::from_secs(60)])
+                .with_deadline(at(600)),
+        );
+
+        // Waktu menunggu sebelum aktif ikut dihitung terhadap deadline token
+        machine.transition_at(SessionStatus::Connecting, None, at(0)).unwrap();
+        machine.transition_at(SessionStatus::Active, None, at(200)).unwrap();
+        assert_eq!(machine.remaining_time_at(at(300)), Some(Duration::from_secs(300)));
+        assert_eq!(
+            machine.poll_time_limit_at(at(550)),
+            Some(TimeLimitEvent::Warning { remaining_secs: 50 })
+        );
+        assert_eq!(machine.poll_time_limit_at(at(600)), Some(TimeLimitEvent::Expired));
+    }
+}
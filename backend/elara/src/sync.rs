@@ -0,0 +1,207 @@
+//! Modul Sync ELARA
+//!
+//! Lip sync audio/video di sisi penerima. Timestamp media tiap stream
+//! dipetakan ke wall clock pengirim (dari sender report atau referensi
+//! waktu ELARA), lalu stream yang lebih cepat ditunda agar selisih
+//! audio dan video tetap dalam toleransi.
+
+use napi_derive::napi;
+
+/// Selisih A/V yang masih dianggap sinkron (ms)
+const LIP_SYNC_TOLERANCE_MS: f64 = 20.0;
+
+/// Perubahan delay maksimum per penyesuaian, agar tidak terdengar lompatan
+const MAX_SYNC_STEP_MS: f64 = 80.0;
+
+/// Delay sinkronisasi maksimum per stream (ms)
+const MAX_SYNC_DELAY_MS: f64 = 400.0;
+
+/// Interval minimum antar penyesuaian (ms)
+const SYNC_INTERVAL_MS: u64 = 1000;
+
+/// Jenis stream media
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq)]
+pub enum MediaKind {
+    /// Stream audio
+    Audio,
+    /// Stream video
+    Video,
+}
+
+/// Referensi clock pengirim: wall clock (ms) untuk satu timestamp RTP
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockReference {
+    /// Wall clock pengirim (ms)
+    pub wall_clock_ms: f64,
+    /// Timestamp RTP yang bersesuaian
+    pub rtp_timestamp: u32,
+    /// Clock rate stream
+    pub clock_rate: u32,
+}
+
+impl ClockReference {
+    /// Buat referensi dari timestamp NTP 64 bit sender report
+    pub fn from_ntp(ntp_time: u64, rtp_timestamp: u32, clock_rate: u32) -> Self {
+        let seconds = (ntp_time >> 32) as f64;
+        let fraction = (ntp_time & 0xFFFF_FFFF) as f64 / 4_294_967_296.0;
+        Self {
+            wall_clock_ms: (seconds + fraction) * 1000.0,
+            rtp_timestamp,
+            clock_rate,
+        }
+    }
+
+    /// Wall clock pengirim saat media dengan timestamp ini di-capture
+    pub fn capture_time_ms(&self, timestamp: u32) -> f64 {
+        let diff = timestamp.wrapping_sub(self.rtp_timestamp) as i32 as f64;
+        self.wall_clock_ms + diff * 1000.0 / self.clock_rate as f64
+    }
+}
+
+/// Pengendali lip sync antara stream audio dan video yang diterima
+#[derive(Debug, Default)]
+pub struct LipSync {
+    audio: Option<ClockReference>,
+    video: Option<ClockReference>,
+    audio_delay_ms: f64,
+    video_delay_ms: f64,
+    offset_ms: Option<f64>,
+    last_adjust_ms: Option<u64>,
+}
+
+impl LipSync {
+    /// Buat pengendali baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set referensi clock pengirim untuk satu stream
+    pub fn set_reference(&mut self, kind: MediaKind, reference: ClockReference) {
+        match kind {
+            MediaKind::Audio => self.audio = Some(reference),
+            MediaKind::Video => self.video = Some(reference),
+        }
+    }
+
+    /// Selisih A/V terakhir (ms); positif berarti video tertinggal dari audio
+    pub fn offset_ms(&self) -> Option<f64> {
+        self.offset_ms
+    }
+
+    /// Delay tambahan yang harus diterapkan ke stream (ms)
+    pub fn delay_ms(&self, kind: MediaKind) -> f64 {
+        match kind {
+            MediaKind::Audio => self.audio_delay_ms,
+            MediaKind::Video => self.video_delay_ms,
+        }
+    }
+
+    /// Perbarui dari posisi putar tiap stream: (timestamp RTP, waktu putar lokal)
+    ///
+    /// Selisih end-to-end delay kedua stream dihitung terhadap wall clock
+    /// pengirim yang sama, jadi offset clock antar device saling
+    /// menghapus. Delay stream yang tertinggal dikurangi dulu sebelum
+    /// stream yang lebih cepat ditunda. Mengembalikan `true` jika delay
+    /// berubah.
+    pub fn update(
+        &mut self,
+        now_ms: u64,
+        audio: Option<(u32, f64)>,
+        video: Option<(u32, f64)>,
+    ) -> bool {
+        let (Some(audio_ref), Some(video_ref), Some(audio), Some(video)) =
+            (self.audio, self.video, audio, video)
+        else {
+            self.offset_ms = None;
+            return false;
+        };
+
+        let audio_latency = audio.1 - audio_ref.capture_time_ms(audio.0);
+        let video_latency = video.1 - video_ref.capture_time_ms(video.0);
+        let offset = video_latency - audio_latency;
+        self.offset_ms = Some(offset);
+
+        if offset.abs() <= LIP_SYNC_TOLERANCE_MS {
+            return false;
+        }
+        if let Some(last) = self.last_adjust_ms {
+            if now_ms.saturating_sub(last) < SYNC_INTERVAL_MS {
+                return false;
+            }
+        }
+        self.last_adjust_ms = Some(now_ms);
+
+        let step = offset.abs().min(MAX_SYNC_STEP_MS);
+        let (late, early) = if offset > 0.0 {
+            (&mut self.video_delay_ms, &mut self.audio_delay_ms)
+        } else {
+            (&mut self.audio_delay_ms, &mut self.video_delay_ms)
+        };
+
+        if *late > 0.0 {
+            *late = (*late - step).max(0.0);
+        } else {
+            *early = (*early + step).min(MAX_SYNC_DELAY_MS);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Simulasi: waktu putar = capture + latency + delay sync
+    fn playout(sync: &LipSync, kind: MediaKind, latency: f64) -> f64 {
+        1_000.0 + latency + sync.delay_ms(kind)
+    }
+
+    #[test]
+    fn converges_within_tolerance() {
+        let mut sync = LipSync::new();
+        // Pengirim: audio ts 0 dan video ts 0 di-capture pada wall clock 1000 ms
+        for (kind, clock_rate) in [(MediaKind::Audio, 48000), (MediaKind::Video, 90000)] {
+            let reference = ClockReference {
+                wall_clock_ms: 1000.0,
+                rtp_timestamp: 0,
+                clock_rate,
+            };
+            sync.set_reference(kind, reference);
+        }
+
+        // Video butuh 180 ms lebih lama (jitter buffer + NACK)
+        for step in 0..10u64 {
+            let audio = playout(&sync, MediaKind::Audio, 60.0);
+            let video = playout(&sync, MediaKind::Video, 240.0);
+            sync.update(step * 1000, Some((0, audio)), Some((0, video)));
+        }
+
+        assert!(sync.offset_ms().unwrap().abs() <= LIP_SYNC_TOLERANCE_MS);
+        assert_eq!(sync.delay_ms(MediaKind::Video), 0.0);
+        assert!((sync.delay_ms(MediaKind::Audio) - 180.0).abs() <= LIP_SYNC_TOLERANCE_MS);
+
+        // Video membaik: delay audio dikurangi lagi, video tidak ditunda
+        for step in 10..20u64 {
+            let audio = playout(&sync, MediaKind::Audio, 60.0);
+            let video = playout(&sync, MediaKind::Video, 100.0);
+            sync.update(step * 1000, Some((0, audio)), Some((0, video)));
+        }
+        assert!(sync.offset_ms().unwrap().abs() <= LIP_SYNC_TOLERANCE_MS);
+        assert_eq!(sync.delay_ms(MediaKind::Video), 0.0);
+    }
+
+    #[test]
+    fn capture_time_handles_timestamp_wrap() {
+        let reference = ClockReference::from_ntp(10 << 32, u32::MAX - 47_999, 48000);
+        assert_eq!(reference.wall_clock_ms, 10_000.0);
+        assert_eq!(reference.capture_time_ms(0), 11_000.0);
+    }
+
+    #[test]
+    fn no_offset_without_references() {
+        let mut sync = LipSync::new();
+        assert!(!sync.update(0, Some((0, 0.0)), Some((0, 0.0))));
+        assert_eq!(sync.offset_ms(), None);
+    }
+}
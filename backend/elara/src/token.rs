@@ -0,0 +1,125 @@
+//! Modul Token ELARA
+//!
+//! Verifikasi token yang ditandatangani backend (`generateToken` di
+//! elara.service.ts). Formatnya `base64(data).signature`, dengan
+//! signature HMAC-SHA256 (hex) atas JSON data memakai secret JWT backend.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use thiserror::Error;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Error verifikasi token
+#[derive(Debug, Error, PartialEq)]
+pub enum TokenError {
+    /// Bukan `base64(data).signature` atau data bukan JSON yang dikenal
+    #[error("format token tidak valid")]
+    Malformed,
+    /// Signature tidak cocok dengan secret
+    #[error("signature token tidak valid")]
+    InvalidSignature,
+    /// Token sudah lewat `expiresAt`
+    #[error("token sudah expired")]
+    Expired,
+}
+
+impl From<TokenError> for napi::Error {
+    fn from(err: TokenError) -> Self {
+        napi::Error::new(napi::Status::InvalidArg, err.to_string())
+    }
+}
+
+/// Tanda tangani data dengan HMAC-SHA256, hasil dalam hex
+pub fn sign_token_data(data: &[u8], secret: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC menerima key apa pun");
+    mac.update(data);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Verifikasi `base64(data).signature` dan kembalikan data aslinya
+pub fn verify_signed_data(token: &str, secret: &str) -> Result<Vec<u8>, TokenError> {
+    let (data, signature) = token.split_once('.').ok_or(TokenError::Malformed)?;
+    let data = STANDARD.decode(data).map_err(|_| TokenError::Malformed)?;
+    let signature = hex::decode(signature).map_err(|_| TokenError::InvalidSignature)?;
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC menerima key apa pun");
+    mac.update(&data);
+    // Perbandingan constant-time
+    mac.verify_slice(&signature)
+        .map_err(|_| TokenError::InvalidSignature)?;
+    Ok(data)
+}
+
+/// Isi token sesi dari backend
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionToken {
+    /// ID sesi
+    pub session_id: String,
+    /// ID user pemilik token
+    pub user_id: String,
+    /// Waktu kedaluwarsa (detik sejak epoch)
+    pub expires_at: u64,
+}
+
+impl SessionToken {
+    /// Verifikasi token dan cek kedaluwarsa terhadap `now_secs`
+    pub fn verify(token: &str, secret: &str, now_secs: u64) -> Result<Self, TokenError> {
+        let data = verify_signed_data(token, secret)?;
+        let parsed: Self = serde_json::from_slice(&data).map_err(|_| TokenError::Malformed)?;
+        if parsed.expires_at < now_secs {
+            return Err(TokenError::Expired);
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Token dari `generateToken` backend dengan secret "secret"
+    const BACKEND_TOKEN: &str = "eyJzZXNzaW9uSWQiOiJhYmMxMjMiLCJ1c2VySWQiOiJ1c2VyLTEiLCJleHBpcmVzQXQiOjE3MDAwMDM2MDAsIm5vbmNlIjoiMDBmZiJ9.5048ccfdf2d98490a577e15eb88007e1041b6f451bad96f656fdd9ea206a3dd5";
+
+    #[test]
+    fn accepts_backend_token() {
+        let token = SessionToken::verify(BACKEND_TOKEN, "secret", 1_700_000_000).unwrap();
+        assert_eq!(token.session_id, "abc123");
+        assert_eq!(token.user_id, "user-1");
+        assert_eq!(token.expires_at, 1_700_003_600);
+    }
+
+    #[test]
+    fn rejects_forged_token() {
+        assert_eq!(
+            SessionToken::verify(BACKEND_TOKEN, "other", 1_700_000_000),
+            Err(TokenError::InvalidSignature)
+        );
+
+        // Data diganti tapi signature lama dipakai ulang
+        let data = STANDARD.encode(br#"{"sessionId":"abc123","userId":"user-1","expiresAt":1900000000}"#);
+        let signature = BACKEND_TOKEN.split_once('.').unwrap().1;
+        let forged = format!("{}.{}", data, signature);
+        assert_eq!(
+            SessionToken::verify(&forged, "secret", 1_700_000_000),
+            Err(TokenError::InvalidSignature)
+        );
+        assert_eq!(SessionToken::verify("bukan-token", "secret", 0), Err(TokenError::Malformed));
+    }
+
+    #[test]
+    fn rejects_expired_token() {
+        assert_eq!(
+            SessionToken::verify(BACKEND_TOKEN, "secret", 1_700_003_601),
+            Err(TokenError::Expired)
+        );
+
+        let data = br#"{"sessionId":"s","userId":"u","expiresAt":10}"#;
+        let token = format!("{}.{}", STANDARD.encode(data), sign_token_data(data, "k"));
+        assert_eq!(SessionToken::verify(&token, "k", 10).unwrap().session_id, "s");
+    }
+}
@@ -0,0 +1,613 @@
+//! Modul Transfer ELARA
+//!
+//! Kirim foto/file ke teman baru selama panggilan. File dipecah menjadi
+//! chunk di atas data channel khusus (reliable, berurutan), diverifikasi
+//! dengan SHA-256 di sisi penerima, dan bisa dilanjutkan dari offset
+//! terakhir setelah koneksi sempat putus. Kedua sisi boleh membatalkan.
+
+use std::collections::{HashMap, VecDeque};
+
+use napi_derive::napi;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Data channel untuk transfer file
+pub const FILE_TRANSFER_CHANNEL: &str = "file-transfer";
+
+/// Ukuran file maksimum (bytes)
+pub const MAX_FILE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Ukuran data per chunk, di bawah batas pesan data channel
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Chunk baru hanya diantrekan jika data channel menahan kurang dari ini,
+/// agar chat di channel lain tidak ikut tertahan
+const MAX_QUEUED_BYTES: usize = 256 * 1024;
+
+/// Progress dilaporkan tiap kelipatan porsi ini
+const PROGRESS_STEP: f64 = 0.05;
+
+const MAX_NAME_LEN: usize = 255;
+
+const KIND_OFFER: u8 = 0;
+const KIND_ACCEPT: u8 = 1;
+const KIND_CHUNK: u8 = 2;
+const KIND_COMPLETE: u8 = 3;
+const KIND_CANCEL: u8 = 4;
+
+/// Error transfer file
+#[derive(Debug, Error, PartialEq)]
+pub enum TransferError {
+    /// File melebihi `MAX_FILE_SIZE`
+    #[error("file terlalu besar: {size} byte, maksimum {max}")]
+    TooLarge { size: usize, max: usize },
+    /// Nama atau tipe file terlalu panjang
+    #[error("nama/tipe file maksimum {MAX_NAME_LEN} byte")]
+    NameTooLong,
+    /// Transfer tidak ada atau sudah selesai
+    #[error("transfer tidak dikenal: {0}")]
+    UnknownTransfer(u32),
+}
+
+impl From<TransferError> for napi::Error {
+    fn from(err: TransferError) -> Self {
+        napi::Error::new(napi::Status::InvalidArg, err.to_string())
+    }
+}
+
+/// Alasan transfer dibatalkan
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CancelReason {
+    /// Dibatalkan salah satu pengguna
+    Cancelled = 0,
+    /// Ukuran melebihi batas penerima
+    TooLarge = 1,
+    /// Hash tidak cocok, data rusak
+    HashMismatch = 2,
+}
+
+impl CancelReason {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => CancelReason::TooLarge,
+            2 => CancelReason::HashMismatch,
+            _ => CancelReason::Cancelled,
+        }
+    }
+
+    /// Nama alasan untuk event JS
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CancelReason::Cancelled => "cancelled",
+            CancelReason::TooLarge => "too_large",
+            CancelReason::HashMismatch => "hash_mismatch",
+        }
+    }
+}
+
+/// Pesan protokol transfer, dikirim sebagai pesan binary data channel
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferMessage {
+    /// Pengirim menawarkan file
+    Offer {
+        id: u32,
+        size: u32,
+        sha256: [u8; 32],
+        name: String,
+        mime_type: String,
+    },
+    /// Penerima meminta data mulai `offset` (0 untuk awal, >0 untuk lanjut)
+    Accept { id: u32, offset: u32 },
+    /// Potongan file
+    Chunk { id: u32, offset: u32, data: Vec<u8> },
+    /// Penerima selesai dan hash cocok
+    Complete { id: u32 },
+    /// Salah satu sisi membatalkan; `from_sender` membedakan arah transfer
+    Cancel {
+        id: u32,
+        from_sender: bool,
+        reason: CancelReason,
+    },
+}
+
+impl TransferMessage {
+    /// Parse pesan, `None` jika rusak
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let read_u32 = |at: usize| -> Option<u32> {
+            Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+        };
+        let id = read_u32(1)?;
+
+        match *data.first()? {
+            KIND_OFFER => {
+                let size = read_u32(5)?;
+                let sha256 = data.get(9..41)?.try_into().ok()?;
+                let name_len = *data.get(41)? as usize;
+                let name = String::from_utf8(data.get(42..42 + name_len)?.to_vec()).ok()?;
+                let mime_at = 42 + name_len;
+                let mime_len = *data.get(mime_at)? as usize;
+                let mime_type =
+                    String::from_utf8(data.get(mime_at + 1..mime_at + 1 + mime_len)?.to_vec()).ok()?;
+                Some(TransferMessage::Offer { id, size, sha256, name, mime_type })
+            }
+            KIND_ACCEPT => Some(TransferMessage::Accept { id, offset: read_u32(5)? }),
+            KIND_CHUNK => Some(TransferMessage::Chunk {
+                id,
+                offset: read_u32(5)?,
+                data: data[9..].to_vec(),
+            }),
+            KIND_COMPLETE => Some(TransferMessage::Complete { id }),
+            KIND_CANCEL => Some(TransferMessage::Cancel {
+                id,
+                from_sender: *data.get(5)? != 0,
+                reason: CancelReason::from_u8(*data.get(6)?),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Serialisasi ke bytes
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            TransferMessage::Offer { id, size, sha256, name, mime_type } => {
+                buf.push(KIND_OFFER);
+                buf.extend_from_slice(&id.to_be_bytes());
+                buf.extend_from_slice(&size.to_be_bytes());
+                buf.extend_from_slice(sha256);
+                buf.push(name.len() as u8);
+                buf.extend_from_slice(name.as_bytes());
+                buf.push(mime_type.len() as u8);
+                buf.extend_from_slice(mime_type.as_bytes());
+            }
+            TransferMessage::Accept { id, offset } => {
+                buf.push(KIND_ACCEPT);
+                buf.extend_from_slice(&id.to_be_bytes());
+                buf.extend_from_slice(&offset.to_be_bytes());
+            }
+            TransferMessage::Chunk { id, offset, data } => {
+                buf.reserve(9 + data.len());
+                buf.push(KIND_CHUNK);
+                buf.extend_from_slice(&id.to_be_bytes());
+                buf.extend_from_slice(&offset.to_be_bytes());
+                buf.extend_from_slice(data);
+            }
+            TransferMessage::Complete { id } => {
+                buf.push(KIND_COMPLETE);
+                buf.extend_from_slice(&id.to_be_bytes());
+            }
+            TransferMessage::Cancel { id, from_sender, reason } => {
+                buf.push(KIND_CANCEL);
+                buf.extend_from_slice(&id.to_be_bytes());
+                buf.push(*from_sender as u8);
+                buf.push(*reason as u8);
+            }
+        }
+        buf
+    }
+}
+
+/// Info transfer file untuk event JS
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileTransferInfo {
+    /// ID transfer
+    pub id: u32,
+    /// Nama file
+    pub name: String,
+    /// Tipe MIME
+    pub mime_type: String,
+    /// Ukuran file (bytes)
+    pub size: u32,
+    /// Bytes yang sudah terkirim/diterima
+    pub transferred: u32,
+    /// `true` untuk file dari peer
+    pub incoming: bool,
+}
+
+/// Event transfer file
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferEvent {
+    /// Peer menawarkan file, terima dengan `accept`
+    Offered(FileTransferInfo),
+    /// Progress kirim/terima
+    Progress(FileTransferInfo),
+    /// File dari peer lengkap dan hash cocok
+    Received(FileTransferInfo, Vec<u8>),
+    /// File terkirim dan dikonfirmasi peer
+    Sent(FileTransferInfo),
+    /// Transfer dibatalkan
+    Cancelled(FileTransferInfo, CancelReason),
+}
+
+struct Outgoing {
+    info: FileTransferInfo,
+    data: Vec<u8>,
+    /// Offset chunk berikutnya, `None` sampai penerima menerima offer
+    offset: Option<usize>,
+    reported: f64,
+}
+
+struct Incoming {
+    info: FileTransferInfo,
+    sha256: [u8; 32],
+    accepted: bool,
+    data: Vec<u8>,
+    hasher: Sha256,
+    reported: f64,
+}
+
+/// Cek apakah progress melewati kelipatan `PROGRESS_STEP` berikutnya
+fn progress_due(info: &FileTransferInfo, reported: &mut f64) -> bool {
+    let progress = info.transferred as f64 / info.size.max(1) as f64;
+    if progress >= 1.0 || progress - *reported >= PROGRESS_STEP {
+        *reported = progress;
+        return true;
+    }
+    false
+}
+
+/// Transfer file satu sesi, di kedua arah
+pub struct FileTransfers {
+    outgoing: HashMap<u32, Outgoing>,
+    incoming: HashMap<u32, Incoming>,
+    pending: VecDeque<TransferMessage>,
+}
+
+impl Default for FileTransfers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileTransfers {
+    /// Buat pengelola transfer kosong
+    pub fn new() -> Self {
+        Self {
+            outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Tawarkan file ke peer, kembalikan ID transfer
+    pub fn send_file(&mut self, name: &str, mime_type: &str, data: Vec<u8>) -> Result<u32, TransferError> {
+        if data.len() > MAX_FILE_SIZE {
+            return Err(TransferError::TooLarge { size: data.len(), max: MAX_FILE_SIZE });
+        }
+        if name.len() > MAX_NAME_LEN || mime_type.len() > MAX_NAME_LEN {
+            return Err(TransferError::NameTooLong);
+        }
+
+        let mut id = Uuid::new_v4().as_u128() as u32;
+        while self.outgoing.contains_key(&id) {
+            id = id.wrapping_add(1);
+        }
+        let info = FileTransferInfo {
+            id,
+            name: name.to_string(),
+            mime_type: mime_type.to_string(),
+            size: data.len() as u32,
+            transferred: 0,
+            incoming: false,
+        };
+        self.pending.push_back(TransferMessage::Offer {
+            id,
+            size: info.size,
+            sha256: Sha256::digest(&data).into(),
+            name: info.name.clone(),
+            mime_type: info.mime_type.clone(),
+        });
+        self.outgoing.insert(id, Outgoing { info, data, offset: None, reported: 0.0 });
+        Ok(id)
+    }
+
+    /// Terima file yang ditawarkan peer
+    ///
+    /// File kosong tidak punya chunk, jadi langsung diverifikasi dan
+    /// selesai di sini.
+    pub fn accept(&mut self, id: u32) -> Result<Vec<TransferEvent>, TransferError> {
+        let incoming = self.incoming.get_mut(&id).ok_or(TransferError::UnknownTransfer(id))?;
+        if incoming.accepted {
+            return Ok(Vec::new());
+        }
+        incoming.accepted = true;
+        if incoming.info.size == 0 {
+            return Ok(self.on_chunk(id, 0, &[]));
+        }
+        self.pending.push_back(TransferMessage::Accept { id, offset: 0 });
+        Ok(Vec::new())
+    }
+
+    /// Lanjutkan transfer masuk dari offset terakhir, mis. setelah reconnect
+    pub fn resume(&mut self) {
+        for incoming in self.incoming.values().filter(|incoming| incoming.accepted) {
+            self.pending.push_back(TransferMessage::Accept {
+                id: incoming.info.id,
+                offset: incoming.data.len() as u32,
+            });
+        }
+    }
+
+    /// Batalkan transfer di arah mana pun
+    pub fn cancel(&mut self, id: u32) -> Option<TransferEvent> {
+        let (info, from_sender) = match self.outgoing.remove(&id) {
+            Some(outgoing) => (outgoing.info, true),
+            None => (self.incoming.remove(&id)?.info, false),
+        };
+        self.pending.push_back(TransferMessage::Cancel {
+            id,
+            from_sender,
+            reason: CancelReason::Cancelled,
+        });
+        Some(TransferEvent::Cancelled(info, CancelReason::Cancelled))
+    }
+
+    /// Proses pesan dari channel transfer peer
+    pub fn handle_message(&mut self, data: &[u8]) -> Vec<TransferEvent> {
+        let Some(message) = TransferMessage::parse(data) else {
+            return Vec::new();
+        };
+
+        match message {
+            TransferMessage::Offer { id, size, sha256, name, mime_type } => {
+                let info = FileTransferInfo {
+                    id,
+                    name,
+                    mime_type,
+                    size,
+                    transferred: 0,
+                    incoming: true,
+                };
+                if size as usize > MAX_FILE_SIZE {
+                    self.pending.push_back(TransferMessage::Cancel {
+                        id,
+                        from_sender: false,
+                        reason: CancelReason::TooLarge,
+                    });
+                    return vec![TransferEvent::Cancelled(info, CancelReason::TooLarge)];
+                }
+                self.incoming.insert(
+                    id,
+                    Incoming {
+                        info: info.clone(),
+                        sha256,
+                        accepted: false,
+                        data: Vec::new(),
+                        hasher: Sha256::new(),
+                        reported: 0.0,
+                    },
+                );
+                vec![TransferEvent::Offered(info)]
+            }
+            TransferMessage::Accept { id, offset } => {
+                if let Some(outgoing) = self.outgoing.get_mut(&id) {
+                    // Lanjut dari data terakhir yang dimiliki penerima
+                    outgoing.offset = Some((offset as usize).min(outgoing.data.len()));
+                }
+                Vec::new()
+            }
+            TransferMessage::Chunk { id, offset, data } => self.on_chunk(id, offset as usize, &data),
+            TransferMessage::Complete { id } => match self.outgoing.remove(&id) {
+                Some(mut outgoing) => {
+                    outgoing.info.transferred = outgoing.info.size;
+                    vec![TransferEvent::Sent(outgoing.info)]
+                }
+                None => Vec::new(),
+            },
+            TransferMessage::Cancel { id, from_sender, reason } => {
+                // Pengirim peer membatalkan transfer masuk kita, dan sebaliknya
+                let info = if from_sender {
+                    self.incoming.remove(&id).map(|incoming| incoming.info)
+                } else {
+                    self.outgoing.remove(&id).map(|outgoing| outgoing.info)
+                };
+                info.map(|info| vec![TransferEvent::Cancelled(info, reason)])
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    fn on_chunk(&mut self, id: u32, offset: usize, chunk: &[u8]) -> Vec<TransferEvent> {
+        let Some(incoming) = self.incoming.get_mut(&id).filter(|incoming| incoming.accepted) else {
+            return Vec::new();
+        };
+
+        let received = incoming.data.len();
+        if offset > received {
+            // Ada data yang hilang (channel sempat di-reset), minta ulang
+            self.pending.push_back(TransferMessage::Accept { id, offset: received as u32 });
+            return Vec::new();
+        }
+        // Bagian yang sudah dimiliki (kiriman ulang setelah resume) dilewati
+        let fresh = &chunk[(received - offset).min(chunk.len())..];
+        if received + fresh.len() > incoming.info.size as usize {
+            let info = self.incoming.remove(&id).unwrap().info;
+            self.pending.push_back(TransferMessage::Cancel {
+                id,
+                from_sender: false,
+                reason: CancelReason::TooLarge,
+            });
+            return vec![TransferEvent::Cancelled(info, CancelReason::TooLarge)];
+        }
+        incoming.hasher.update(fresh);
+        incoming.data.extend_from_slice(fresh);
+        incoming.info.transferred = incoming.data.len() as u32;
+
+        let mut events = Vec::new();
+        if progress_due(&incoming.info, &mut incoming.reported) {
+            events.push(TransferEvent::Progress(incoming.info.clone()));
+        }
+        if incoming.info.transferred < incoming.info.size {
+            return events;
+        }
+
+        let incoming = self.incoming.remove(&id).unwrap();
+        if incoming.hasher.finalize().as_slice() != incoming.sha256 {
+            self.pending.push_back(TransferMessage::Cancel {
+                id,
+                from_sender: false,
+                reason: CancelReason::HashMismatch,
+            });
+            events.push(TransferEvent::Cancelled(incoming.info, CancelReason::HashMismatch));
+            return events;
+        }
+        self.pending.push_back(TransferMessage::Complete { id });
+        events.push(TransferEvent::Received(incoming.info, incoming.data));
+        events
+    }
+
+    /// Pesan yang perlu dikirim di channel transfer beserta event progress
+    ///
+    /// `channel_buffered` adalah bytes yang masih tertahan di channel
+    /// transfer; chunk baru hanya diambil selama masih di bawah batas.
+    pub fn poll(&mut self, channel_buffered: usize) -> (Vec<Vec<u8>>, Vec<TransferEvent>) {
+        let mut messages: Vec<Vec<u8>> = self.pending.drain(..).map(|message| message.serialize()).collect();
+        let mut events = Vec::new();
+        let mut queued = channel_buffered;
+
+        let mut ids: Vec<u32> = self.outgoing.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let outgoing = self.outgoing.get_mut(&id).unwrap();
+            let Some(mut offset) = outgoing.offset else {
+                continue;
+            };
+            while offset < outgoing.data.len() && queued < MAX_QUEUED_BYTES {
+                let end = (offset + CHUNK_SIZE).min(outgoing.data.len());
+                let message = TransferMessage::Chunk {
+                    id,
+                    offset: offset as u32,
+                    data: outgoing.data[offset..end].to_vec(),
+                }
+                .serialize();
+                queued += message.len();
+                messages.push(message);
+                offset = end;
+            }
+            outgoing.offset = Some(offset);
+            outgoing.info.transferred = offset as u32;
+            if progress_due(&outgoing.info, &mut outgoing.reported) {
+                events.push(TransferEvent::Progress(outgoing.info.clone()));
+            }
+        }
+        (messages, events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Jalankan pertukaran pesan sampai tidak ada lagi yang dikirim
+    fn run(
+        alice: &mut FileTransfers,
+        bob: &mut FileTransfers,
+        drop: impl Fn(&[u8]) -> bool,
+    ) -> (Vec<TransferEvent>, Vec<TransferEvent>) {
+        let (mut alice_events, mut bob_events) = (Vec::new(), Vec::new());
+        for _ in 0..1000 {
+            let (to_bob, events) = alice.poll(0);
+            alice_events.extend(events);
+            let (to_alice, events) = bob.poll(0);
+            bob_events.extend(events);
+            if to_bob.is_empty() && to_alice.is_empty() {
+                break;
+            }
+            for message in to_bob.iter().filter(|message| !drop(message)) {
+                bob_events.extend(bob.handle_message(message));
+            }
+            for message in &to_alice {
+                alice_events.extend(alice.handle_message(message));
+            }
+        }
+        (alice_events, bob_events)
+    }
+
+    fn photo() -> Vec<u8> {
+        (0..100_000u32).map(|i| (i * 7) as u8).collect()
+    }
+
+    #[test]
+    fn photo_is_transferred_and_verified() {
+        let (mut alice, mut bob) = (FileTransfers::new(), FileTransfers::new());
+        let id = alice.send_file("foto.jpg", "image/jpeg", photo()).unwrap();
+
+        let (_, bob_events) = run(&mut alice, &mut bob, |_| false);
+        let TransferEvent::Offered(offer) = &bob_events[0] else {
+            panic!("offer tidak diterima");
+        };
+        assert_eq!((offer.id, offer.size, offer.name.as_str()), (id, 100_000, "foto.jpg"));
+
+        bob.accept(id).unwrap();
+        let (alice_events, bob_events) = run(&mut alice, &mut bob, |_| false);
+        assert!(matches!(alice_events.last(), Some(TransferEvent::Sent(info)) if info.transferred == 100_000));
+        assert!(bob_events.iter().any(|event| matches!(event, TransferEvent::Progress(_))));
+        assert!(matches!(bob_events.last(), Some(TransferEvent::Received(_, data)) if *data == photo()));
+    }
+
+    #[test]
+    fn transfer_resumes_after_disconnect() {
+        let (mut alice, mut bob) = (FileTransfers::new(), FileTransfers::new());
+        let id = alice.send_file("foto.jpg", "image/jpeg", photo()).unwrap();
+        run(&mut alice, &mut bob, |_| false);
+        bob.accept(id).unwrap();
+
+        // Koneksi putus setelah chunk kedua, sisa kiriman hilang
+        let offset = |message: &[u8]| match TransferMessage::parse(message) {
+            Some(TransferMessage::Chunk { offset, .. }) => offset as usize,
+            _ => 0,
+        };
+        run(&mut alice, &mut bob, |message| offset(message) >= 2 * CHUNK_SIZE);
+        assert_eq!(bob.incoming[&id].data.len(), 2 * CHUNK_SIZE);
+
+        bob.resume();
+        let (_, bob_events) = run(&mut alice, &mut bob, |_| false);
+        assert!(matches!(bob_events.last(), Some(TransferEvent::Received(_, data)) if *data == photo()));
+    }
+
+    #[test]
+    fn empty_file_completes_on_accept() {
+        let (mut alice, mut bob) = (FileTransfers::new(), FileTransfers::new());
+        let id = alice.send_file("kosong.txt", "text/plain", Vec::new()).unwrap();
+        run(&mut alice, &mut bob, |_| false);
+
+        let events = bob.accept(id).unwrap();
+        assert!(matches!(&events[..], [TransferEvent::Received(info, data)] if info.size == 0 && data.is_empty()));
+        let (alice_events, _) = run(&mut alice, &mut bob, |_| false);
+        assert!(matches!(&alice_events[..], [TransferEvent::Sent(info)] if info.id == id));
+        assert!(alice.outgoing.is_empty() && bob.incoming.is_empty());
+    }
+
+    #[test]
+    fn cancel_caps_and_corruption() {
+        let (mut alice, mut bob) = (FileTransfers::new(), FileTransfers::new());
+        assert_eq!(
+            alice.send_file("besar.mp4", "video/mp4", vec![0; MAX_FILE_SIZE + 1]),
+            Err(TransferError::TooLarge { size: MAX_FILE_SIZE + 1, max: MAX_FILE_SIZE })
+        );
+
+        // Penerima membatalkan, pengirim ikut tahu
+        let id = alice.send_file("a.png", "image/png", vec![1; 1000]).unwrap();
+        run(&mut alice, &mut bob, |_| false);
+        assert!(matches!(bob.cancel(id), Some(TransferEvent::Cancelled(_, CancelReason::Cancelled))));
+        let (alice_events, _) = run(&mut alice, &mut bob, |_| false);
+        assert!(matches!(alice_events[..], [TransferEvent::Cancelled(_, CancelReason::Cancelled)]));
+        assert_eq!(alice.accept(id), Err(TransferError::UnknownTransfer(id)));
+
+        // Data rusak di jalan ditolak lewat hash
+        let id = alice.send_file("b.png", "image/png", vec![2; 1000]).unwrap();
+        run(&mut alice, &mut bob, |_| false);
+        bob.accept(id).unwrap();
+        let (to_alice, _) = bob.poll(0);
+        alice.handle_message(&to_alice[0]);
+        let (to_bob, _) = alice.poll(0);
+        let mut corrupted = to_bob[0].clone();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        let events = bob.handle_message(&corrupted);
+        assert!(matches!(events.last(), Some(TransferEvent::Cancelled(_, CancelReason::HashMismatch))));
+        let (alice_events, _) = run(&mut alice, &mut bob, |_| false);
+        assert!(matches!(alice_events.last(), Some(TransferEvent::Cancelled(_, CancelReason::HashMismatch))));
+    }
+}
@@ -7,6 +7,11 @@
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::{MtuMessage, PathMtuDiscovery, DEFAULT_MTU};
 
 /// Tipe transport
 #[napi]
@@ -63,6 +68,24 @@ pub struct TransportManager {
     turn_servers: Vec<String>,
     current_transport: TransportType,
     local_candidates: Vec<IceCandidate>,
+    mtu: PathMtuDiscovery,
+    path_mtu: Arc<AtomicU32>,
+    clock: Instant,
+}
+
+impl TransportManager {
+    /// Handle MTU bersama untuk `MediaEngine.bind_transport`
+    pub(crate) fn path_mtu_handle(&self) -> Arc<AtomicU32> {
+        self.path_mtu.clone()
+    }
+
+    /// Mulai ulang MTU discovery setelah jalur berganti
+    ///
+    /// Jalur TCP tidak mengenal fragmentasi IP, jadi tidak perlu probe.
+    fn reset_path_mtu(&mut self) {
+        self.mtu.reset(self.current_transport != TransportType::TcpFallback);
+        self.path_mtu.store(self.mtu.mtu() as u32, Ordering::Relaxed);
+    }
 }
 
 #[napi]
@@ -75,6 +98,9 @@ impl TransportManager {
             turn_servers,
             current_transport: TransportType::Direct,
             local_candidates: Vec::new(),
+            mtu: PathMtuDiscovery::new(),
+            path_mtu: Arc::new(AtomicU32::new(DEFAULT_MTU as u32)),
+            clock: Instant::now(),
         }
     }
 
@@ -161,9 +187,44 @@ impl TransportManager {
         
         // Untuk sekarang, assume direct connection berhasil
         self.current_transport = TransportType::Direct;
+        self.reset_path_mtu();
         Ok(self.current_transport.clone())
     }
 
+    /// Ambil probe MTU yang perlu dikirim lewat jalur terpilih, jika ada
+    ///
+    /// Kirim dengan DF (don't fragment) aktif dan panggil berkala; probe
+    /// yang hilang diulang lalu ukurannya dianggap tidak lolos.
+    #[napi]
+    pub fn poll_mtu_probe(&mut self) -> Option<Buffer> {
+        let now = self.clock.elapsed().as_millis() as u64;
+        self.mtu.poll(now).map(Buffer::from)
+    }
+
+    /// Proses paket probe/ack MTU dari peer
+    ///
+    /// Mengembalikan ack yang harus dikirim balik untuk probe peer.
+    /// Paket yang bukan probe MTU diabaikan.
+    #[napi]
+    pub fn handle_mtu_packet(&mut self, packet: Buffer) -> Option<Buffer> {
+        let now = self.clock.elapsed().as_millis() as u64;
+        match MtuMessage::parse(&packet)? {
+            MtuMessage::Probe { id, size } => Some(MtuMessage::Ack { id, size }.serialize().into()),
+            MtuMessage::Ack { id, size } => {
+                if self.mtu.on_ack(id, size, now) {
+                    self.path_mtu.store(self.mtu.mtu() as u32, Ordering::Relaxed);
+                }
+                None
+            }
+        }
+    }
+
+    /// Path MTU terkonfirmasi untuk paket media (bytes)
+    #[napi]
+    pub fn get_path_mtu(&self) -> u32 {
+        self.path_mtu.load(Ordering::Relaxed)
+    }
+
     /// Dapatkan statistik transport
     #[napi]
     pub fn get_stats(&self) -> TransportStats {
@@ -0,0 +1,638 @@
+//! Modul VP8/VP9 ELARA
+//!
+//! Payload format RTP untuk VP8 (RFC 7741) dan VP9 (RFC 9628), termasuk
+//! picture ID, deteksi keyframe dan indeks temporal/spatial layer.
+//! Dipakai untuk device yang encoder H.264 hardware-nya bermasalah.
+
+use crate::rtp::{Depayloader, FrameInfo, PacketError, Payloader, RtpPacket};
+
+const PICTURE_ID_MASK: u16 = 0x7FFF;
+
+/// Cek keyframe VP8 dari byte pertama frame (bit P = 0 untuk keyframe)
+pub fn is_vp8_keyframe(frame: &[u8]) -> bool {
+    frame.first().map(|b| b & 0x01 == 0).unwrap_or(false)
+}
+
+/// Cek keyframe VP9 dari uncompressed header
+pub fn is_vp9_keyframe(frame: &[u8]) -> bool {
+    let Some(&byte) = frame.first() else {
+        return false;
+    };
+
+    // frame_marker harus 0b10
+    if byte >> 6 != 0b10 {
+        return false;
+    }
+
+    let profile = ((byte >> 5) & 1) | (((byte >> 4) & 1) << 1);
+    // Profile 3 punya satu bit reserved tambahan
+    let shift = if profile == 3 { 1 } else { 0 };
+    let show_existing_frame = (byte >> (3 - shift)) & 1;
+    let frame_type = (byte >> (2 - shift)) & 1;
+
+    show_existing_frame == 0 && frame_type == 0
+}
+
+/// Penyusun frame dari paket berurutan dengan bit awal/akhir frame
+#[derive(Default)]
+struct VpxAssembler {
+    frame: Vec<u8>,
+    in_progress: bool,
+    corrupted: bool,
+    last_sequence: Option<u16>,
+    lost_frames: u64,
+}
+
+impl VpxAssembler {
+    fn check_sequence(&mut self, sequence_number: u16) {
+        if let Some(last) = self.last_sequence {
+            if sequence_number != last.wrapping_add(1) {
+                self.corrupted = true;
+            }
+        }
+        self.last_sequence = Some(sequence_number);
+    }
+
+    fn start(&mut self) {
+        if self.in_progress {
+            // Akhir frame sebelumnya tidak pernah datang
+            self.lost_frames += 1;
+        }
+        self.frame.clear();
+        self.in_progress = true;
+        self.corrupted = false;
+    }
+
+    fn append(&mut self, data: &[u8]) {
+        if !self.in_progress {
+            // Awal frame hilang, buang sampai frame berikutnya
+            self.in_progress = true;
+            self.corrupted = true;
+        }
+        self.frame.extend_from_slice(data);
+    }
+
+    fn finish(&mut self, timestamp: u32) -> Result<Option<Vec<u8>>, PacketError> {
+        let frame = std::mem::take(&mut self.frame);
+        let corrupted = self.corrupted;
+        self.in_progress = false;
+        self.corrupted = false;
+
+        if corrupted {
+            self.lost_frames += 1;
+            Err(PacketError::IncompleteFrame { timestamp })
+        } else {
+            Ok(Some(frame))
+        }
+    }
+
+    fn reset(&mut self) {
+        self.frame.clear();
+        self.in_progress = false;
+        self.corrupted = false;
+    }
+}
+
+fn truncated(needed: usize, actual: usize) -> PacketError {
+    PacketError::Truncated { needed, actual }
+}
+
+/// Tulis picture ID 15-bit (bit M = 1)
+fn push_picture_id(out: &mut Vec<u8>, picture_id: u16) {
+    out.push(0x80 | ((picture_id >> 8) as u8 & 0x7F));
+    out.push(picture_id as u8);
+}
+
+/// Baca picture ID 7 atau 15 bit, kembalikan (picture_id, panjang)
+fn read_picture_id(data: &[u8], offset: usize) -> Result<(u16, usize), PacketError> {
+    let first = *data.get(offset).ok_or_else(|| truncated(offset + 1, data.len()))?;
+    if first & 0x80 != 0 {
+        let second = *data.get(offset + 1).ok_or_else(|| truncated(offset + 2, data.len()))?;
+        Ok(((((first & 0x7F) as u16) << 8) | second as u16, 2))
+    } else {
+        Ok((first as u16, 1))
+    }
+}
+
+/// Payloader VP8
+#[derive(Default)]
+pub struct Vp8Payloader {
+    picture_id: u16,
+    tl0_pic_idx: u8,
+    layer: Option<FrameInfo>,
+}
+
+impl Vp8Payloader {
+    /// Buat payloader baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Payloader for Vp8Payloader {
+    fn payload(&mut self, frame: &[u8], max_payload: usize) -> Result<Vec<Vec<u8>>, PacketError> {
+        let temporal = self.layer.as_ref().and_then(|info| info.temporal_layer);
+        if temporal == Some(0) {
+            self.tl0_pic_idx = self.tl0_pic_idx.wrapping_add(1);
+        }
+
+        // X + extension byte + picture ID 15-bit (+ TL0PICIDX + TID)
+        let mut descriptor = vec![0x80, 0x80];
+        push_picture_id(&mut descriptor, self.picture_id);
+        if let Some(tid) = temporal {
+            descriptor[1] |= 0x60;
+            descriptor.push(self.tl0_pic_idx);
+            let layer_sync = self.layer.as_ref().map(|info| info.layer_sync).unwrap_or(false);
+            descriptor.push(((tid & 0x03) << 6) | ((layer_sync as u8) << 5));
+        }
+
+        if max_payload <= descriptor.len() {
+            return Err(PacketError::MtuTooSmall(max_payload));
+        }
+
+        let out = frame
+            .chunks(max_payload - descriptor.len())
+            .enumerate()
+            .map(|(i, chunk)| {
+                let mut payload = descriptor.clone();
+                if i == 0 {
+                    // S = 1, partition index 0
+                    payload[0] |= 0x10;
+                }
+                payload.extend_from_slice(chunk);
+                payload
+            })
+            .collect();
+
+        self.picture_id = self.picture_id.wrapping_add(1) & PICTURE_ID_MASK;
+        Ok(out)
+    }
+
+    fn set_frame_info(&mut self, info: &FrameInfo) {
+        self.layer = Some(info.clone());
+    }
+}
+
+/// Descriptor VP8 hasil parse
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vp8Descriptor {
+    /// Frame non-referensi (boleh dibuang)
+    pub non_reference: bool,
+    /// Awal partisi
+    pub start_of_partition: bool,
+    /// Indeks partisi
+    pub partition_index: u8,
+    /// Picture ID
+    pub picture_id: Option<u16>,
+    /// TL0PICIDX
+    pub tl0_pic_idx: Option<u8>,
+    /// Indeks temporal layer
+    pub temporal_layer: Option<u8>,
+    /// Layer sync
+    pub layer_sync: bool,
+    /// Panjang descriptor dalam byte
+    pub len: usize,
+}
+
+impl Vp8Descriptor {
+    /// Parse descriptor dari awal payload
+    pub fn parse(payload: &[u8]) -> Result<Self, PacketError> {
+        let first = *payload.first().ok_or_else(|| truncated(1, 0))?;
+        let mut descriptor = Vp8Descriptor {
+            non_reference: first & 0x20 != 0,
+            start_of_partition: first & 0x10 != 0,
+            partition_index: first & 0x0F,
+            ..Default::default()
+        };
+        let mut offset = 1;
+
+        if first & 0x80 != 0 {
+            let ext = *payload.get(offset).ok_or_else(|| truncated(offset + 1, payload.len()))?;
+            offset += 1;
+
+            if ext & 0x80 != 0 {
+                let (picture_id, len) = read_picture_id(payload, offset)?;
+                descriptor.picture_id = Some(picture_id);
+                offset += len;
+            }
+            if ext & 0x40 != 0 {
+                let idx = *payload.get(offset).ok_or_else(|| truncated(offset + 1, payload.len()))?;
+                descriptor.tl0_pic_idx = Some(idx);
+                offset += 1;
+            }
+            if ext & 0x30 != 0 {
+                let byte = *payload.get(offset).ok_or_else(|| truncated(offset + 1, payload.len()))?;
+                if ext & 0x20 != 0 {
+                    descriptor.temporal_layer = Some(byte >> 6);
+                    descriptor.layer_sync = byte & 0x20 != 0;
+                }
+                offset += 1;
+            }
+        }
+
+        descriptor.len = offset;
+        Ok(descriptor)
+    }
+}
+
+/// Depayloader VP8
+#[derive(Default)]
+pub struct Vp8Depayloader {
+    assembler: VpxAssembler,
+    current: Vp8Descriptor,
+    last_info: FrameInfo,
+}
+
+impl Vp8Depayloader {
+    /// Buat depayloader baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Depayloader for Vp8Depayloader {
+    fn push(&mut self, packet: &RtpPacket) -> Result<Option<Vec<u8>>, PacketError> {
+        self.assembler.check_sequence(packet.sequence_number);
+
+        let descriptor = Vp8Descriptor::parse(&packet.payload)?;
+        if descriptor.start_of_partition && descriptor.partition_index == 0 {
+            self.assembler.start();
+            self.current = descriptor.clone();
+        }
+        self.assembler.append(&packet.payload[descriptor.len..]);
+
+        if !packet.marker {
+            return Ok(None);
+        }
+
+        let frame = self.assembler.finish(packet.timestamp)?;
+        if let Some(frame) = &frame {
+            self.last_info = FrameInfo {
+                keyframe: is_vp8_keyframe(frame),
+                picture_id: self.current.picture_id,
+                temporal_layer: self.current.temporal_layer,
+                spatial_layer: None,
+                layer_sync: self.current.layer_sync,
+            };
+        }
+        Ok(frame)
+    }
+
+    fn reset(&mut self) {
+        self.assembler.reset();
+    }
+
+    fn lost_frames(&self) -> u64 {
+        self.assembler.lost_frames
+    }
+
+    fn last_frame_info(&self) -> FrameInfo {
+        self.last_info.clone()
+    }
+}
+
+const VP9_I: u8 = 0x80;
+const VP9_P: u8 = 0x40;
+const VP9_L: u8 = 0x20;
+const VP9_F: u8 = 0x10;
+const VP9_B: u8 = 0x08;
+const VP9_E: u8 = 0x04;
+const VP9_V: u8 = 0x02;
+
+/// Payloader VP9 (non-flexible mode)
+#[derive(Default)]
+pub struct Vp9Payloader {
+    picture_id: u16,
+    tl0_pic_idx: u8,
+    layer: Option<FrameInfo>,
+    started: bool,
+}
+
+impl Vp9Payloader {
+    /// Buat payloader baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Payloader for Vp9Payloader {
+    fn payload(&mut self, frame: &[u8], max_payload: usize) -> Result<Vec<Vec<u8>>, PacketError> {
+        let keyframe = is_vp9_keyframe(frame);
+        let layer = self.layer.clone().unwrap_or_default();
+        let has_layers = layer.temporal_layer.is_some() || layer.spatial_layer.is_some();
+        let temporal = layer.temporal_layer.unwrap_or(0);
+        let spatial = layer.spatial_layer.unwrap_or(0);
+
+        // Spatial layer dari picture yang sama berbagi picture ID
+        if spatial == 0 {
+            if self.started {
+                self.picture_id = self.picture_id.wrapping_add(1) & PICTURE_ID_MASK;
+            }
+            if has_layers && temporal == 0 {
+                self.tl0_pic_idx = self.tl0_pic_idx.wrapping_add(1);
+            }
+        }
+        self.started = true;
+
+        let mut descriptor = vec![VP9_I];
+        if !keyframe {
+            descriptor[0] |= VP9_P;
+        }
+        push_picture_id(&mut descriptor, self.picture_id);
+        if has_layers {
+            descriptor[0] |= VP9_L;
+            descriptor.push(((temporal & 0x07) << 5) | ((layer.layer_sync as u8) << 4) | ((spatial & 0x07) << 1));
+            descriptor.push(self.tl0_pic_idx);
+        }
+
+        // Scalability structure minimal di keyframe: satu spatial layer
+        let ss: &[u8] = if keyframe && spatial == 0 { &[0x00] } else { &[] };
+
+        if max_payload <= descriptor.len() + ss.len() {
+            return Err(PacketError::MtuTooSmall(max_payload));
+        }
+
+        let first_chunk = max_payload - descriptor.len() - ss.len();
+        let rest_chunk = max_payload - descriptor.len();
+        let mut chunks = vec![&frame[..first_chunk.min(frame.len())]];
+        chunks.extend(frame[first_chunk.min(frame.len())..].chunks(rest_chunk));
+        let count = chunks.len();
+
+        let out = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let mut payload = descriptor.clone();
+                if i == 0 {
+                    payload[0] |= VP9_B;
+                    if !ss.is_empty() {
+                        payload[0] |= VP9_V;
+                        payload.extend_from_slice(ss);
+                    }
+                }
+                if i + 1 == count {
+                    payload[0] |= VP9_E;
+                }
+                payload.extend_from_slice(chunk);
+                payload
+            })
+            .collect();
+
+        Ok(out)
+    }
+
+    fn set_frame_info(&mut self, info: &FrameInfo) {
+        self.layer = Some(info.clone());
+    }
+}
+
+/// Descriptor VP9 hasil parse
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vp9Descriptor {
+    /// Frame inter-predicted (bukan keyframe)
+    pub inter_predicted: bool,
+    /// Flexible mode
+    pub flexible: bool,
+    /// Awal frame
+    pub start_of_frame: bool,
+    /// Akhir frame
+    pub end_of_frame: bool,
+    /// Picture ID
+    pub picture_id: Option<u16>,
+    /// Indeks temporal layer
+    pub temporal_layer: Option<u8>,
+    /// Indeks spatial layer
+    pub spatial_layer: Option<u8>,
+    /// Switching up point
+    pub layer_sync: bool,
+    /// TL0PICIDX (non-flexible mode)
+    pub tl0_pic_idx: Option<u8>,
+    /// Panjang descriptor dalam byte
+    pub len: usize,
+}
+
+impl Vp9Descriptor {
+    /// Parse descriptor dari awal payload
+    pub fn parse(payload: &[u8]) -> Result<Self, PacketError> {
+        let first = *payload.first().ok_or_else(|| truncated(1, 0))?;
+        let mut descriptor = Vp9Descriptor {
+            inter_predicted: first & VP9_P != 0,
+            flexible: first & VP9_F != 0,
+            start_of_frame: first & VP9_B != 0,
+            end_of_frame: first & VP9_E != 0,
+            ..Default::default()
+        };
+        let mut offset = 1;
+        let byte_at = |offset: usize| {
+            payload
+                .get(offset)
+                .copied()
+                .ok_or_else(|| truncated(offset + 1, payload.len()))
+        };
+
+        if first & VP9_I != 0 {
+            let (picture_id, len) = read_picture_id(payload, offset)?;
+            descriptor.picture_id = Some(picture_id);
+            offset += len;
+        }
+
+        if first & VP9_L != 0 {
+            let layers = byte_at(offset)?;
+            descriptor.temporal_layer = Some(layers >> 5);
+            descriptor.layer_sync = layers & 0x10 != 0;
+            descriptor.spatial_layer = Some((layers >> 1) & 0x07);
+            offset += 1;
+
+            if !descriptor.flexible {
+                descriptor.tl0_pic_idx = Some(byte_at(offset)?);
+                offset += 1;
+            }
+        }
+
+        if descriptor.flexible && descriptor.inter_predicted {
+            // Hingga 3 P_DIFF, bit N menandai masih ada lanjutan
+            for _ in 0..3 {
+                let p_diff = byte_at(offset)?;
+                offset += 1;
+                if p_diff & 0x01 == 0 {
+                    break;
+                }
+            }
+        }
+
+        if first & VP9_V != 0 {
+            let ss = byte_at(offset)?;
+            offset += 1;
+            let spatial_layers = ((ss >> 5) + 1) as usize;
+
+            if ss & 0x10 != 0 {
+                // WIDTH dan HEIGHT 16-bit per spatial layer
+                offset += spatial_layers * 4;
+            }
+            if ss & 0x08 != 0 {
+                let groups = byte_at(offset)?;
+                offset += 1;
+                for _ in 0..groups {
+                    let group = byte_at(offset)?;
+                    offset += 1 + ((group >> 2) & 0x03) as usize;
+                }
+            }
+            if offset > payload.len() {
+                return Err(truncated(offset, payload.len()));
+            }
+        }
+
+        descriptor.len = offset;
+        Ok(descriptor)
+    }
+}
+
+/// Depayloader VP9 - mengembalikan satu frame per spatial layer
+#[derive(Default)]
+pub struct Vp9Depayloader {
+    assembler: VpxAssembler,
+    current: Vp9Descriptor,
+    last_info: FrameInfo,
+}
+
+impl Vp9Depayloader {
+    /// Buat depayloader baru
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Depayloader for Vp9Depayloader {
+    fn push(&mut self, packet: &RtpPacket) -> Result<Option<Vec<u8>>, PacketError> {
+        self.assembler.check_sequence(packet.sequence_number);
+
+        let descriptor = Vp9Descriptor::parse(&packet.payload)?;
+        if descriptor.start_of_frame {
+            self.assembler.start();
+            self.current = descriptor.clone();
+        }
+        self.assembler.append(&packet.payload[descriptor.len..]);
+
+        if !descriptor.end_of_frame {
+            return Ok(None);
+        }
+
+        let frame = self.assembler.finish(packet.timestamp)?;
+        if let Some(frame) = &frame {
+            self.last_info = FrameInfo {
+                keyframe: !self.current.inter_predicted && is_vp9_keyframe(frame),
+                picture_id: self.current.picture_id,
+                temporal_layer: self.current.temporal_layer,
+                spatial_layer: self.current.spatial_layer,
+                layer_sync: self.current.layer_sync,
+            };
+        }
+        Ok(frame)
+    }
+
+    fn reset(&mut self) {
+        self.assembler.reset();
+    }
+
+    fn lost_frames(&self) -> u64 {
+        self.assembler.lost_frames
+    }
+
+    fn last_frame_info(&self) -> FrameInfo {
+        self.last_info.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtp::Packetizer;
+
+    /// Frame VP8 dengan frame tag asli (keyframe: start code 9d 01 2a)
+    fn vp8_frame(keyframe: bool, len: usize) -> Vec<u8> {
+        let mut frame = if keyframe {
+            vec![0x50, 0x42, 0x00, 0x9d, 0x01, 0x2a, 0x40, 0x01, 0xf0, 0x00]
+        } else {
+            vec![0x51, 0x12, 0x00]
+        };
+        frame.extend((0..len - frame.len()).map(|i| (i * 7) as u8));
+        frame
+    }
+
+    /// Frame VP9 profile 0 dengan uncompressed header asli
+    fn vp9_frame(keyframe: bool, len: usize) -> Vec<u8> {
+        let mut frame = if keyframe {
+            vec![0x82, 0x49, 0x83, 0x42, 0x00]
+        } else {
+            vec![0x86, 0x00, 0x40]
+        };
+        frame.extend((0..len - frame.len()).map(|i| (i * 13) as u8));
+        frame
+    }
+
+    #[test]
+    fn keyframe_detection() {
+        assert!(is_vp8_keyframe(&vp8_frame(true, 100)));
+        assert!(!is_vp8_keyframe(&vp8_frame(false, 100)));
+        assert!(is_vp9_keyframe(&vp9_frame(true, 100)));
+        assert!(!is_vp9_keyframe(&vp9_frame(false, 100)));
+    }
+
+    #[test]
+    fn vp8_round_trip_with_temporal_layers() {
+        let mut packetizer = Packetizer::new(96, Box::new(Vp8Payloader::new()));
+        packetizer.set_mtu(500);
+        let mut depayloader = Vp8Depayloader::new();
+
+        for (i, tid) in [0u8, 2, 1, 2].iter().enumerate() {
+            packetizer.set_frame_info(&FrameInfo {
+                temporal_layer: Some(*tid),
+                layer_sync: *tid > 0,
+                ..Default::default()
+            });
+            let frame = vp8_frame(i == 0, 1800);
+            let packets = packetizer.packetize(&frame, i as u32 * 3000).unwrap();
+            assert!(packets.len() > 1);
+
+            let mut output = None;
+            for packet in &packets {
+                let parsed = RtpPacket::parse(&packet.serialize()).unwrap();
+                output = depayloader.push(&parsed).unwrap();
+            }
+
+            assert_eq!(output, Some(frame));
+            let info = depayloader.last_frame_info();
+            assert_eq!(info.keyframe, i == 0);
+            assert_eq!(info.picture_id, Some(i as u16));
+            assert_eq!(info.temporal_layer, Some(*tid));
+        }
+    }
+
+    #[test]
+    fn vp9_round_trip_and_loss() {
+        let mut packetizer = Packetizer::new(98, Box::new(Vp9Payloader::new()));
+        packetizer.set_mtu(400);
+        let mut depayloader = Vp9Depayloader::new();
+
+        let key = vp9_frame(true, 1500);
+        let packets = packetizer.packetize(&key, 0).unwrap();
+        let descriptor = Vp9Descriptor::parse(&packets[0].payload).unwrap();
+        assert!(descriptor.start_of_frame && !descriptor.inter_predicted);
+
+        let mut output = None;
+        for packet in &packets {
+            output = depayloader.push(packet).unwrap();
+        }
+        assert_eq!(output, Some(key));
+        assert!(depayloader.last_frame_info().keyframe);
+
+        let mut packets = packetizer.packetize(&vp9_frame(false, 1500), 3000).unwrap();
+        packets.remove(1);
+        let results: Vec<_> = packets.iter().map(|p| depayloader.push(p)).collect();
+        assert_eq!(
+            results.last().unwrap(),
+            &Err(PacketError::IncompleteFrame { timestamp: 3000 })
+        );
+        assert_eq!(depayloader.lost_frames(), 1);
+    }
+}
@@ -0,0 +1,161 @@
+#!/usr/bin/env python3
+"""Encoder H.264 minimal untuk fixture test depayloader.
+
+Menghasilkan stream Annex-B Baseline 32x32 (CAVLC): AUD + SPS + PPS + IDR
+(4 macroblock I_PCM), P dengan semua macroblock skip, dan P dengan tiga
+macroblock skip plus satu I_PCM. Semua syntax element mengikuti ITU-T H.264
+7.3, sehingga stream bisa didekode decoder mana pun.
+
+    python3 gen_h264_fixture.py > h264_baseline_32x32.h264
+"""
+
+import sys
+
+MB_COLS = MB_ROWS = 2
+
+
+class Bits:
+    def __init__(self):
+        self.bits = []
+
+    def u(self, n, value):
+        self.bits += [(value >> (n - 1 - i)) & 1 for i in range(n)]
+
+    def ue(self, value):
+        value += 1
+        n = value.bit_length()
+        self.u(n - 1, 0)
+        self.u(n, value)
+
+    def se(self, value):
+        self.ue(2 * value - 1 if value > 0 else -2 * value)
+
+    def align_zero(self):
+        while len(self.bits) % 8:
+            self.bits.append(0)
+
+    def trailing(self):
+        self.bits.append(1)
+        self.align_zero()
+
+    def bytes(self):
+        assert len(self.bits) % 8 == 0
+        return bytes(
+            int("".join(map(str, self.bits[i:i + 8])), 2) for i in range(0, len(self.bits), 8)
+        )
+
+
+def nal(header, rbsp):
+    # Emulation prevention (7.4.1)
+    out, zeros = bytearray([header]), 0
+    for byte in rbsp:
+        if zeros >= 2 and byte <= 3:
+            out.append(3)
+            zeros = 0
+        out.append(byte)
+        zeros = zeros + 1 if byte == 0 else 0
+    return b"\x00\x00\x00\x01" + bytes(out)
+
+
+def sps():
+    b = Bits()
+    b.u(8, 66)          # profile_idc: Baseline
+    b.u(8, 0xC0)        # constraint_set0/1 (constrained baseline)
+    b.u(8, 10)          # level_idc 1.0
+    b.ue(0)             # seq_parameter_set_id
+    b.ue(0)             # log2_max_frame_num_minus4
+    b.ue(2)             # pic_order_cnt_type
+    b.ue(1)             # max_num_ref_frames
+    b.u(1, 0)           # gaps_in_frame_num_value_allowed_flag
+    b.ue(MB_COLS - 1)   # pic_width_in_mbs_minus1
+    b.ue(MB_ROWS - 1)   # pic_height_in_map_units_minus1
+    b.u(1, 1)           # frame_mbs_only_flag
+    b.u(1, 1)           # direct_8x8_inference_flag
+    b.u(1, 0)           # frame_cropping_flag
+    b.u(1, 0)           # vui_parameters_present_flag
+    b.trailing()
+    return nal(0x67, b.bytes())
+
+
+def pps():
+    b = Bits()
+    b.ue(0)             # pic_parameter_set_id
+    b.ue(0)             # seq_parameter_set_id
+    b.u(1, 0)           # entropy_coding_mode_flag (CAVLC)
+    b.u(1, 0)           # bottom_field_pic_order_in_frame_present_flag
+    b.ue(0)             # num_slice_groups_minus1
+    b.ue(0)             # num_ref_idx_l0_default_active_minus1
+    b.ue(0)             # num_ref_idx_l1_default_active_minus1
+    b.u(1, 0)           # weighted_pred_flag
+    b.u(2, 0)           # weighted_bipred_idc
+    b.se(0)             # pic_init_qp_minus26
+    b.se(0)             # pic_init_qs_minus26
+    b.se(0)             # chroma_qp_index_offset
+    b.u(1, 1)           # deblocking_filter_control_present_flag
+    b.u(1, 0)           # constrained_intra_pred_flag
+    b.u(1, 0)           # redundant_pic_cnt_present_flag
+    b.trailing()
+    return nal(0x68, b.bytes())
+
+
+def pcm_macroblock(b, mb_type, seed):
+    b.ue(mb_type)       # I_PCM
+    b.align_zero()      # pcm_alignment_zero_bit
+    for i in range(256 + 2 * 64):
+        # Sampel 16..235 (tidak pernah nol, jadi tanpa emulation prevention)
+        b.u(8, 16 + (seed * 37 + i * 7) % 220)
+
+
+def slice_header(b, idr, frame_num):
+    b.ue(0)                     # first_mb_in_slice
+    b.ue(7 if idr else 5)       # slice_type: I / P (semua slice sama)
+    b.ue(0)                     # pic_parameter_set_id
+    b.u(4, frame_num)           # frame_num
+    if idr:
+        b.ue(0)                 # idr_pic_id
+    else:
+        b.u(1, 0)               # num_ref_idx_active_override_flag
+        b.u(1, 0)               # ref_pic_list_modification_flag_l0
+    if idr:
+        b.u(1, 0)               # no_output_of_prior_pics_flag
+        b.u(1, 0)               # long_term_reference_flag
+    else:
+        b.u(1, 0)               # adaptive_ref_pic_marking_mode_flag
+    b.se(0)                     # slice_qp_delta
+    b.ue(1)                     # disable_deblocking_filter_idc
+
+
+def idr_slice():
+    b = Bits()
+    slice_header(b, True, 0)
+    for mb in range(MB_COLS * MB_ROWS):
+        pcm_macroblock(b, 25, mb)
+    b.trailing()
+    return nal(0x65, b.bytes())
+
+
+def p_slice(frame_num, coded_last_mb):
+    b = Bits()
+    slice_header(b, False, frame_num)
+    mbs = MB_COLS * MB_ROWS
+    if coded_last_mb:
+        b.ue(mbs - 1)           # mb_skip_run
+        pcm_macroblock(b, 5 + 25, frame_num)
+    else:
+        b.ue(mbs)               # mb_skip_run: seluruh frame di-skip
+    b.trailing()
+    return nal(0x41, b.bytes())
+
+
+def main():
+    aud = b"\x00\x00\x00\x01\x09\xf0"   # primary_pic_type 7
+    frames = [
+        aud + sps() + pps() + idr_slice(),
+        aud + p_slice(1, False),
+        aud + p_slice(2, True),
+    ]
+    sys.stdout.buffer.write(b"".join(frames))
+
+
+if __name__ == "__main__":
+    main()
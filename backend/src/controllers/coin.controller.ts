@@ -82,6 +82,9 @@ export class CoinController {
       if (error.code === 'INVALID_RECEIVER') {
         return errors.badRequest(reply, 'Invalid receiver');
       }
+      if (error.code === 'INVALID_GIFT') {
+        return errors.badRequest(reply, 'Invalid gift');
+      }
       return errors.internal(reply);
     }
   }
@@ -1,6 +1,7 @@
 import { prisma } from '../config/database';
 import { createLogger } from '../utils/logger';
 import { TrustScoreService } from './trust-score.service';
+import { generateGiftReceipt } from './elara.service';
 import { v4 as uuidv4 } from 'uuid';
 
 const logger = createLogger('CoinService');
@@ -14,13 +15,15 @@ const COIN_PACKAGES = {
   premium: { id: 'premium', coins: 5000, price: 39.99 },
 };
 
-// Gift types
-const GIFT_TYPES = {
+// Gift types - must match GiftPanel.tsx and GIFT_CATALOG in the elara crate,
+// which verifies signed gift receipts against these IDs and costs
+const GIFT_TYPES: Record<string, { name: string; cost: number }> = {
+  wave: { name: 'Wave', cost: 5 },
   heart: { name: 'Heart', cost: 10 },
   star: { name: 'Star', cost: 25 },
-  flower: { name: 'Flower', cost: 50 },
-  crown: { name: 'Crown', cost: 100 },
-  diamond: { name: 'Diamond', cost: 500 },
+  gift: { name: 'Gift Box', cost: 50 },
+  diamond: { name: 'Diamond', cost: 100 },
+  rocket: { name: 'Rocket', cost: 500 },
 };
 
 interface SendGiftData {
@@ -84,6 +87,12 @@ export class CoinService {
   async sendGift(data: SendGiftData) {
     const { senderId, sessionId, receiverId, giftType, coinAmount } = data;
 
+    // Verify gift type and amount (multiples allowed for combos)
+    const gift = GIFT_TYPES[giftType];
+    if (!gift || !Number.isInteger(coinAmount) || coinAmount <= 0 || coinAmount % gift.cost !== 0) {
+      throw { code: 'INVALID_GIFT' };
+    }
+
     // Verify session exists and both users are in it
     const session = await prisma.session.findUnique({
       where: { id: sessionId },
@@ -110,6 +119,7 @@ export class CoinService {
 
     // Process gift transaction
     const receiverAmount = Math.floor(coinAmount * 0.7); // 70% to receiver
+    const giftId = uuidv4();
 
     await prisma.$transaction([
       // Deduct from sender
@@ -131,7 +141,7 @@ export class CoinService {
       // Create gift record
       prisma.gift.create({
         data: {
-          id: uuidv4(),
+          id: giftId,
           sessionId,
           senderId,
           receiverId,
@@ -163,11 +173,23 @@ export class CoinService {
 
     logger.info(`Gift sent: ${senderId} -> ${receiverId}, ${giftType}, ${coinAmount} coins`);
 
+    // Signed receipt so the receiving peer can verify the gift in-call
+    const receipt = generateGiftReceipt({
+      receiptId: giftId,
+      senderId,
+      receiverId,
+      sessionId,
+      giftId: giftType,
+      coins: coinAmount,
+    });
+
     return {
       giftType,
       coinAmount,
       receiverAmount,
       newBalance: sender.coinBalance - coinAmount,
+      receiptId: giftId,
+      receipt,
     };
   }
 
@@ -42,14 +42,72 @@ export interface ElaraToken {
   expiresAt: number;
 }
 
-export interface SignalingMessage {
-  type: 'offer' | 'answer' | 'candidate' | 'ready' | 'bye';
+export interface GiftReceiptData {
+  receiptId: string;
+  senderId: string;
+  receiverId: string;
+  sessionId: string;
+  giftId: string;
+  coins: number;
+}
+
+// Payload signaling, sama dengan modul signaling di crate elara
+
+export interface IceCredentials {
+  ufrag: string;
+  pwd: string;
+}
+
+export interface DtlsFingerprint {
+  algorithm: 'sha-256' | 'sha-384' | 'sha-512';
+  value: string;
+}
+
+export interface SignalingCandidate {
+  foundation: string;
+  component: number;
+  protocol: 'udp' | 'tcp';
+  priority: number;
+  address: string;
+  port: number;
+  candidateType: 'host' | 'srflx' | 'prflx' | 'relay';
+}
+
+export interface SessionDescription {
+  ice: IceCredentials;
+  fingerprint: DtlsFingerprint;
+  codecs: {
+    video: Array<{
+      codec: 'h264' | 'h265' | 'vp8' | 'vp9' | 'av1';
+      payloadType: number;
+      profile?: string | null;
+      hardwareAccelerated: boolean;
+    }>;
+    audio: Array<{
+      codec: 'opus' | 'aac';
+      payloadType: number;
+      clockRate: number;
+      channels: number;
+      fec: boolean;
+      dtx: boolean;
+    }>;
+  };
+  candidates?: SignalingCandidate[];
+}
+
+interface SignalingEnvelope {
   sessionId: string;
   from: string;
   to: string;
-  payload: any;
 }
 
+export type SignalingMessage = SignalingEnvelope & (
+  | { type: 'offer' | 'answer'; payload: SessionDescription }
+  | { type: 'candidate'; payload: SignalingCandidate }
+  | { type: 'ready'; payload?: null }
+  | { type: 'bye'; payload?: { reason?: string } | null }
+);
+
 // Konfigurasi STUN/TURN server
 const ICE_SERVERS = [
   { urls: 'stun:stun.l.google.com:19302' },
@@ -158,6 +216,11 @@ export function verifyToken(token: string): { valid: boolean; data?: any; error?
     
     const parsed = JSON.parse(data);
     
+    // Receipt gift memakai key yang sama, jangan diterima sebagai token
+    if (typeof parsed.expiresAt !== 'number') {
+      return { valid: false, error: 'Format token tidak valid' };
+    }
+    
     // Cek expired
     if (parsed.expiresAt < Math.floor(Date.now() / 1000)) {
       return { valid: false, error: 'Token sudah expired' };
@@ -169,6 +232,26 @@ export function verifyToken(token: string): { valid: boolean; data?: any; error?
   }
 }
 
+/**
+ * Generate receipt gift yang ditandatangani
+ * 
+ * Dibawa pengirim lewat protokol in-call dan diverifikasi penerima
+ * sebelum animasi gift ditampilkan. Format sama dengan token sesi.
+ */
+export function generateGiftReceipt(receipt: GiftReceiptData): string {
+  const data = JSON.stringify({
+    type: 'gift-receipt',
+    ...receipt,
+    timestamp: Date.now(),
+  });
+  
+  const hmac = crypto.createHmac('sha256', config.jwt.secret);
+  hmac.update(data);
+  const signature = hmac.digest('hex');
+  
+  return Buffer.from(data).toString('base64') + '.' + signature;
+}
+
 /**
  * Dapatkan sesi berdasarkan ID
  */
@@ -315,6 +398,7 @@ export default {
   createSession,
   generateToken,
   verifyToken,
+  generateGiftReceipt,
   getSession,
   getUserSession,
   updateSessionStatus,
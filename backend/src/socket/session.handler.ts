@@ -128,6 +128,12 @@ export class SessionHandler {
         newBalance: result.newBalance,
       });
 
+      // Receipt for the in-call gift message
+      this.socket.emit('session:gift-receipt', {
+        receiptId: result.receiptId,
+        receipt: result.receipt,
+      });
+
       logger.info(`Gift sent in session ${sessionId}: ${giftType} from ${this.userId}`);
     } catch (error: any) {
       this.socket.emit('session:gift-error', {